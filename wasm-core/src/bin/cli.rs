@@ -58,7 +58,7 @@ fn main() {
                 
                 // Write tile
                 fs::write(&tile_path, &tile.data)
-                    .expect(&format!("Failed to save tile: {}", tile.path));
+                    .unwrap_or_else(|_| panic!("Failed to save tile: {}", tile.path));
                 
                 println!("  ✓ {}", tile.path);
             }
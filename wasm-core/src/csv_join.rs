@@ -0,0 +1,243 @@
+// CSV attribute join module
+// Merge columns of a sidecar CSV into feature properties, keyed by an id column
+
+use crate::diagnostics::Diagnostics;
+use crate::geojson_parser::Feature;
+use crate::ConverterError;
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Number of unmatched CSV keys listed in the diagnostics warning
+const MAX_REPORTED_KEYS: usize = 10;
+
+/// Join CSV columns onto features where `properties[feature_key]` equals the row's `csv_key`
+///
+/// Keys are compared as text, so a numeric property `1` matches the CSV value `"1"`.
+/// CSV values are typed (integers and decimals become numbers, `true`/`false` booleans,
+/// empty cells null) and overwrite existing properties of the same name; the key column
+/// itself is not copied. Cells with a leading zero, a leading `+` or an exponent stay
+/// text, so codes such as `01100` keep their digits. A key given on two rows is an error. Features without a matching row keep their properties unchanged.
+/// Rows that match no feature are reported as a warning in the returned diagnostics.
+pub fn join_attributes(
+    features: &mut [Feature],
+    csv_bytes: &[u8],
+    feature_key: &str,
+    csv_key: &str,
+) -> Result<Diagnostics, ConverterError> {
+    let text = crate::geojson_parser::decode_text(csv_bytes, "CSV")?;
+    let mut records = parse_csv(text)?.into_iter();
+    
+    let header = records
+        .next()
+        .ok_or_else(|| ConverterError::EmptyInput("CSV is empty".to_string()))?;
+    let key_column = header
+        .iter()
+        .position(|name| name == csv_key)
+        .ok_or_else(|| ConverterError::InvalidInput(format!("CSV has no column {:?}", csv_key)))?;
+    
+    // Key -> (row properties, matched)
+    let mut rows: HashMap<String, (serde_json::Map<String, Value>, bool)> = HashMap::new();
+    let mut row_order = Vec::new();
+    for (line, record) in records.enumerate() {
+        if record.len() != header.len() {
+            return Err(ConverterError::Parse(format!(
+                "CSV row {} has {} columns, expected {}",
+                line + 2,
+                record.len(),
+                header.len()
+            )));
+        }
+        
+        let mut properties = serde_json::Map::new();
+        for (column, (name, value)) in header.iter().zip(&record).enumerate() {
+            if column != key_column {
+                properties.insert(name.clone(), typed_value(value));
+            }
+        }
+        
+        let key = record[key_column].clone();
+        if rows.contains_key(&key) {
+            return Err(ConverterError::InvalidInput(format!(
+                "CSV row {} repeats the key {:?}",
+                line + 2,
+                key
+            )));
+        }
+        row_order.push(key.clone());
+        rows.insert(key, (properties, false));
+    }
+    
+    for feature in features.iter_mut() {
+        let key = match feature.properties.get(feature_key) {
+            Some(Value::String(s)) => s.clone(),
+            Some(Value::Number(n)) => n.to_string(),
+            Some(Value::Bool(b)) => b.to_string(),
+            _ => continue,
+        };
+        if let Some((properties, matched)) = rows.get_mut(&key) {
+            *matched = true;
+            for (name, value) in properties.iter() {
+                feature.properties.insert(name.clone(), value.clone());
+            }
+        }
+    }
+    
+    let mut diagnostics = Diagnostics::default();
+    let unmatched: Vec<&String> = row_order.iter().filter(|key| !rows[*key].1).collect();
+    if !unmatched.is_empty() {
+        let listed: Vec<&str> = unmatched.iter().take(MAX_REPORTED_KEYS).map(|key| key.as_str()).collect();
+        diagnostics.warnings.push(format!(
+            "{} CSV row(s) did not match any feature: {}{}",
+            unmatched.len(),
+            listed.join(", "),
+            if unmatched.len() > MAX_REPORTED_KEYS { ", ..." } else { "" }
+        ));
+    }
+    
+    Ok(diagnostics)
+}
+
+/// Convert a CSV cell to a typed JSON value
+/// 
+/// Cells that look like identifiers rather than numbers (`01100`, `+81`, `1e5`) are kept
+/// as strings, since converting them would change their text.
+fn typed_value(cell: &str) -> Value {
+    let digits = cell.strip_prefix('-').unwrap_or(cell);
+    let leading_zero = digits.len() > 1 && digits.starts_with('0') && !digits[1..].starts_with('.');
+    let id_like = leading_zero || cell.starts_with('+') || cell.contains(['e', 'E']);
+    let number = || {
+        if let Ok(i) = cell.parse::<i64>() {
+            return Some(Value::from(i));
+        }
+        cell.parse::<f64>().ok().filter(|f| f.is_finite()).and_then(serde_json::Number::from_f64).map(Value::Number)
+    };
+    
+    if cell.is_empty() {
+        Value::Null
+    } else if let Some(value) = number().filter(|_| !id_like) {
+        value
+    } else if cell == "true" || cell == "false" {
+        Value::Bool(cell == "true")
+    } else {
+        Value::String(cell.to_string())
+    }
+}
+
+/// Parse CSV text (RFC 4180: quoted fields, doubled quotes, CRLF or LF line endings)
+fn parse_csv(text: &str) -> Result<Vec<Vec<String>>, ConverterError> {
+    let mut records = Vec::new();
+    let mut record = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = text.chars().peekable();
+    
+    while let Some(c) = chars.next() {
+        if in_quotes {
+            match c {
+                '"' if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                '"' => in_quotes = false,
+                _ => field.push(c),
+            }
+            continue;
+        }
+        
+        match c {
+            '"' if field.is_empty() => in_quotes = true,
+            ',' => record.push(std::mem::take(&mut field)),
+            '\r' if chars.peek() == Some(&'\n') => {}
+            '\n' => {
+                record.push(std::mem::take(&mut field));
+                if !(record.len() == 1 && record[0].is_empty()) {
+                    records.push(std::mem::take(&mut record));
+                }
+                record.clear();
+            }
+            _ => field.push(c),
+        }
+    }
+    
+    if in_quotes {
+        return Err(ConverterError::Parse("Unterminated quoted field in CSV".to_string()));
+    }
+    if !field.is_empty() || !record.is_empty() {
+        record.push(field);
+        records.push(record);
+    }
+    
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geojson_parser::GeometryType;
+    
+    fn feature(id: serde_json::Value) -> Feature {
+        let mut properties = serde_json::Map::new();
+        properties.insert("code".to_string(), id);
+        properties.insert("name".to_string(), serde_json::json!("original"));
+        Feature {
+            geometry: GeometryType::Point(geo_types::Point::new(0.0, 0.0)),
+            properties,
+        }
+    }
+    
+    #[test]
+    fn test_join_attributes() {
+        let mut features = vec![
+            feature(serde_json::json!("A1")),
+            feature(serde_json::json!(2)),
+            feature(serde_json::json!("none")),
+        ];
+        let csv = "id,population,name,note\r\nA1,1200,\"Shibuya, Tokyo\",\n2,3.5,\"Say \"\"hi\"\"\",true\nZ9,1,x,\n";
+        
+        let diagnostics = join_attributes(&mut features, csv.as_bytes(), "code", "id").unwrap();
+        
+        assert_eq!(features[0].properties["population"], 1200);
+        assert_eq!(features[0].properties["name"], "Shibuya, Tokyo");
+        assert_eq!(features[0].properties["note"], Value::Null);
+        assert_eq!(features[1].properties["population"], 3.5);
+        assert_eq!(features[1].properties["name"], "Say \"hi\"");
+        assert_eq!(features[1].properties["note"], true);
+        assert!(!features[0].properties.contains_key("id"));
+        
+        // Unmatched feature is untouched
+        assert_eq!(features[2].properties.len(), 2);
+        assert_eq!(features[2].properties["name"], "original");
+        
+        assert_eq!(diagnostics.warnings.len(), 1);
+        assert!(diagnostics.warnings[0].contains("Z9"));
+    }
+    
+    #[test]
+    fn test_join_attributes_keeps_codes_as_text() {
+        let mut features = vec![feature(serde_json::json!("01100"))];
+        let csv = "id,code,postal,phone,exponent,zero,fraction\n01100,01100,060-0001,+81,1e5,0,0.25\n";
+        
+        let diagnostics = join_attributes(&mut features, csv.as_bytes(), "code", "id").unwrap();
+        assert!(diagnostics.warnings.is_empty());
+        assert_eq!(features[0].properties["code"], "01100");
+        assert_eq!(features[0].properties["postal"], "060-0001");
+        assert_eq!(features[0].properties["phone"], "+81");
+        assert_eq!(features[0].properties["exponent"], "1e5");
+        assert_eq!(features[0].properties["zero"], 0);
+        assert_eq!(features[0].properties["fraction"], 0.25);
+    }
+    
+    #[test]
+    fn test_join_attributes_duplicate_key() {
+        let mut features = vec![feature(serde_json::json!(1))];
+        let result = join_attributes(&mut features, b"id,value\n1,2\n1,3\n", "code", "id");
+        assert!(matches!(result, Err(ConverterError::InvalidInput(m)) if m.contains("row 3")));
+    }
+    
+    #[test]
+    fn test_join_attributes_missing_key_column() {
+        let mut features = vec![feature(serde_json::json!(1))];
+        let result = join_attributes(&mut features, b"code,value\n1,2\n", "code", "id");
+        assert!(matches!(result, Err(ConverterError::InvalidInput(_))));
+    }
+}
@@ -0,0 +1,138 @@
+// Date module
+// ISO 8601 date/time parsing and formatting for normalizing date properties
+
+/// Parse an ISO 8601 date or date-time string into seconds since the Unix epoch
+///
+/// Accepts `YYYY-MM-DD`, optionally followed by `T` (or a space) and `HH:MM`, `HH:MM:SS`
+/// or `HH:MM:SS.fff`, then `Z` or a `±HH:MM` / `±HHMM` offset. Times without an offset
+/// are taken as UTC, dates without a time as midnight; fractional seconds are dropped.
+/// Returns `None` for anything else, including out-of-range fields.
+pub fn parse_iso8601(text: &str) -> Option<i64> {
+    let bytes = text.as_bytes();
+    if bytes.len() < 10 || bytes[4] != b'-' || bytes[7] != b'-' {
+        return None;
+    }
+    let year = digits(&bytes[0..4])?;
+    let month = digits(&bytes[5..7])?;
+    let day = digits(&bytes[8..10])?;
+    if !(1..=12).contains(&month) || day < 1 || day > days_in_month(year, month) {
+        return None;
+    }
+    let mut seconds = days_from_civil(year, month, day) * 86400;
+    
+    let rest = &bytes[10..];
+    if rest.is_empty() {
+        return Some(seconds);
+    }
+    if rest.len() < 6 || !matches!(rest[0], b'T' | b' ') || rest[3] != b':' {
+        return None;
+    }
+    let hour = digits(&rest[1..3])?;
+    let minute = digits(&rest[4..6])?;
+    let mut rest = &rest[6..];
+    let mut second = 0;
+    if let [b':', s1, s2, tail @ ..] = rest {
+        second = digits(&[*s1, *s2])?;
+        rest = tail;
+        if let [b'.', tail @ ..] = rest {
+            let fraction = tail.iter().take_while(|b| b.is_ascii_digit()).count();
+            if fraction == 0 {
+                return None;
+            }
+            rest = &tail[fraction..];
+        }
+    }
+    if hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    seconds += hour * 3600 + minute * 60 + second;
+    
+    let offset = match rest {
+        [] | [b'Z'] => 0,
+        [sign @ (b'+' | b'-'), h1, h2, b':', m1, m2] | [sign @ (b'+' | b'-'), h1, h2, m1, m2] => {
+            let (hours, minutes) = (digits(&[*h1, *h2])?, digits(&[*m1, *m2])?);
+            if hours > 23 || minutes > 59 {
+                return None;
+            }
+            let offset = hours * 3600 + minutes * 60;
+            if *sign == b'+' { offset } else { -offset }
+        }
+        _ => return None,
+    };
+    Some(seconds - offset)
+}
+
+/// Format seconds since the Unix epoch as `YYYY-MM-DDTHH:MM:SSZ`
+pub fn format_iso8601(seconds: i64) -> String {
+    let days = seconds.div_euclid(86400);
+    let time = seconds.rem_euclid(86400);
+    let (year, month, day) = civil_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
+
+fn digits(bytes: &[u8]) -> Option<i64> {
+    bytes.iter().try_fold(0, |value, &b| b.is_ascii_digit().then(|| value * 10 + (b - b'0') as i64))
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's algorithm)
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+/// Inverse of `days_from_civil`
+fn civil_from_days(days: i64) -> (i64, i64, i64) {
+    let days = days + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days - era * 146097;
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_parse_and_format_iso8601() {
+        assert_eq!(parse_iso8601("1970-01-01"), Some(0));
+        assert_eq!(parse_iso8601("2023-01-02T03:04:05Z"), Some(1672628645));
+        assert_eq!(parse_iso8601("2023-01-02T12:04:05.250+09:00"), Some(1672628645));
+        assert_eq!(parse_iso8601("2023-01-01 22:04:05-0500"), Some(1672628645));
+        assert_eq!(parse_iso8601("1969-12-31T23:59"), Some(-60));
+        assert_eq!(parse_iso8601("2024-02-29"), Some(1709164800));
+        for invalid in ["2023-02-29", "2023-13-01", "2023-01-02T24:00", "2023-01-02T03:04:05+", "20230102", "plan"] {
+            assert_eq!(parse_iso8601(invalid), None, "{}", invalid);
+        }
+        
+        assert_eq!(format_iso8601(1672628645), "2023-01-02T03:04:05Z");
+        assert_eq!(format_iso8601(-60), "1969-12-31T23:59:00Z");
+        assert_eq!(format_iso8601(1709164800), "2024-02-29T00:00:00Z");
+    }
+}
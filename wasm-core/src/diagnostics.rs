@@ -0,0 +1,275 @@
+// Diagnostics module
+// Non-fatal warnings and suggestions collected while generating tiles
+
+use crate::geojson_parser::Feature;
+use crate::projection::lonlat_to_tile;
+use std::collections::HashMap;
+
+/// Default limit on the estimated number of tiles before a lower max zoom is suggested
+pub const DEFAULT_MAX_ESTIMATED_TILES: u64 = 1_000_000;
+
+/// Highest zoom `suggest_max_zoom` returns, for data too dense to thin out (e.g. duplicates)
+pub const MAX_SUGGESTED_ZOOM: u8 = 16;
+
+/// Number of tiles kept in each "heaviest tiles" list
+pub const HEAVIEST_TILE_COUNT: usize = 5;
+
+/// Warnings and suggestions about a tile generation run
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct Diagnostics {
+    /// Human-readable warnings
+    pub warnings: Vec<String>,
+    /// Estimated number of tiles for the requested zoom range
+    pub estimated_tiles: u64,
+    /// Highest max zoom that keeps the estimate within the limit (set when it is exceeded)
+    pub suggested_max_zoom: Option<u8>,
+    /// Largest tiles by encoded size, largest first
+    pub largest_tiles: Vec<TileWeight>,
+    /// Tiles with the most features, densest first
+    pub densest_tiles: Vec<TileWeight>,
+    /// LineStrings dropped by `min_line_length_px` while tiling, counted once per zoom level
+    pub short_lines_dropped: u64,
+    /// Features dropped by `keep_geometry_type`
+    pub geometry_type_dropped: usize,
+    /// Tiles with a layer of more than `max_tile_values` unique values
+    pub value_overflow_tiles: usize,
+    /// Features whose geometry mixes 2D and 3D positions (z values are dropped)
+    pub mixed_dimension_features: usize,
+    /// Points dropped by `point_grid`, counted once per tile
+    pub points_thinned: u64,
+    /// Features dropped by `validate_geometry`, counted once per tile
+    pub invalid_geometry_dropped: u64,
+}
+
+/// Size of one generated tile
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize)]
+pub struct TileWeight {
+    pub z: u8,
+    pub x: u32,
+    pub y: u32,
+    /// Encoded (uncompressed MVT) size in bytes
+    pub bytes: usize,
+    /// Number of features in the tile
+    pub features: usize,
+}
+
+impl Diagnostics {
+    /// Record a generated tile, keeping the `HEAVIEST_TILE_COUNT` largest and densest tiles
+    /// 
+    /// Ties are broken by tile coordinate so the lists do not depend on generation order.
+    pub fn record_tile(&mut self, tile: TileWeight) {
+        let coord = |t: &TileWeight| (t.z, t.x, t.y);
+        insert_top(&mut self.largest_tiles, tile, |a, b| {
+            b.bytes.cmp(&a.bytes).then_with(|| coord(a).cmp(&coord(b)))
+        });
+        insert_top(&mut self.densest_tiles, tile, |a, b| {
+            b.features.cmp(&a.features).then_with(|| coord(a).cmp(&coord(b)))
+        });
+    }
+    
+    /// Record the lines dropped by `min_line_length_px` over all zoom levels, with a warning
+    pub fn record_short_lines(&mut self, dropped: u64, min_line_length_px: f64) {
+        self.short_lines_dropped = dropped;
+        if dropped > 0 {
+            self.warnings.push(format!(
+                "{} line(s) shorter than {}px were dropped (counted per zoom level)",
+                dropped, min_line_length_px
+            ));
+        }
+    }
+    
+    /// Record a tile with a layer of `values` unique values, more than `limit`
+    /// 
+    /// Only the first such tile gets a warning; later ones are counted in
+    /// `value_overflow_tiles`.
+    pub fn record_value_overflow(&mut self, tile: TileWeight, values: usize, limit: usize, coerced: bool) {
+        self.value_overflow_tiles += 1;
+        if self.value_overflow_tiles > 1 {
+            return;
+        }
+        let action = if coerced {
+            "string values occurring once were replaced by the placeholder"
+        } else {
+            "set value_overflow_placeholder to replace rare string values"
+        };
+        self.warnings.push(format!(
+            "Tile {}/{}/{} has {} unique property values, more than the limit of {} ({}); other tiles may too, see value_overflow_tiles",
+            tile.z, tile.x, tile.y, values, limit, action
+        ));
+    }
+}
+
+/// Insert `tile` into a list sorted by `order`, truncated to `HEAVIEST_TILE_COUNT`
+fn insert_top(
+    list: &mut Vec<TileWeight>,
+    tile: TileWeight,
+    order: impl Fn(&TileWeight, &TileWeight) -> std::cmp::Ordering,
+) {
+    let index = list.partition_point(|other| order(other, &tile).is_lt());
+    if index < HEAVIEST_TILE_COUNT {
+        list.insert(index, tile);
+        list.truncate(HEAVIEST_TILE_COUNT);
+    }
+}
+
+/// Number of tiles at `zoom` covered by `bounds`
+fn tiles_covered(bounds: (f64, f64, f64, f64), zoom: u8) -> u64 {
+    let (min_lon, min_lat, max_lon, max_lat) = bounds;
+    let (tx_min, ty_min) = lonlat_to_tile(min_lon, max_lat, zoom);
+    let (tx_max, ty_max) = lonlat_to_tile(max_lon, min_lat, zoom);
+    (tx_max as u64 + 1).saturating_sub(tx_min as u64) * (ty_max as u64 + 1).saturating_sub(ty_min as u64)
+}
+
+/// Estimate the number of tiles generated at `zoom`
+///
+/// A tile only exists if a feature touches it. Each point touches one tile, each line or
+/// polygon at most the tiles covered by its bounding box (`shape_bounds`), and the total
+/// is capped at the tiles covered by the dataset `bounds`.
+pub fn estimate_tile_count(
+    point_count: usize,
+    shape_bounds: &[(f64, f64, f64, f64)],
+    bounds: (f64, f64, f64, f64),
+    zoom: u8,
+) -> u64 {
+    let covered = tiles_covered(bounds, zoom);
+    let touched = shape_bounds
+        .iter()
+        .try_fold(point_count as u64, |sum, &shape| Some(sum + tiles_covered(shape, zoom)).filter(|&sum| sum < covered))
+        .unwrap_or(covered);
+    touched.clamp(1, covered.max(1))
+}
+
+/// Check the requested zoom range against the dataset before generating tiles
+///
+/// When the estimated tile count exceeds `max_tiles`, a warning is added and
+/// `suggested_max_zoom` is set to the highest zoom that stays within the limit
+/// (never below `min_zoom`).
+pub fn preflight(
+    point_count: usize,
+    shape_bounds: &[(f64, f64, f64, f64)],
+    bounds: (f64, f64, f64, f64),
+    min_zoom: u8,
+    max_zoom: u8,
+    max_tiles: u64,
+) -> Diagnostics {
+    let mut diagnostics = Diagnostics::default();
+    let mut suggested = min_zoom;
+
+    for zoom in min_zoom..=max_zoom {
+        diagnostics.estimated_tiles += estimate_tile_count(point_count, shape_bounds, bounds, zoom);
+        if diagnostics.estimated_tiles <= max_tiles {
+            suggested = zoom;
+        }
+    }
+
+    if diagnostics.estimated_tiles > max_tiles {
+        diagnostics.suggested_max_zoom = Some(suggested);
+        diagnostics.warnings.push(format!(
+            "Zoom range {}-{} is estimated to produce {} tiles (limit {}); consider max_zoom {}",
+            min_zoom, max_zoom, diagnostics.estimated_tiles, max_tiles, suggested
+        ));
+    }
+
+    diagnostics
+}
+
+/// Suggest a max zoom from feature density
+///
+/// Features are binned into tiles by their centroid at increasing zoom levels; the
+/// result is the first zoom whose densest tile has at most `target_features_per_tile`
+/// features, capped at `MAX_SUGGESTED_ZOOM`. Returns 0 for an empty dataset.
+pub fn suggest_max_zoom(features: &[Feature], target_features_per_tile: usize) -> u8 {
+    let points: Vec<(f64, f64)> = features
+        .iter()
+        .filter_map(|feature| crate::tiler::centroid(&feature.geometry))
+        .map(|point| (point.x(), point.y()))
+        .collect();
+    
+    for zoom in 0..MAX_SUGGESTED_ZOOM {
+        let mut counts: HashMap<(u32, u32), usize> = HashMap::new();
+        for &(lon, lat) in &points {
+            *counts.entry(lonlat_to_tile(lon, lat, zoom)).or_default() += 1;
+        }
+        if counts.values().all(|&count| count <= target_features_per_tile) {
+            return zoom;
+        }
+    }
+    MAX_SUGGESTED_ZOOM
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preflight_suggests_lower_max_zoom_for_dense_data() {
+        // 2M features spread over Japan
+        let bounds = (129.0, 30.0, 146.0, 46.0);
+        let diagnostics = preflight(2_000_000, &[], bounds, 0, 18, DEFAULT_MAX_ESTIMATED_TILES);
+
+        let suggested = diagnostics.suggested_max_zoom.expect("suggestion expected");
+        assert!(suggested < 18);
+        assert!(diagnostics.estimated_tiles > DEFAULT_MAX_ESTIMATED_TILES);
+        assert_eq!(diagnostics.warnings.len(), 1);
+
+        // The suggested range fits the limit
+        let capped = preflight(2_000_000, &[], bounds, 0, suggested, DEFAULT_MAX_ESTIMATED_TILES);
+        assert!(capped.suggested_max_zoom.is_none());
+        assert!(capped.warnings.is_empty());
+    }
+    
+    #[test]
+    fn test_preflight_large_polygon() {
+        // One polygon covering most of the world touches every tile of its bounds
+        let bounds = (-170.0, -80.0, 170.0, 80.0);
+        assert!(estimate_tile_count(0, &[bounds], bounds, 6) > 3000);
+        let diagnostics = preflight(0, &[bounds], bounds, 0, 18, DEFAULT_MAX_ESTIMATED_TILES);
+        assert!(diagnostics.estimated_tiles > DEFAULT_MAX_ESTIMATED_TILES);
+        let suggested = diagnostics.suggested_max_zoom.expect("suggestion expected");
+        assert!((8..=10).contains(&suggested), "{}", suggested);
+        
+        // A small polygon and a point far apart only touch their own tiles
+        let small = (139.0, 35.0, 139.1, 35.1);
+        let spread = (-10.0, -10.0, 139.1, 35.1);
+        assert_eq!(estimate_tile_count(1, &[small], spread, 4), 2);
+    }
+    
+    #[test]
+    fn test_record_tile_keeps_heaviest() {
+        let mut diagnostics = Diagnostics::default();
+        for i in 0..10u32 {
+            diagnostics.record_tile(TileWeight { z: 3, x: i, y: 0, bytes: (i as usize * 7) % 10, features: i as usize });
+        }
+        
+        assert_eq!(diagnostics.largest_tiles.len(), HEAVIEST_TILE_COUNT);
+        let bytes: Vec<usize> = diagnostics.largest_tiles.iter().map(|t| t.bytes).collect();
+        assert_eq!(bytes, vec![9, 8, 7, 6, 5]);
+        let x: Vec<u32> = diagnostics.densest_tiles.iter().map(|t| t.x).collect();
+        assert_eq!(x, vec![9, 8, 7, 6, 5]);
+    }
+    
+    #[test]
+    fn test_suggest_max_zoom_follows_density() {
+        let point = |lon: f64, lat: f64| Feature {
+            geometry: crate::geojson_parser::GeometryType::Point(geo_types::Point::new(lon, lat)),
+            properties: serde_json::Map::new(),
+        };
+        // 100 points on a 10° grid, and 100 points within a few hundred meters
+        let sparse: Vec<Feature> = (0..100)
+            .map(|i| point(-50.0 + (i % 10) as f64 * 10.0, -40.0 + (i / 10) as f64 * 8.0))
+            .collect();
+        let clustered: Vec<Feature> = (0..100)
+            .map(|i| point(139.7 + (i % 10) as f64 * 0.003, 35.6 + (i / 10) as f64 * 0.003))
+            .collect();
+        
+        let sparse_zoom = suggest_max_zoom(&sparse, 10);
+        let clustered_zoom = suggest_max_zoom(&clustered, 10);
+        assert!(sparse_zoom <= 4, "{}", sparse_zoom);
+        assert!(clustered_zoom > sparse_zoom + 5, "{} vs {}", clustered_zoom, sparse_zoom);
+        
+        assert_eq!(suggest_max_zoom(&sparse, 100), 0);
+        assert_eq!(suggest_max_zoom(&[], 1), 0);
+        // Duplicates never thin out
+        assert_eq!(suggest_max_zoom(&vec![point(1.0, 1.0); 3], 1), MAX_SUGGESTED_ZOOM);
+    }
+}
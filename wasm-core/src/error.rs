@@ -0,0 +1,45 @@
+// Error module
+// Error type shared by all conversion steps
+
+use std::fmt;
+
+/// Error returned by the converter
+///
+/// Each variant carries a human-readable message; the WASM API passes
+/// `to_string()` of the error to JavaScript.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConverterError {
+    /// Input could not be read as GeoJSON (invalid JSON, unsupported structure or geometry)
+    Parse(String),
+    /// Coordinates could not be projected to Web Mercator
+    Projection(String),
+    /// Input contained no usable features or tiles
+    EmptyInput(String),
+    /// Tile, directory or archive encoding failed
+    Encode(String),
+    /// Invalid argument such as a layer name or an out-of-range tile coordinate
+    InvalidInput(String),
+    /// Reading the input failed
+    Io(String),
+    /// Input is larger than the configured limit
+    TooLarge(String),
+    /// Generation was cancelled by the caller
+    Cancelled,
+}
+
+impl fmt::Display for ConverterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConverterError::Parse(message)
+            | ConverterError::Projection(message)
+            | ConverterError::EmptyInput(message)
+            | ConverterError::Encode(message)
+            | ConverterError::InvalidInput(message)
+            | ConverterError::Io(message)
+            | ConverterError::TooLarge(message) => f.write_str(message),
+            ConverterError::Cancelled => f.write_str("Cancelled"),
+        }
+    }
+}
+
+impl std::error::Error for ConverterError {}
@@ -1,6 +1,7 @@
 // GeoJSON parsing module
 use geojson::{GeoJson, FeatureCollection, Geometry, Value};
-use geo_types::{Point, LineString, Polygon, Coord};
+use geo_types::{Point, LineString, Polygon, MultiPolygon, Coord};
+use crate::ConverterError;
 
 /// Parsed feature structure
 #[derive(Debug, Clone)]
@@ -15,135 +16,501 @@ pub enum GeometryType {
     Point(Point<f64>),
     LineString(LineString<f64>),
     Polygon(Polygon<f64>),
+    MultiPolygon(MultiPolygon<f64>),
+}
+
+/// Features parsed from a GeoJSON document
+#[derive(Debug, Clone, Default)]
+pub struct ParsedGeoJson {
+    /// Features with a supported geometry
+    pub features: Vec<Feature>,
+    /// Properties of features with `"geometry": null` (attribute-only rows)
+    pub attribute_only: Vec<serde_json::Map<String, serde_json::Value>>,
+    /// Number of features whose geometry mixes 2D and 3D positions
+    pub mixed_dimensions: usize,
+}
+
+/// Which features get their bounding box as `_minx`, `_miny`, `_maxx` and `_maxy` properties
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BboxProperties {
+    /// No bounding box properties (default)
+    #[default]
+    Off,
+    /// Copy the GeoJSON `bbox` member of features that have one
+    Input,
+    /// As `Input`; features without a `bbox` get the bounds of their geometry
+    InputOrComputed,
 }
 
 /// Parse features from GeoJSON bytes
-pub fn parse_geojson(bytes: &[u8]) -> Result<Vec<Feature>, String> {
-    let geojson_str = std::str::from_utf8(bytes)
-        .map_err(|e| format!("UTF-8 conversion error: {}", e))?;
+/// 
+/// Features without geometry are dropped; use `parse_geojson_with_attributes` to keep
+/// their properties.
+pub fn parse_geojson(bytes: &[u8]) -> Result<Vec<Feature>, ConverterError> {
+    Ok(parse_geojson_with_attributes(bytes)?.features)
+}
+
+/// Parse features from GeoJSON bytes, keeping the properties of features without geometry
+pub fn parse_geojson_with_attributes(bytes: &[u8]) -> Result<ParsedGeoJson, ConverterError> {
+    parse_geojson_with_bbox(bytes, BboxProperties::Off)
+}
+
+/// Parse features from GeoJSON bytes like `parse_geojson_with_attributes`, adding
+/// bounding box properties as selected by `bbox`
+pub fn parse_geojson_with_bbox(bytes: &[u8], bbox: BboxProperties) -> Result<ParsedGeoJson, ConverterError> {
+    let geojson_str = decode_text(bytes, "GeoJSON")?;
+    
+    let json: serde_json::Value = serde_json::from_str(geojson_str).map_err(|e| json_error(geojson_str, &e))?;
+    let mut parsed = ParsedGeoJson::default();
+    parse_json_value(json, bbox, &mut parsed)?;
+    
+    if parsed.features.is_empty() {
+        return Err(ConverterError::EmptyInput("No valid features found".to_string()));
+    }
+    
+    Ok(parsed)
+}
+
+/// Parse features from several GeoJSON inputs that together form one layer
+/// 
+/// Each input may be a FeatureCollection, a single Feature or newline-delimited
+/// GeoJSON (one Feature or FeatureCollection per line, optionally prefixed with the
+/// RFC 8142 record separator). Features are concatenated in input order. Errors name
+/// the failing input by its 0-based index; inputs without features are allowed as long
+/// as at least one input has some.
+pub fn parse_geojson_many(inputs: &[&[u8]]) -> Result<Vec<Feature>, ConverterError> {
+    let mut parsed = ParsedGeoJson::default();
+    for (index, bytes) in inputs.iter().enumerate() {
+        parse_input(bytes, &mut parsed).map_err(|e| in_input(index, e))?;
+    }
+    
+    if parsed.features.is_empty() {
+        return Err(ConverterError::EmptyInput(format!("No valid features found in {} inputs", inputs.len())));
+    }
+    
+    Ok(parsed.features)
+}
+
+/// Parse one input of `parse_geojson_many`, detecting a single document or NDJSON
+fn parse_input(bytes: &[u8], parsed: &mut ParsedGeoJson) -> Result<(), ConverterError> {
+    let text = decode_text(bytes, "GeoJSON")?;
+    
+    // A single document parses as one value; NDJSON has a complete value on its first line
+    let error = match serde_json::from_str::<serde_json::Value>(text) {
+        Ok(json) => return parse_json_value(json, BboxProperties::Off, parsed),
+        Err(e) => e,
+    };
+    let mut lines = text
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number + 1, line.trim_start_matches('\u{1e}').trim()))
+        .filter(|(_, line)| !line.is_empty())
+        .peekable();
+    let is_ndjson = lines
+        .peek()
+        .is_some_and(|(_, line)| serde_json::from_str::<serde_json::Value>(line).is_ok());
+    if !is_ndjson {
+        return Err(json_error(text, &error));
+    }
     
-    let geojson = geojson_str.parse::<GeoJson>()
-        .map_err(|e| format!("GeoJSON parse error: {}", e))?;
+    for (number, line) in lines {
+        let json = serde_json::from_str(line)
+            .map_err(|e| ConverterError::Parse(format!("Invalid JSON on line {}: {}", number, e)))?;
+        parse_json_value(json, BboxProperties::Off, parsed)?;
+    }
+    Ok(())
+}
+
+/// Add the features of a GeoJSON FeatureCollection or Feature value to `parsed`
+fn parse_json_value(json: serde_json::Value, bbox: BboxProperties, parsed: &mut ParsedGeoJson) -> Result<(), ConverterError> {
+    let geojson = GeoJson::from_json_value(json)
+        .map_err(|e| ConverterError::Parse(format!("GeoJSON parse error: {}", e)))?;
     
     match geojson {
-        GeoJson::FeatureCollection(fc) => parse_feature_collection(fc),
+        GeoJson::FeatureCollection(fc) => {
+            let collection = parse_feature_collection(fc, bbox);
+            parsed.features.extend(collection.features);
+            parsed.attribute_only.extend(collection.attribute_only);
+            parsed.mixed_dimensions += collection.mixed_dimensions;
+        }
         GeoJson::Feature(f) => {
-            let features = vec![parse_feature(f)?];
-            Ok(features)
+            if f.geometry.is_none() {
+                parsed.attribute_only.push(f.properties.unwrap_or_default());
+            } else {
+                parsed.mixed_dimensions += f.geometry.as_ref().is_some_and(has_mixed_dimensions) as usize;
+                parsed.features.push(parse_feature(f, bbox)?);
+            }
         }
-        _ => Err("Unsupported GeoJSON format".to_string()),
+        _ => return Err(ConverterError::Parse("Unsupported GeoJSON format".to_string())),
     }
+    Ok(())
+}
+
+/// Decode UTF-8 text input, skipping a leading byte order mark
+/// 
+/// `input` names the kind of input in errors. UTF-16 input (recognized by its BOM, as
+/// written by some Windows tools) is rejected with a hint to re-encode it.
+pub fn decode_text<'a>(bytes: &'a [u8], input: &str) -> Result<&'a str, ConverterError> {
+    reject_utf16(bytes, input)?;
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    std::str::from_utf8(bytes)
+        .map_err(|e| ConverterError::Parse(format!("UTF-8 conversion error in {}: {}", input, e)))
 }
 
-fn parse_feature_collection(fc: FeatureCollection) -> Result<Vec<Feature>, String> {
-    let mut features = Vec::new();
+/// Fail if `bytes` start with a UTF-16 byte order mark
+pub(crate) fn reject_utf16(bytes: &[u8], input: &str) -> Result<(), ConverterError> {
+    if bytes.starts_with(b"\xFF\xFE") || bytes.starts_with(b"\xFE\xFF") {
+        return Err(ConverterError::Parse(format!(
+            "{} is UTF-16 encoded; re-encode it as UTF-8 (e.g. \"Save as UTF-8\" in the exporting tool)",
+            input
+        )));
+    }
+    Ok(())
+}
+
+/// Prefix an error message with the index of the input it came from
+fn in_input(index: usize, error: ConverterError) -> ConverterError {
+    let context = |message: String| format!("Input {}: {}", index, message);
+    match error {
+        ConverterError::Parse(m) => ConverterError::Parse(context(m)),
+        ConverterError::Projection(m) => ConverterError::Projection(context(m)),
+        ConverterError::EmptyInput(m) => ConverterError::EmptyInput(context(m)),
+        ConverterError::Encode(m) => ConverterError::Encode(context(m)),
+        ConverterError::InvalidInput(m) => ConverterError::InvalidInput(context(m)),
+        ConverterError::Io(m) => ConverterError::Io(context(m)),
+        ConverterError::TooLarge(m) => ConverterError::TooLarge(context(m)),
+        ConverterError::Cancelled => ConverterError::Cancelled,
+    }
+}
+
+/// Byte offset into `bytes` of a JSON syntax error
+pub(crate) fn json_error_offset(bytes: &[u8], error: &serde_json::Error) -> usize {
+    // serde_json reports 1-based lines and columns (in bytes)
+    let line_start: usize = bytes
+        .split_inclusive(|&b| b == b'\n')
+        .take(error.line().saturating_sub(1))
+        .map(<[u8]>::len)
+        .sum();
+    (line_start + error.column().saturating_sub(1)).min(bytes.len())
+}
+
+/// Message of a JSON error without serde_json's position suffix
+pub(crate) fn json_error_detail(error: &serde_json::Error) -> String {
+    let message = error.to_string();
+    message.split(" at line ").next().unwrap_or(&message).to_string()
+}
+
+/// Describe a JSON syntax error with its line, column and byte offset
+fn json_error(text: &str, error: &serde_json::Error) -> ConverterError {
+    let offset = json_error_offset(text.as_bytes(), error);
+    let detail = json_error_detail(error);
+    let hint = if error.is_eof() { " (the input ends early; is the file truncated?)" } else { "" };
+    ConverterError::Parse(format!(
+        "Invalid JSON at line {}, column {} (byte {}): {}{}",
+        error.line(),
+        error.column(),
+        offset,
+        detail,
+        hint
+    ))
+}
+
+fn parse_feature_collection(fc: FeatureCollection, bbox: BboxProperties) -> ParsedGeoJson {
+    let mut parsed = ParsedGeoJson::default();
     
     for feature in fc.features {
-        match parse_feature(feature) {
-            Ok(f) => features.push(f),
+        if feature.geometry.is_none() {
+            parsed.attribute_only.push(feature.properties.unwrap_or_default());
+            continue;
+        }
+        
+        let mixed = feature.geometry.as_ref().is_some_and(has_mixed_dimensions);
+        match parse_feature(feature, bbox) {
+            Ok(f) => {
+                parsed.mixed_dimensions += mixed as usize;
+                parsed.features.push(f);
+            }
             Err(_e) => {
                 // Skip invalid features silently
             },
         }
     }
     
-    if features.is_empty() {
-        return Err("No valid features found".to_string());
+    parsed
+}
+
+/// Whether a geometry has both 2D positions and positions with a z value
+/// 
+/// Positions are converted to 2D either way, but such a mix within one feature often
+/// points at corrupted or badly merged data.
+pub(crate) fn has_mixed_dimensions(geometry: &Geometry) -> bool {
+    let mut positions = Vec::new();
+    collect_positions(&geometry.value, &mut positions);
+    let mut has_z = positions.iter().map(|position| position.len() > 2);
+    match has_z.next() {
+        Some(first) => has_z.any(|z| z != first),
+        None => false,
     }
-    
-    Ok(features)
 }
 
-fn parse_feature(feature: geojson::Feature) -> Result<Feature, String> {
+fn collect_positions<'a>(value: &'a Value, positions: &mut Vec<&'a Vec<f64>>) {
+    match value {
+        Value::Point(position) => positions.push(position),
+        Value::MultiPoint(points) | Value::LineString(points) => positions.extend(points),
+        Value::MultiLineString(lines) | Value::Polygon(lines) => positions.extend(lines.iter().flatten()),
+        Value::MultiPolygon(polygons) => positions.extend(polygons.iter().flatten().flatten()),
+        Value::GeometryCollection(geometries) => {
+            for geometry in geometries {
+                collect_positions(&geometry.value, positions);
+            }
+        }
+    }
+}
+
+pub(crate) fn parse_feature(feature: geojson::Feature, bbox: BboxProperties) -> Result<Feature, ConverterError> {
     let geometry = feature.geometry
-        .ok_or("No geometry")?;
+        .ok_or_else(|| ConverterError::Parse("No geometry".to_string()))?;
     
     let geometry_type = parse_geometry(geometry)?;
     
     let properties = feature.properties
-        .unwrap_or_else(|| serde_json::Map::new());
+        .unwrap_or_default();
     
-    Ok(Feature {
+    let mut parsed = Feature {
         geometry: geometry_type,
         properties,
-    })
+    };
+    let bounds = match bbox {
+        BboxProperties::Off => None,
+        BboxProperties::Input => feature.bbox.as_deref().and_then(bbox_bounds),
+        BboxProperties::InputOrComputed => {
+            Some(feature.bbox.as_deref().and_then(bbox_bounds).unwrap_or_else(|| feature_bounds(&parsed)))
+        }
+    };
+    if let Some((min_x, min_y, max_x, max_y)) = bounds {
+        for (key, value) in [("_minx", min_x), ("_miny", min_y), ("_maxx", max_x), ("_maxy", max_y)] {
+            parsed.properties.insert(key.to_string(), serde_json::json!(value));
+        }
+    }
+    
+    Ok(parsed)
 }
 
-fn parse_geometry(geometry: Geometry) -> Result<GeometryType, String> {
+/// Horizontal bounds of a GeoJSON `bbox` (2D or 3D), if it is well-formed
+fn bbox_bounds(bbox: &[f64]) -> Option<(f64, f64, f64, f64)> {
+    let axes = match bbox.len() {
+        4 => 2,
+        6 => 3,
+        _ => return None,
+    };
+    Some((bbox[0], bbox[1], bbox[axes], bbox[axes + 1]))
+}
+
+fn parse_geometry(geometry: Geometry) -> Result<GeometryType, ConverterError> {
     match geometry.value {
         Value::Point(coords) => {
-            let point = Point::new(coords[0], coords[1]);
-            Ok(GeometryType::Point(point))
+            let coord = position_to_coord(&coords)?;
+            Ok(GeometryType::Point(Point::from(coord)))
         }
         Value::LineString(coords) => {
-            let line: Vec<Coord<f64>> = coords
-                .iter()
-                .map(|c| Coord { x: c[0], y: c[1] })
-                .collect();
-            Ok(GeometryType::LineString(LineString::from(line)))
+            Ok(GeometryType::LineString(LineString::from(positions_to_coords(&coords)?)))
         }
         Value::Polygon(rings) => {
-            if rings.is_empty() {
-                return Err("Empty polygon".to_string());
+            Ok(GeometryType::Polygon(parse_polygon_rings(&rings)?))
+        }
+        Value::MultiPolygon(polygons) => {
+            if polygons.is_empty() {
+                return Err(ConverterError::Parse("Empty multipolygon".to_string()));
             }
             
-            // Exterior ring
-            let exterior: Vec<Coord<f64>> = rings[0]
+            let polygons = polygons
                 .iter()
-                .map(|c| Coord { x: c[0], y: c[1] })
-                .collect();
+                .map(|rings| parse_polygon_rings(rings))
+                .collect::<Result<Vec<_>, _>>()?;
             
-            // Interior rings (holes)
-            let interiors: Vec<LineString<f64>> = rings[1..]
-                .iter()
-                .map(|ring| {
-                    let coords: Vec<Coord<f64>> = ring
-                        .iter()
-                        .map(|c| Coord { x: c[0], y: c[1] })
-                        .collect();
-                    LineString::from(coords)
-                })
-                .collect();
-            
-            Ok(GeometryType::Polygon(Polygon::new(
-                LineString::from(exterior),
-                interiors,
-            )))
+            Ok(GeometryType::MultiPolygon(MultiPolygon::new(polygons)))
+        }
+        _ => Err(ConverterError::Parse(format!("Unsupported geometry type: {:?}", geometry.value))),
+    }
+}
+
+/// Build a Polygon from GeoJSON rings (first ring is exterior, the rest are holes)
+fn parse_polygon_rings(rings: &[Vec<Vec<f64>>]) -> Result<Polygon<f64>, ConverterError> {
+    if rings.is_empty() {
+        return Err(ConverterError::Parse("Empty polygon".to_string()));
+    }
+    
+    // Exterior ring
+    let exterior = positions_to_coords(&rings[0])?;
+    
+    // Interior rings (holes)
+    let mut interiors = Vec::with_capacity(rings.len() - 1);
+    for ring in &rings[1..] {
+        interiors.push(LineString::from(positions_to_coords(ring)?));
+    }
+    
+    Ok(Polygon::new(LineString::from(exterior), interiors))
+}
+
+/// Convert GeoJSON positions to coordinates in one pass over a preallocated buffer
+/// 
+/// Positions are walked in a flat loop, so rings and lines with millions of vertices
+/// convert in linear time without recursion. Nesting depth is bounded earlier by
+/// serde_json's recursion limit, which rejects pathological input with an error.
+/// 
+/// Consecutive duplicate positions (e.g. GPS dwell points) are dropped, as they only
+/// add zero-length segments. A sequence of identical positions keeps two of them, so
+/// the result is still a (degenerate) line or ring rather than a single point.
+fn positions_to_coords(positions: &[Vec<f64>]) -> Result<Vec<Coord<f64>>, ConverterError> {
+    let mut coords: Vec<Coord<f64>> = Vec::with_capacity(positions.len());
+    for position in positions {
+        let coord = position_to_coord(position)?;
+        if coords.last() != Some(&coord) {
+            coords.push(coord);
         }
-        _ => Err(format!("Unsupported geometry type: {:?}", geometry.value)),
+    }
+    if coords.len() == 1 && positions.len() > 1 {
+        coords.push(coords[0]);
+    }
+    Ok(coords)
+}
+
+/// Convert one GeoJSON position (extra values such as altitude are ignored)
+fn position_to_coord(position: &[f64]) -> Result<Coord<f64>, ConverterError> {
+    match position {
+        [x, y, ..] => Ok(Coord { x: *x, y: *y }),
+        _ => Err(ConverterError::Parse(format!("Position {:?} needs at least two values", position))),
     }
 }
 
 /// Calculate bounds (bounding box) from GeoJSON features
-pub fn calculate_bounds(features: &[Feature]) -> Result<(f64, f64, f64, f64), String> {
+pub fn calculate_bounds(features: &[Feature]) -> Result<(f64, f64, f64, f64), ConverterError> {
     if features.is_empty() {
-        return Err("Features are empty".to_string());
+        return Err(ConverterError::EmptyInput("Features are empty".to_string()));
+    }
+    
+    Ok(features.iter().map(feature_bounds).fold(EMPTY_BOUNDS, merge_bounds))
+}
+
+/// Convert features with EPSG:3857 coordinates (WebMercator meters) to lon/lat in place
+pub fn unproject_features(features: &mut [Feature]) -> Result<(), ConverterError> {
+    fn line(line: &LineString<f64>) -> Result<LineString<f64>, ConverterError> {
+        line.coords()
+            .map(|c| crate::projection::unproject_meters(c.x, c.y).map(|(x, y)| Coord { x, y }))
+            .collect::<Result<Vec<_>, _>>()
+            .map(LineString::new)
+    }
+    fn polygon(polygon: &Polygon<f64>) -> Result<Polygon<f64>, ConverterError> {
+        let interiors = polygon.interiors().iter().map(line).collect::<Result<_, _>>()?;
+        Ok(Polygon::new(line(polygon.exterior())?, interiors))
     }
     
+    for feature in features {
+        feature.geometry = match &feature.geometry {
+            GeometryType::Point(point) => {
+                let (lon, lat) = crate::projection::unproject_meters(point.x(), point.y())?;
+                GeometryType::Point(Point::new(lon, lat))
+            }
+            GeometryType::LineString(l) => GeometryType::LineString(line(l)?),
+            GeometryType::Polygon(p) => GeometryType::Polygon(polygon(p)?),
+            GeometryType::MultiPolygon(multi) => GeometryType::MultiPolygon(MultiPolygon::new(
+                multi.0.iter().map(polygon).collect::<Result<_, _>>()?,
+            )),
+        };
+    }
+    Ok(())
+}
+
+/// Sort features by geometry type, then by a SHA-256 hash of geometry and properties
+/// 
+/// The order only depends on the features themselves, not on their input order, so
+/// everything generated downstream is reproducible. Identical features keep their order.
+pub fn sort_features_canonically(features: &mut [Feature]) {
+    features.sort_by_cached_key(|feature| {
+        let rank = match feature.geometry {
+            GeometryType::Point(_) => 0u8,
+            GeometryType::LineString(_) => 1,
+            GeometryType::Polygon(_) => 2,
+            GeometryType::MultiPolygon(_) => 3,
+        };
+        
+        let mut hasher = crate::checksum::Sha256::new();
+        match &feature.geometry {
+            GeometryType::Point(point) => hash_coords(&mut hasher, &[point.0]),
+            GeometryType::LineString(line) => hash_coords(&mut hasher, &line.0),
+            GeometryType::Polygon(polygon) => hash_polygon(&mut hasher, polygon),
+            GeometryType::MultiPolygon(multi_polygon) => {
+                for polygon in &multi_polygon.0 {
+                    hash_polygon(&mut hasher, polygon);
+                }
+            }
+        }
+        // Property maps are sorted by key, so their JSON text is canonical
+        hasher.update(serde_json::Value::Object(feature.properties.clone()).to_string().as_bytes());
+        
+        (rank, hasher.finalize())
+    });
+}
+
+fn hash_polygon(hasher: &mut crate::checksum::Sha256, polygon: &Polygon<f64>) {
+    hash_coords(hasher, &polygon.exterior().0);
+    for interior in polygon.interiors() {
+        hash_coords(hasher, &interior.0);
+    }
+}
+
+/// Hash a coordinate sequence, prefixed with its length so sequences cannot run together
+fn hash_coords(hasher: &mut crate::checksum::Sha256, coords: &[Coord<f64>]) {
+    hasher.update(&(coords.len() as u64).to_le_bytes());
+    for coord in coords {
+        hasher.update(&coord.x.to_le_bytes());
+        hasher.update(&coord.y.to_le_bytes());
+    }
+}
+
+/// Bounds that contain nothing (identity for `merge_bounds`)
+pub const EMPTY_BOUNDS: (f64, f64, f64, f64) = (f64::INFINITY, f64::INFINITY, f64::NEG_INFINITY, f64::NEG_INFINITY);
+
+/// Union of two bounding boxes
+pub fn merge_bounds(a: (f64, f64, f64, f64), b: (f64, f64, f64, f64)) -> (f64, f64, f64, f64) {
+    (a.0.min(b.0), a.1.min(b.1), a.2.max(b.2), a.3.max(b.3))
+}
+
+/// Calculate bounds (bounding box) of a single feature
+pub fn feature_bounds(feature: &Feature) -> (f64, f64, f64, f64) {
     let mut min_lon = f64::INFINITY;
     let mut min_lat = f64::INFINITY;
     let mut max_lon = f64::NEG_INFINITY;
     let mut max_lat = f64::NEG_INFINITY;
     
-    for feature in features {
-        match &feature.geometry {
-            GeometryType::Point(point) => {
-                let lon = point.x();
-                let lat = point.y();
-                min_lon = min_lon.min(lon);
-                min_lat = min_lat.min(lat);
-                max_lon = max_lon.max(lon);
-                max_lat = max_lat.max(lat);
+    match &feature.geometry {
+        GeometryType::Point(point) => {
+            let lon = point.x();
+            let lat = point.y();
+            min_lon = min_lon.min(lon);
+            min_lat = min_lat.min(lat);
+            max_lon = max_lon.max(lon);
+            max_lat = max_lat.max(lat);
+        }
+        GeometryType::LineString(line) => {
+            for coord in &line.0 {
+                min_lon = min_lon.min(coord.x);
+                min_lat = min_lat.min(coord.y);
+                max_lon = max_lon.max(coord.x);
+                max_lat = max_lat.max(coord.y);
             }
-            GeometryType::LineString(line) => {
-                for coord in &line.0 {
-                    min_lon = min_lon.min(coord.x);
-                    min_lat = min_lat.min(coord.y);
-                    max_lon = max_lon.max(coord.x);
-                    max_lat = max_lat.max(coord.y);
-                }
+        }
+        GeometryType::Polygon(polygon) => {
+            for coord in polygon.exterior().0.iter() {
+                min_lon = min_lon.min(coord.x);
+                min_lat = min_lat.min(coord.y);
+                max_lon = max_lon.max(coord.x);
+                max_lat = max_lat.max(coord.y);
             }
-            GeometryType::Polygon(polygon) => {
+        }
+        GeometryType::MultiPolygon(multi_polygon) => {
+            for polygon in &multi_polygon.0 {
                 for coord in polygon.exterior().0.iter() {
                     min_lon = min_lon.min(coord.x);
                     min_lat = min_lat.min(coord.y);
@@ -154,7 +521,7 @@ pub fn calculate_bounds(features: &[Feature]) -> Result<(f64, f64, f64, f64), St
         }
     }
     
-    Ok((min_lon, min_lat, max_lon, max_lat))
+    (min_lon, min_lat, max_lon, max_lat)
 }
 
 /// Calculate center coordinates from bounds
@@ -168,7 +535,56 @@ pub fn calculate_center(bounds: (f64, f64, f64, f64)) -> (f64, f64) {
 #[cfg(test)]
 mod tests {
     use super::*;
-
+    
+    #[test]
+    fn test_consecutive_duplicate_coordinates_removed() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.0, 35.0], [139.5, 35.5], [139.5, 35.5], [139.5, 35.5, 10.0], [140.0, 36.0]]}, "properties": {}},
+            {"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [[[139.0, 35.0], [140.0, 35.0], [140.0, 35.0], [140.0, 36.0], [139.0, 35.0]]]}, "properties": {}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.0, 35.0], [139.0, 35.0], [139.0, 35.0]]}, "properties": {}}
+        ]}"#;
+        let features = parse_geojson(geojson.as_bytes()).unwrap();
+        
+        let GeometryType::LineString(line) = &features[0].geometry else {
+            panic!("Expected LineString geometry");
+        };
+        let coords: Vec<(f64, f64)> = line.coords().map(|c| (c.x, c.y)).collect();
+        assert_eq!(coords, vec![(139.0, 35.0), (139.5, 35.5), (140.0, 36.0)]);
+        
+        let GeometryType::Polygon(polygon) = &features[1].geometry else {
+            panic!("Expected Polygon geometry");
+        };
+        assert_eq!(polygon.exterior().0.len(), 4);
+        
+        let GeometryType::LineString(line) = &features[2].geometry else {
+            panic!("Expected LineString geometry");
+        };
+        assert_eq!(line.0.len(), 2);
+    }
+    
+    #[test]
+    fn test_parse_geojson_with_bom() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}}]}"#;
+        let mut bytes = b"\xEF\xBB\xBF".to_vec();
+        bytes.extend_from_slice(geojson.as_bytes());
+        
+        let features = parse_geojson(&bytes).unwrap();
+        assert_eq!(features.len(), 1);
+        assert_eq!(features[0].properties["name"], "Tokyo");
+        assert_eq!(parse_geojson_many(&[&bytes]).unwrap().len(), 1);
+        
+        let mut stream = crate::geojson_stream::FeatureStreamParser::new();
+        assert_eq!(stream.push(&bytes).unwrap().len(), 1);
+        
+        let utf16: Vec<u8> = [0xFF, 0xFE].into_iter().chain(geojson.bytes().flat_map(|b| [b, 0])).collect();
+        for error in [parse_geojson(&utf16).unwrap_err(), parse_geojson_many(&[&utf16]).unwrap_err()] {
+            assert!(error.to_string().contains("UTF-16"), "{}", error);
+        }
+        let mut stream = crate::geojson_stream::FeatureStreamParser::new();
+        assert!(stream.push(&utf16[..1]).is_ok());
+        assert!(stream.push(&utf16[1..]).is_err());
+    }
+    
     #[test]
     fn test_parse_point_geojson() {
         let geojson = r#"{
@@ -198,4 +614,75 @@ mod tests {
             _ => panic!("Expected Point geometry"),
         }
     }
+    
+    #[test]
+    fn test_malformed_json_reports_position() {
+        let truncated = "{\"type\": \"FeatureCollection\",\n \"features\": [{\"type\": \"Feat";
+        let Err(ConverterError::Parse(message)) = parse_geojson(truncated.as_bytes()) else {
+            panic!("expected a parse error");
+        };
+        assert!(message.starts_with("Invalid JSON at line 2,"), "{}", message);
+        assert!(message.contains("truncated"), "{}", message);
+        
+        let Err(ConverterError::Parse(message)) = parse_geojson(b"{\"type\": x}") else {
+            panic!("expected a parse error");
+        };
+        assert!(message.starts_with("Invalid JSON at line 1, column 10 (byte 9): expected value"), "{}", message);
+        assert!(!message.contains("truncated"));
+    }
+    
+    #[test]
+    fn test_parse_geojson_many() {
+        let first = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [1, 2]}, "properties": {"n": 1}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [3, 4]}, "properties": {"n": 2}}
+        ]}"#;
+        let second = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [5, 6]}, "properties": {"n": 3}}
+        ]}"#;
+        let features = parse_geojson_many(&[first.as_bytes(), second.as_bytes()]).unwrap();
+        let numbers: Vec<_> = features.iter().map(|f| f.properties["n"].as_i64().unwrap()).collect();
+        assert_eq!(numbers, vec![1, 2, 3]);
+        
+        // Single features and NDJSON are detected per input
+        let single = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}, "properties": {"n": 4}}"#;
+        let ndjson = format!("{}\n\x1e{}\n", single.replace('4', "5"), single.replace('4', "6"));
+        let features = parse_geojson_many(&[second.as_bytes(), single.as_bytes(), ndjson.as_bytes()]).unwrap();
+        let numbers: Vec<_> = features.iter().map(|f| f.properties["n"].as_i64().unwrap()).collect();
+        assert_eq!(numbers, vec![3, 4, 5, 6]);
+        
+        let Err(ConverterError::Parse(message)) = parse_geojson_many(&[first.as_bytes(), b"{\"type\": x}"]) else {
+            panic!("expected a parse error");
+        };
+        assert!(message.starts_with("Input 1: Invalid JSON at line 1"), "{}", message);
+    }
+    
+    #[test]
+    fn test_huge_linestring() {
+        let vertices = 1_000_000;
+        let mut text = String::with_capacity(vertices * 24);
+        text.push_str(r#"{"type": "Feature", "properties": {}, "geometry": {"type": "LineString", "coordinates": ["#);
+        for i in 0..vertices {
+            if i > 0 {
+                text.push(',');
+            }
+            text.push_str(&format!("[{:.6},{:.6}]", (i % 360) as f64 - 180.0 + 1e-6 * i as f64, (i % 170) as f64 - 85.0));
+        }
+        text.push_str("]}}");
+        
+        let start = std::time::Instant::now();
+        let features = parse_geojson(text.as_bytes()).unwrap();
+        assert!(start.elapsed() < std::time::Duration::from_secs(30), "{:?}", start.elapsed());
+        let GeometryType::LineString(line) = &features[0].geometry else {
+            panic!("expected a line");
+        };
+        assert_eq!(line.0.len(), vertices);
+        
+        // Pathological nesting is rejected by the JSON parser instead of overflowing the stack
+        let nested = format!("{}{}", "[".repeat(100_000), "]".repeat(100_000));
+        assert!(matches!(parse_geojson(nested.as_bytes()), Err(ConverterError::Parse(_))));
+        // Short positions are an error instead of a panic
+        let short = r#"{"type": "Feature", "properties": {}, "geometry": {"type": "Point", "coordinates": [1]}}"#;
+        assert!(parse_geojson(short.as_bytes()).is_err());
+    }
 }
@@ -0,0 +1,534 @@
+// Streaming GeoJSON module
+// Split a FeatureCollection into features incrementally, without holding the whole input
+
+use crate::geojson_parser::{has_mixed_dimensions, json_error_detail, json_error_offset, parse_feature, BboxProperties, Feature};
+use crate::ConverterError;
+
+/// Longest top-level key that is remembered while scanning (enough for "features")
+const MAX_KEY_LENGTH: usize = 16;
+
+/// Push-based FeatureCollection splitter
+///
+/// Bytes can be pushed in arbitrary chunks; each complete member of the top-level
+/// `features` array is parsed as soon as its closing brace arrives. Only the bytes
+/// of the feature currently being read are buffered.
+#[derive(Debug, Default)]
+pub struct FeatureStreamParser {
+    depth: usize,
+    in_string: bool,
+    escaped: bool,
+    /// Top-level string being read (candidate key)
+    key: Vec<u8>,
+    /// Last top-level key followed by ':'
+    pending_key: Vec<u8>,
+    in_features: bool,
+    seen_features: bool,
+    /// Last structural byte of the `features` array outside a feature: `[`, `,` or `}`
+    last_in_features: u8,
+    /// Raw bytes of the feature currently being captured
+    feature: Vec<u8>,
+    capturing: bool,
+    /// Bytes consumed before the current chunk
+    offset: usize,
+    /// Offset of the first byte of the feature being captured
+    feature_start: usize,
+    max_buffered: usize,
+    feature_count: usize,
+    /// Features whose geometry mixes 2D and 3D positions
+    mixed_dimensions: usize,
+    /// Properties of features without geometry, until taken by `take_attribute_only`
+    attribute_only: Vec<serde_json::Map<String, serde_json::Value>>,
+    /// First two bytes of the input, to detect a UTF-16 byte order mark
+    head: Vec<u8>,
+    bbox: BboxProperties,
+}
+
+impl FeatureStreamParser {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parser that adds bounding box properties to features as selected by `bbox`
+    pub fn with_bbox_properties(bbox: BboxProperties) -> Self {
+        Self {
+            bbox,
+            ..Self::default()
+        }
+    }
+
+    /// Parser that reads features as the generate functions do with `options`
+    /// (currently `options.bbox_properties`)
+    pub fn with_options(options: &crate::TilerOptions) -> Self {
+        Self::with_bbox_properties(options.bbox_properties)
+    }
+    
+    /// Feed the next chunk of input and return the features completed by it
+    ///
+    /// Features that are valid JSON but not valid GeoJSON are skipped, like `parse_geojson`
+    /// does; a JSON syntax error, or anything but `,` and whitespace between features, is an
+    /// error naming its byte offset in the stream. The properties of features without
+    /// geometry are kept for `take_attribute_only`. A UTF-8 byte order mark needs no special
+    /// handling, as bytes outside the top-level object are ignored.
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<Feature>, ConverterError> {
+        if self.head.len() < 2 {
+            let missing = (2 - self.head.len()).min(bytes.len());
+            self.head.extend_from_slice(&bytes[..missing]);
+            crate::geojson_parser::reject_utf16(&self.head, "GeoJSON")?;
+        }
+        
+        let mut features = Vec::new();
+
+        for (i, &byte) in bytes.iter().enumerate() {
+            let position = self.offset + i;
+            if self.depth == 2 && self.in_features && !self.capturing && !byte.is_ascii_whitespace() {
+                let expected = match byte {
+                    b'{' => self.last_in_features != b'}',
+                    b',' => self.last_in_features == b'}',
+                    b']' => self.last_in_features != b',',
+                    _ => false,
+                };
+                if !expected {
+                    return Err(ConverterError::Parse(format!(
+                        "Unexpected {:?} between features at byte {}",
+                        byte as char, position
+                    )));
+                }
+                if byte == b',' {
+                    self.last_in_features = byte;
+                }
+            }
+            
+            if self.capturing {
+                self.feature.push(byte);
+            }
+
+            if self.in_string {
+                if self.escaped {
+                    self.escaped = false;
+                } else if byte == b'\\' {
+                    self.escaped = true;
+                } else if byte == b'"' {
+                    self.in_string = false;
+                } else if self.depth == 1 && self.key.len() < MAX_KEY_LENGTH {
+                    self.key.push(byte);
+                }
+                continue;
+            }
+
+            match byte {
+                b'"' => {
+                    self.in_string = true;
+                    if self.depth == 1 {
+                        self.key.clear();
+                    }
+                }
+                b':' if self.depth == 1 => {
+                    self.pending_key = std::mem::take(&mut self.key);
+                }
+                b'{' | b'[' => {
+                    if self.depth == 0 && byte != b'{' {
+                        return Err(ConverterError::Parse("Streaming input must be a GeoJSON FeatureCollection".to_string()));
+                    }
+                    if self.depth == 1 && byte == b'[' && self.pending_key == b"features" {
+                        self.in_features = true;
+                        self.seen_features = true;
+                        self.last_in_features = byte;
+                    }
+                    if self.depth == 2 && self.in_features && byte == b'{' {
+                        self.capturing = true;
+                        self.feature_start = position;
+                        self.feature.clear();
+                        self.feature.push(byte);
+                    }
+                    self.depth += 1;
+                }
+                b'}' | b']' => {
+                    if self.depth == 0 {
+                        return Err(ConverterError::Parse("Unbalanced brackets in GeoJSON input".to_string()));
+                    }
+                    self.depth -= 1;
+                    if self.depth == 2 && self.capturing {
+                        self.capturing = false;
+                        self.max_buffered = self.max_buffered.max(self.feature.len());
+                        self.last_in_features = byte;
+                        match parse_feature_bytes(&self.feature, self.bbox).map_err(|e| {
+                            ConverterError::Parse(format!(
+                                "Invalid JSON at byte {}: {}",
+                                self.feature_start + json_error_offset(&self.feature, &e),
+                                json_error_detail(&e)
+                            ))
+                        })? {
+                            Some(Parsed::Feature(feature, mixed)) => {
+                                self.feature_count += 1;
+                                self.mixed_dimensions += mixed as usize;
+                                features.push(feature);
+                            }
+                            Some(Parsed::AttributeOnly(properties)) => self.attribute_only.push(properties),
+                            None => {}
+                        }
+                        self.feature.clear();
+                    }
+                    if self.depth == 1 {
+                        self.in_features = false;
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        self.offset += bytes.len();
+        self.max_buffered = self.max_buffered.max(self.feature.len());
+        Ok(features)
+    }
+
+    /// Check that the input ended cleanly and contained at least one valid feature
+    pub fn finish(&self) -> Result<(), ConverterError> {
+        if self.depth != 0 || self.in_string {
+            return Err(ConverterError::Parse("Unexpected end of GeoJSON input".to_string()));
+        }
+        if !self.seen_features {
+            return Err(ConverterError::Parse("Streaming input must be a GeoJSON FeatureCollection".to_string()));
+        }
+        if self.feature_count == 0 {
+            return Err(ConverterError::EmptyInput("No valid features found".to_string()));
+        }
+        Ok(())
+    }
+
+    /// Largest number of bytes buffered for a single feature so far
+    pub fn max_buffered(&self) -> usize {
+        self.max_buffered
+    }
+    
+    /// Number of features so far whose geometry mixes 2D and 3D positions
+    pub fn mixed_dimensions(&self) -> usize {
+        self.mixed_dimensions
+    }
+
+    /// Take the properties of the features without geometry read since the last call
+    pub fn take_attribute_only(&mut self) -> Vec<serde_json::Map<String, serde_json::Value>> {
+        std::mem::take(&mut self.attribute_only)
+    }
+}
+
+/// A member of the `features` array
+enum Parsed {
+    /// Feature with geometry, and whether it mixes 2D and 3D positions
+    Feature(Feature, bool),
+    /// Properties of a feature without geometry
+    AttributeOnly(serde_json::Map<String, serde_json::Value>),
+}
+
+/// Parse a captured member, failing only on JSON syntax errors (`None` for invalid GeoJSON)
+fn parse_feature_bytes(bytes: &[u8], bbox: BboxProperties) -> Result<Option<Parsed>, serde_json::Error> {
+    let value: serde_json::Value = serde_json::from_slice(bytes)?;
+    let Ok(feature) = geojson::Feature::from_json_value(value) else {
+        return Ok(None);
+    };
+    let Some(geometry) = &feature.geometry else {
+        return Ok(Some(Parsed::AttributeOnly(feature.properties.unwrap_or_default())));
+    };
+    let mixed = has_mixed_dimensions(geometry);
+    Ok(parse_feature(feature, bbox).ok().map(|feature| Parsed::Feature(feature, mixed)))
+}
+
+/// Iterator over the features of a FeatureCollection read from `R` (native only)
+#[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+pub struct FeatureReader<R: std::io::Read> {
+    reader: R,
+    parser: FeatureStreamParser,
+    pending: std::collections::VecDeque<Feature>,
+    buffer: Vec<u8>,
+    done: bool,
+}
+
+#[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+impl<R: std::io::Read> FeatureReader<R> {
+    /// Read chunk size
+    const CHUNK_SIZE: usize = 64 * 1024;
+
+    pub fn new(reader: R) -> Self {
+        Self::with_parser(reader, FeatureStreamParser::new())
+    }
+
+    /// Reader that parses features as the generate functions do with `options`
+    pub fn with_options(reader: R, options: &crate::TilerOptions) -> Self {
+        Self::with_parser(reader, FeatureStreamParser::with_options(options))
+    }
+
+    fn with_parser(reader: R, parser: FeatureStreamParser) -> Self {
+        Self {
+            reader,
+            parser,
+            pending: std::collections::VecDeque::new(),
+            buffer: vec![0; Self::CHUNK_SIZE],
+            done: false,
+        }
+    }
+
+    /// Largest number of bytes buffered for a single feature so far
+    pub fn max_buffered(&self) -> usize {
+        self.parser.max_buffered()
+    }
+
+    /// Number of features read so far whose geometry mixes 2D and 3D positions
+    pub fn mixed_dimensions(&self) -> usize {
+        self.parser.mixed_dimensions()
+    }
+
+    /// Take the properties of the features without geometry read since the last call
+    pub fn take_attribute_only(&mut self) -> Vec<serde_json::Map<String, serde_json::Value>> {
+        self.parser.take_attribute_only()
+    }
+}
+
+#[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+impl<R: std::io::Read> Iterator for FeatureReader<R> {
+    type Item = Result<Feature, ConverterError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(feature) = self.pending.pop_front() {
+                return Some(Ok(feature));
+            }
+            if self.done {
+                return None;
+            }
+
+            let read = match self.reader.read(&mut self.buffer) {
+                Ok(read) => read,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(ConverterError::Io(format!("Read error: {}", e))));
+                }
+            };
+
+            if read == 0 {
+                self.done = true;
+                if let Err(e) = self.parser.finish() {
+                    return Some(Err(e));
+                }
+                continue;
+            }
+
+            match self.parser.push(&self.buffer[..read]) {
+                Ok(features) => self.pending.extend(features),
+                Err(e) => {
+                    self.done = true;
+                    return Some(Err(e));
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLLECTION: &str = r#"{
+        "type": "FeatureCollection",
+        "name": "features [not the array]",
+        "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo \"}{\""}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[135.5, 34.7], [139.7, 35.7]]}, "properties": {}}
+        ]
+    }"#;
+
+    #[test]
+    fn test_stream_parser_handles_arbitrary_chunks() {
+        for chunk_size in [1, 7, 64, COLLECTION.len()] {
+            let mut parser = FeatureStreamParser::new();
+            let mut features = Vec::new();
+            for chunk in COLLECTION.as_bytes().chunks(chunk_size) {
+                features.extend(parser.push(chunk).unwrap());
+            }
+            parser.finish().unwrap();
+
+            assert_eq!(features.len(), 2);
+            assert_eq!(features[0].properties["name"], "Tokyo \"}{\"");
+        }
+    }
+
+    #[test]
+    fn test_stream_parser_rejects_truncated_input() {
+        let mut parser = FeatureStreamParser::new();
+        parser.push(&COLLECTION.as_bytes()[..COLLECTION.len() / 2]).unwrap();
+        assert!(parser.finish().is_err());
+    }
+
+    #[test]
+    fn test_stream_parser_errors() {
+        let push_all = |input: &str| {
+            let mut parser = FeatureStreamParser::new();
+            input.as_bytes().chunks(5).try_for_each(|chunk| parser.push(chunk).map(|_| ()))?;
+            parser.finish()
+        };
+        
+        // A syntax error is reported at the same byte as by the in-memory parser
+        let broken = r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}, "properties": {}}, {"type": "Feature", "geometry": null, "properties": {"a": 1,}}]}"#;
+        let Err(ConverterError::Parse(message)) = push_all(broken) else { panic!("syntax error expected") };
+        let Err(ConverterError::Parse(in_memory)) = crate::geojson_parser::parse_geojson(broken.as_bytes()) else {
+            panic!("syntax error expected")
+        };
+        let byte = |m: &str| m.split("byte ").nth(1).and_then(|r| r.split(|c: char| !c.is_ascii_digit()).next()).map(str::to_string);
+        assert!(message.starts_with("Invalid JSON at byte"), "{}", message);
+        assert_eq!(byte(&message), byte(&in_memory));
+        
+        // Valid JSON that is not a Feature is skipped
+        let invalid_geometry = r#"{"type": "FeatureCollection", "features": [{"type": "Feature", "geometry": {"type": "Point", "coordinates": "x"}, "properties": {}}, {"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}, "properties": {}}]}"#;
+        push_all(invalid_geometry).unwrap();
+        
+        // Only commas and whitespace may appear between features
+        for stray in [r#"[{"type": "Feature", "geometry": null, "properties": {}} 42]"#, "[{}{}]", "[,{}]", "[{},]", r#"["a"]"#] {
+            let input = format!(r#"{{"type": "FeatureCollection", "features": {}}}"#, stray);
+            let Err(ConverterError::Parse(message)) = push_all(&input) else { panic!("{} should be rejected", stray) };
+            assert!(message.contains("between features at byte"), "{}", message);
+        }
+    }
+    
+    /// Reader producing a FeatureCollection of `count` points on the fly
+    #[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+    struct SyntheticCollection {
+        count: usize,
+        next: usize,
+        pending: Vec<u8>,
+        finished: bool,
+    }
+
+    #[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+    impl std::io::Read for SyntheticCollection {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            while self.pending.is_empty() && !self.finished {
+                let chunk = if self.next == 0 {
+                    r#"{"type":"FeatureCollection","features":["#.to_string()
+                } else if self.next > self.count {
+                    self.finished = true;
+                    "]}".to_string()
+                } else {
+                    let i = self.next - 1;
+                    let lon = (i % 360) as f64 - 179.5;
+                    let lat = ((i * 7) % 170) as f64 - 84.5;
+                    format!(
+                        r#"{}{{"type":"Feature","geometry":{{"type":"Point","coordinates":[{},{}]}},"properties":{{"id":{}}}}}"#,
+                        if i > 0 { "," } else { "" },
+                        lon,
+                        lat,
+                        i
+                    )
+                };
+                self.next += 1;
+                self.pending = chunk.into_bytes();
+            }
+            let n = buf.len().min(self.pending.len());
+            buf[..n].copy_from_slice(&self.pending[..n]);
+            self.pending.drain(..n);
+            Ok(n)
+        }
+    }
+
+    #[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_feature_reader_bounded_memory() {
+        let count = 200_000;
+        let mut reader = FeatureReader::new(SyntheticCollection {
+            count,
+            next: 0,
+            pending: Vec::new(),
+            finished: false,
+        });
+
+        let mut seen = 0;
+        for feature in reader.by_ref() {
+            feature.unwrap();
+            seen += 1;
+        }
+
+        assert_eq!(seen, count);
+        // ~18MB of input, but never more than one feature buffered at a time
+        assert!(reader.max_buffered() < 256);
+    }
+
+    #[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_generate_tiles_from_reader_matches_in_memory() {
+        let options = crate::TilerOptions::default();
+        let (mut streamed, streamed_metadata) =
+            crate::generate_tiles_from_reader(COLLECTION.as_bytes(), 0, 4, "layer", &options).unwrap();
+        let (mut in_memory, metadata) =
+            crate::generate_tiles_with_options(COLLECTION.as_bytes(), 0, 4, "layer", &options).unwrap();
+
+        streamed.sort_by(|a, b| a.path.cmp(&b.path));
+        in_memory.sort_by(|a, b| a.path.cmp(&b.path));
+
+        assert_eq!(streamed.len(), in_memory.len());
+        for (a, b) in streamed.iter().zip(&in_memory) {
+            assert_eq!(a.path, b.path);
+            assert_eq!(a.data, b.data);
+        }
+        assert_eq!(streamed_metadata.feature_count, metadata.feature_count);
+        assert_eq!(streamed_metadata.bounds, metadata.bounds);
+    }
+
+    #[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_generate_tiles_from_reader_options() {
+        let collection = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.6, 35.6, 10.0], [139.7, 35.65]]}, "properties": {}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {}}
+        ]}"#;
+        let options = crate::TilerOptions {
+            bbox_properties: BboxProperties::InputOrComputed,
+            ..Default::default()
+        };
+        let (tiles, metadata) = crate::generate_tiles_from_reader(collection.as_bytes(), 0, 0, "layer", &options).unwrap();
+        assert_eq!(metadata.diagnostics.mixed_dimension_features, 1);
+        assert!(metadata.fields.contains_key("_minx"));
+        let (in_memory, _) = crate::generate_tiles_with_options(collection.as_bytes(), 0, 0, "layer", &options).unwrap();
+        assert_eq!(tiles[0].data, in_memory[0].data);
+
+        let strict = crate::TilerOptions {
+            strict_dimensions: true,
+            ..Default::default()
+        };
+        let result = crate::generate_tiles_from_reader(collection.as_bytes(), 0, 0, "layer", &strict);
+        assert!(matches!(result, Err(ConverterError::InvalidInput(_))));
+    }
+
+    #[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_generate_tiles_from_reader_counts_attribute_only() {
+        let collection = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": null, "properties": {"note": "survey"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {}},
+            {"type": "Feature", "geometry": null, "properties": {}}
+        ]}"#;
+        let options = crate::TilerOptions {
+            attribute_only_stats: true,
+            ..Default::default()
+        };
+        let (_, metadata) = crate::generate_tiles_from_reader(collection.as_bytes(), 0, 0, "layer", &options).unwrap();
+        let (_, in_memory) = crate::generate_tiles_with_options(collection.as_bytes(), 0, 0, "layer", &options).unwrap();
+        assert!(metadata.diagnostics.warnings.iter().any(|w| w.starts_with("2 feature(s) without geometry")));
+        assert_eq!(metadata.diagnostics.warnings, in_memory.diagnostics.warnings);
+        assert_eq!(metadata.fields, in_memory.fields);
+    }
+
+    #[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+    #[test]
+    fn test_generate_tiles_from_large_stream() {
+        let reader = SyntheticCollection {
+            count: 20_000,
+            next: 0,
+            pending: Vec::new(),
+            finished: false,
+        };
+        let (tiles, metadata) =
+            crate::generate_tiles_from_reader(reader, 0, 2, "points", &crate::TilerOptions::default()).unwrap();
+
+        assert_eq!(metadata.feature_count, 20_000);
+        // 1 + 4 + 16 tiles: the synthetic points cover the whole world
+        assert_eq!(tiles.len(), 21);
+    }
+}
@@ -0,0 +1,103 @@
+// Geometry module
+// Planar and geodesic ring areas shared by the tiler and the MVT encoder
+
+use geo_types::Coord;
+
+/// Mean Earth radius used for geodesic areas (WGS84 semi-major axis, as in WebMercator)
+const EARTH_RADIUS: f64 = 6378137.0;
+
+/// Signed planar ring area (shoelace formula)
+///
+/// Positive for counterclockwise rings with the Y axis pointing up. The ring may be
+/// closed (last point equal to the first) or open.
+pub fn ring_area(ring: &[(f64, f64)]) -> f64 {
+    shoelace(ring.len(), |i| ring[i])
+}
+
+/// Signed planar area of a ring of coordinates, as `ring_area` (e.g. in degrees)
+pub fn coord_ring_area(ring: &[Coord<f64>]) -> f64 {
+    shoelace(ring.len(), |i| (ring[i].x, ring[i].y))
+}
+
+/// Signed planar area of a ring of integer grid points, as `ring_area`
+///
+/// In tile coordinates, where the Y axis points down, positive means clockwise on screen
+/// (an exterior ring per MVT spec 4.3.4.4).
+pub fn grid_ring_area(ring: &[(i32, i32)]) -> f64 {
+    shoelace(ring.len(), |i| (ring[i].0 as f64, ring[i].1 as f64))
+}
+
+fn shoelace(len: usize, point: impl Fn(usize) -> (f64, f64)) -> f64 {
+    let mut sum = 0.0;
+    for i in 0..len {
+        let (x1, y1) = point(i);
+        let (x2, y2) = point((i + 1) % len);
+        sum += x1 * y2 - x2 * y1;
+    }
+    sum / 2.0
+}
+
+/// Signed geodesic area of a lon/lat ring in square meters
+///
+/// Uses the spherical approximation of Chamberlain & Duquette ("Some Algorithms for
+/// Polygons on a Sphere", 2007), as geojson-area and Turf do. Positive for
+/// counterclockwise rings, like `ring_area`; closed and open rings give the same result.
+pub fn geodesic_ring_area(ring: &[(f64, f64)]) -> f64 {
+    let ring = match ring {
+        [first, .., last] if first == last => &ring[..ring.len() - 1],
+        _ => ring,
+    };
+    let len = ring.len();
+    if len < 3 {
+        return 0.0;
+    }
+    
+    let mut sum = 0.0;
+    for i in 0..len {
+        let (prev_lon, _) = ring[(i + len - 1) % len];
+        let (_, lat) = ring[i];
+        let (next_lon, _) = ring[(i + 1) % len];
+        sum += (next_lon - prev_lon).to_radians() * lat.to_radians().sin();
+    }
+    -sum * EARTH_RADIUS * EARTH_RADIUS / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_unit_square_area() {
+        let ccw = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)];
+        assert_eq!(ring_area(&ccw), 1.0);
+        // Open rings give the same area
+        assert_eq!(ring_area(&ccw[..4]), 1.0);
+        
+        let mut cw = ccw;
+        cw.reverse();
+        assert_eq!(ring_area(&cw), -1.0);
+        
+        let coords: Vec<Coord<f64>> = ccw.iter().map(|&(x, y)| Coord { x, y }).collect();
+        assert_eq!(coord_ring_area(&coords), 1.0);
+        assert_eq!(grid_ring_area(&[(0, 0), (1, 0), (1, 1), (0, 1)]), 1.0);
+        assert_eq!(ring_area(&ccw[..2]), 0.0);
+    }
+    
+    #[test]
+    fn test_geodesic_area() {
+        // A 1° × 1° cell on the equator: R² · Δλ · (sin 1° − sin 0°) ≈ 12391 km²
+        let cell = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0), (0.0, 0.0)];
+        let expected = EARTH_RADIUS * EARTH_RADIUS * 1f64.to_radians() * 1f64.to_radians().sin();
+        let area = geodesic_ring_area(&cell);
+        assert!((area - expected).abs() / expected < 1e-9, "{} vs {}", area, expected);
+        assert!((area / 1e6 - 12391.4).abs() < 1.0, "{}", area / 1e6);
+        
+        let mut clockwise = cell;
+        clockwise.reverse();
+        assert_eq!(geodesic_ring_area(&clockwise), -area);
+        
+        // The same cell near the pole is much smaller than in degrees
+        let polar = [(0.0, 80.0), (1.0, 80.0), (1.0, 81.0), (0.0, 81.0)];
+        assert!(geodesic_ring_area(&polar) < area / 5.0);
+    }
+}
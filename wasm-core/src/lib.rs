@@ -1,8 +1,19 @@
 // Vector Tile Core Library
 // Rust implementation for generating vector tiles (.pbf) in the browser
 
+pub mod checksum;
+pub mod csv_join;
+pub mod date;
+pub mod diagnostics;
+pub mod error;
 pub mod geojson_parser;
+pub mod geojson_stream;
+pub mod geometry;
+pub mod mbtiles;
+pub mod memory;
 pub mod projection;
+pub mod shapefile_parser;
+pub mod simplify;
 pub mod tiler;
 pub mod mvt_encoder;
 pub mod pmtiles_encoder;
@@ -10,6 +21,8 @@ pub mod pmtiles_encoder;
 #[cfg(target_arch = "wasm32")]
 pub mod wasm_api;
 
+pub use error::ConverterError;
+
 /// Tile coordinate structure
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub struct TileCoord {
@@ -26,15 +39,70 @@ impl TileCoord {
     pub fn to_path(&self) -> String {
         format!("{}/{}/{}.pbf", self.z, self.x, self.y)
     }
+    
+    /// Tile one zoom level up that covers this one (`None` at zoom 0)
+    pub fn parent(&self) -> Option<TileCoord> {
+        let z = self.z.checked_sub(1)?;
+        Some(TileCoord::new(z, self.x / 2, self.y / 2))
+    }
+    
+    /// The four tiles one zoom level down, in (x, y) order: NW, NE, SW, SE
+    /// (`None` when the next zoom level or its coordinates would overflow)
+    pub fn children(&self) -> Option<[TileCoord; 4]> {
+        let z = self.z.checked_add(1)?;
+        let x = self.x.checked_mul(2)?;
+        let y = self.y.checked_mul(2)?;
+        Some([
+            TileCoord::new(z, x, y),
+            TileCoord::new(z, x + 1, y),
+            TileCoord::new(z, x, y + 1),
+            TileCoord::new(z, x + 1, y + 1),
+        ])
+    }
+    
+    /// Whether `other` is this tile or lies within it at a higher zoom level
+    pub fn contains(&self, other: &TileCoord) -> bool {
+        let Some(dz) = other.z.checked_sub(self.z) else {
+            return false;
+        };
+        let ancestor = |v: u32| v.checked_shr(dz as u32).unwrap_or(0);
+        ancestor(other.x) == self.x && ancestor(other.y) == self.y
+    }
+    
+    /// Size record of the tile at this coordinate, for the diagnostics
+    pub(crate) fn weight(&self, bytes: usize, features: usize) -> diagnostics::TileWeight {
+        diagnostics::TileWeight { z: self.z, x: self.x, y: self.y, bytes, features }
+    }
 }
 
 /// Tile file structure
+/// 
+/// `data` holds the raw (uncompressed) MVT protobuf bytes, except from
+/// `generate_tile_tree` with `compress_tiles` set, where it is gzipped.
 #[derive(Debug, Clone)]
 pub struct TileFile {
     pub path: String,
     pub data: Vec<u8>,
 }
 
+impl TileFile {
+    /// Zoom level, read from the `z/x/y` path
+    pub fn zoom(&self) -> Option<u8> {
+        self.path.split('/').next()?.parse().ok()
+    }
+}
+
+/// Indices of `tiles` grouped by zoom level (ascending), keeping the tile order within a zoom
+pub fn index_tiles_by_zoom(tiles: &[TileFile]) -> std::collections::BTreeMap<u8, Vec<usize>> {
+    let mut by_zoom: std::collections::BTreeMap<u8, Vec<usize>> = std::collections::BTreeMap::new();
+    for (index, tile) in tiles.iter().enumerate() {
+        if let Some(zoom) = tile.zoom() {
+            by_zoom.entry(zoom).or_default().push(index);
+        }
+    }
+    by_zoom
+}
+
 /// Tile metadata (for TileJSON generation)
 #[derive(Debug, Clone)]
 pub struct TileMetadata {
@@ -43,199 +111,1430 @@ pub struct TileMetadata {
     pub layer_name: String,
     pub bounds: (f64, f64, f64, f64), // (min_lon, min_lat, max_lon, max_lat)
     pub center: (f64, f64),            // (center_lon, center_lat)
+    pub center_zoom: u8,               // Zoom at which the data roughly fills the view
     pub feature_count: usize,          // Total number of features
-    pub geometry_type: String,         // Most common geometry type: "Point", "LineString", or "Polygon"
+    pub geometry_type: String,         // Most common geometry type over all layers: "Point", "LineString", or "Polygon"
     pub fields: std::collections::HashMap<String, String>, // Field name -> type mapping
     pub attributes: Vec<serde_json::Value>, // Attribute statistics
+    pub diagnostics: diagnostics::Diagnostics, // Warnings and suggestions from the run
+    pub tileset_type: pmtiles_encoder::TilesetType, // TileJSON `type`: overlay or baselayer
+    pub layers: Vec<LayerMetadata>, // Per-layer statistics with `layer_by_field` (empty: one layer)
+    pub bounds_precision: usize,    // Decimals of formatted bounds strings
+    pub generator: Option<String>,  // TileJSON `generator` (None: this crate)
+    pub generator_options: Option<String>, // TileJSON `generator_options` (None: derived from the layer name)
+    pub attribution: Option<String>, // TileJSON `attribution` (None: omitted)
+    pub tilestats: bool,             // Write TileJSON `tilestats`
+}
+
+impl Default for TileMetadata {
+    /// Metadata of an empty whole-world tileset at zoom 0 with the default output settings
+    fn default() -> Self {
+        let max_lat = projection::MAX_LATITUDE;
+        Self {
+            min_zoom: 0,
+            max_zoom: 0,
+            layer_name: String::new(),
+            bounds: (-180.0, -max_lat, 180.0, max_lat),
+            center: (0.0, 0.0),
+            center_zoom: 0,
+            feature_count: 0,
+            geometry_type: "Point".to_string(),
+            fields: std::collections::HashMap::new(),
+            attributes: Vec::new(),
+            diagnostics: Default::default(),
+            tileset_type: Default::default(),
+            layers: Vec::new(),
+            bounds_precision: DEFAULT_BOUNDS_PRECISION,
+            generator: None,
+            generator_options: None,
+            attribution: None,
+            tilestats: true,
+        }
+    }
+}
+
+impl TileMetadata {
+    /// The layers described in the TileJSON `vector_layers` and `tilestats`
+    /// 
+    /// Without per-layer statistics, this is the single layer `layer_name` with the
+    /// dataset-wide fields and attributes.
+    pub fn output_layers(&self) -> Vec<LayerMetadata> {
+        if !self.layers.is_empty() {
+            return self.layers.clone();
+        }
+        vec![LayerMetadata {
+            name: self.layer_name.clone(),
+            feature_count: self.feature_count,
+            geometry_type: self.geometry_type.clone(),
+            fields: self.fields.clone(),
+            attributes: self.attributes.clone(),
+            source: self.layer_name.clone(),
+            description: String::new(),
+            zooms: None,
+        }]
+    }
+}
+
+/// Statistics of one output layer (for TileJSON generation)
+#[derive(Debug, Clone)]
+pub struct LayerMetadata {
+    pub name: String,
+    pub feature_count: usize,
+    pub geometry_type: String,
+    pub fields: std::collections::HashMap<String, String>,
+    pub attributes: Vec<serde_json::Value>,
+    /// Input the layer was generated from, so viewers can attribute layers: the layer
+    /// name given to the generate call, followed by `:<field>=<value>` for layers split
+    /// off by `layer_by_field`
+    pub source: String,
+    /// Human-readable description (`vector_layers[].description`)
+    pub description: String,
+    /// Zoom levels the layer appears in (None: all zooms of the tileset)
+    pub zooms: Option<(u8, u8)>,
+}
+
+/// Options controlling tile generation
+/// 
+/// `TilerOptions::default()` reproduces the behavior of the plain generate functions.
+/// Options can also be read from JSON with `TilerOptions::from_json`.
+#[derive(Debug, Clone, serde::Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TilerOptions {
+    /// Compression of tile data inside PMTiles archives (default: gzip)
+    pub tile_compression: pmtiles_encoder::TileCompression,
+    /// Gzip level per zoom range for PMTiles tile data, first matching range wins (default:
+    /// none, the default level everywhere); e.g. level 9 for rarely fetched low zooms
+    pub tile_compression_levels: Vec<pmtiles_encoder::ZoomCompressionLevel>,
+    /// Split lines and polygons crossing ±180° into western and eastern parts
+    pub split_antimeridian: bool,
+    /// How polygon rings are sorted into exteriors and holes (default: first ring is the exterior)
+    pub ring_classification: tiler::RingClassification,
+    /// Trim the layer name and replace inner whitespace with `_` before validating it (default: true)
+    pub sanitize_layer_name: bool,
+    /// Order of features within each tile (default: input order)
+    pub feature_order: mvt_encoder::FeatureOrder,
+    /// How array and object property values are written (default: JSON strings)
+    pub nested_values: mvt_encoder::NestedValues,
+    /// Whether `null` property values are written as tags (default: omitted)
+    pub null_values: mvt_encoder::NullValues,
+    /// How ISO 8601 date strings in properties are written; when converted, their field
+    /// type is "Date" (default: kept as strings, no detection)
+    pub date_values: mvt_encoder::DateValues,
+    /// Maximum number of unique values listed per attribute in the metadata (default: 100)
+    pub max_unique_values: usize,
+    /// Estimated tile count above which a lower max zoom is suggested in the diagnostics
+    pub max_estimated_tiles: u64,
+    /// Clamp max zoom to the suggested value instead of only warning (default: false)
+    pub auto_cap_max_zoom: bool,
+    /// Record a short hash of the tile data as `content_hash` in the PMTiles metadata
+    pub embed_content_hash: bool,
+    /// MVT version written to layers (default: 2)
+    pub mvt_version: mvt_encoder::MvtVersion,
+    /// Check each encoded geometry and drop features with degenerate commands (default: false)
+    pub validate_geometry: bool,
+    /// Douglas–Peucker tolerance in tile grid units (of 4096); 0 disables simplification
+    pub simplify_tolerance: f64,
+    /// Simplify boundaries shared by polygons once, so neighbors stay gap-free (default: false)
+    pub preserve_shared_boundaries: bool,
+    /// Clip tile geometry to the extent grown by this many grid units on every side,
+    /// as the last step before encoding, for decoders that reject far-out coordinates
+    /// (default: none, geometry extends as far as the input)
+    pub clamp_margin: Option<u32>,
+    /// Number of projected coordinates memoized while tiling, so vertices shared by
+    /// neighboring features are projected once (default: 0, no cache)
+    pub projection_cache_size: usize,
+    /// Center zoom written to the metadata; `None` derives it from the data extent
+    pub center_zoom: Option<u8>,
+    /// TileJSON `type` written to the metadata (default: overlay)
+    pub tileset_type: pmtiles_encoder::TilesetType,
+    /// Decimals of the bounds strings in the metadata (`antimeridian_adjusted_bounds`,
+    /// MBTiles `bounds`); the PMTiles header always uses the unrounded bounds (default: 6)
+    pub bounds_precision: usize,
+    /// `generator` written to the TileJSON and MBTiles metadata (default: web-vector-tile-maker)
+    pub generator: Option<String>,
+    /// `generator_options` written to the TileJSON and MBTiles metadata
+    /// (default: `web-vector-tile-maker -o <layer>.pmtiles`)
+    pub generator_options: Option<String>,
+    /// Attribution (e.g. data copyright, may contain HTML) written to the TileJSON and
+    /// MBTiles metadata (default: none)
+    pub attribution: Option<String>,
+    /// Decimals of lon/lat coordinates in GeoJSON output such as `debug_dump_geojson` (default: 6)
+    pub coordinate_precision: usize,
+    /// Bounds (min_lon, min_lat, max_lon, max_lat) advertised in the metadata and PMTiles
+    /// header instead of the data extent; all features are still tiled
+    pub bounds_override: Option<(f64, f64, f64, f64)>,
+    /// Center (lon, lat, zoom) written to the metadata and PMTiles header instead of the
+    /// center of the bounds; takes precedence over `center_zoom`, and the zoom must lie
+    /// within the generated zoom range
+    pub center_override: Option<(f64, f64, u8)>,
+    /// Property keys to rename in tiles and metadata (source key -> output key)
+    pub rename_fields: std::collections::HashMap<String, String>,
+    /// Include properties of features without geometry in the attribute statistics
+    /// (they are never tiled; default: false)
+    pub attribute_only_stats: bool,
+    /// Sort features right after parsing by geometry type and a hash of their content, so
+    /// output does not depend on input order (default: false, input order; not applied
+    /// by the streaming reader)
+    pub canonical_feature_order: bool,
+    /// Reject in-memory inputs larger than this many bytes before parsing (default: no limit)
+    pub max_input_bytes: Option<usize>,
+    /// Encode polygon rings as LineStrings (no ClosePath, closing point kept), e.g. for
+    /// contour lines stored as polygons (default: false)
+    pub polygons_as_lines: bool,
+    /// Below this zoom, lines and polygons are written as their centroid point (e.g. for
+    /// labels), with full geometry from this zoom up (default: none, full geometry)
+    pub point_below_zoom: Option<u8>,
+    /// Zoom range (inclusive) in which the centroid of each polygon is also written, with
+    /// the polygon's properties, to a `<layer>_labels` point layer (default: none)
+    pub label_layer: Option<(u8, u8)>,
+    /// Grid cell size in tile units (of 4096) and highest zoom at which points are thinned
+    /// to the first point per cell and output layer, so dense clusters stop rendering as
+    /// blobs at low zoom; dropped points are counted in the diagnostics (default: none)
+    pub point_grid: Option<(u32, u8)>,
+    /// Property holding the lowest zoom at which a feature is tiled, e.g. `"minzoom"` for
+    /// level-of-detail control of labels (default: none); missing or non-numeric values
+    /// leave the feature unrestricted
+    pub min_zoom_property: Option<String>,
+    /// Property holding the highest zoom at which a feature is tiled, like
+    /// `min_zoom_property` (default: none)
+    pub max_zoom_property: Option<String>,
+    /// Write `tilestats` to the TileJSON and MBTiles metadata (default: true); without it,
+    /// unique field values are not collected, which saves time and memory on
+    /// high-cardinality data. `vector_layers` fields are written either way
+    pub tilestats: bool,
+    /// Property whose non-negative integer value becomes the MVT feature id (default: none,
+    /// ids are positions in the layer); features without such a value get no id
+    pub id_property: Option<String>,
+    /// Keep `id_property` as a tag after promoting it to the feature id (default: false)
+    pub keep_id_property: bool,
+    /// Sort features within each tile layer by their `id_property` id, for locality of
+    /// similar ids (default: false); takes precedence over `feature_order`, which still
+    /// orders features with equal or no ids (those come last)
+    pub order_by_id: bool,
+    /// Property whose value names the layer of each feature, instead of one fixed layer
+    /// (features without it use the layer name; default: none)
+    pub layer_by_field: Option<String>,
+    /// Gzip the tile bytes returned by `generate_tile_tree` (default: false, raw MVT)
+    pub compress_tiles: bool,
+    /// Compression of tiles inside ZIP exports (default: none; gzipped tiles are named `.pbf.gz`)
+    pub zip_tile_compression: pmtiles_encoder::TileCompression,
+    /// Address every missing tile within the bounds and zoom range of a PMTiles archive,
+    /// pointing at one shared empty MVT tile (default: false)
+    pub fill_empty_tiles: bool,
+    /// Merge features of one geometry type with identical properties within a tile layer
+    /// into one multi-geometry feature (default: false); it keeps the first feature's id
+    pub merge_identical_properties: bool,
+    /// Tile grid features are binned into (default: the standard WebMercator grid); PMTiles
+    /// and MBTiles output assume the default grid. Not available from JSON
+    #[serde(skip)]
+    pub grid: projection::GridSpec,
+    /// Buffer around each tile for points, in MVT grid units of the 4096 extent (default:
+    /// `DEFAULT_BUFFER`); a point within the buffer of a neighboring tile is added there too
+    pub buffer_points: u32,
+    /// Buffer around each tile for lines, as `buffer_points` (default: `DEFAULT_BUFFER`)
+    pub buffer_lines: u32,
+    /// Buffer around each tile for polygons, as `buffer_points` (default: `DEFAULT_BUFFER`)
+    pub buffer_polygons: u32,
+    /// Write PMTiles tile data ordered by tile id (default: true); when false, tile data
+    /// keeps the input order (e.g. for appending tiles) and the header says clustered=0
+    pub clustered: bool,
+    /// Drop LineStrings shorter than this many tile pixels at a zoom level, e.g. clipped
+    /// fragments at low zoom (default: 0.0, keep all); points and polygons are unaffected
+    pub min_line_length_px: f64,
+    /// Polygon in lon/lat that features are clipped to before tiling; features outside
+    /// it are dropped (default: none). See `tiler::clip_to_mask` for the clipping rules.
+    /// Not available from JSON
+    #[serde(skip)]
+    pub clip_mask: Option<geo_types::Polygon<f64>>,
+    /// Only tile features of this geometry kind, dropping (and counting) the rest, e.g.
+    /// stray points in a polygon layer (default: none, keep all)
+    pub keep_geometry_type: Option<tiler::GeometryKind>,
+    /// Input coordinates are EPSG:3857 WebMercator meters instead of lon/lat (default:
+    /// false); they are inverse-projected right after parsing, so bounds, metadata and
+    /// lon/lat options such as `clip_mask` work in degrees as usual
+    pub input_is_3857: bool,
+    /// Below this zoom, tiles carry geometry only, without property tags, to keep overview
+    /// tiles small; the metadata still lists all fields (default: 0, tags at every zoom)
+    pub attributes_min_zoom: u8,
+    /// Which features get their GeoJSON `bbox` (or computed bounds) as `_minx`, `_miny`,
+    /// `_maxx` and `_maxy` number properties, in input coordinates (default: none)
+    pub bbox_properties: geojson_parser::BboxProperties,
+    /// Number of unique values in one tile layer above which a warning is added to the
+    /// diagnostics (default: 65536)
+    pub max_tile_values: usize,
+    /// Placeholder string written instead of string values that occur once in a tile
+    /// layer exceeding `max_tile_values` (default: none, values are kept)
+    pub value_overflow_placeholder: Option<String>,
+    /// Template of the layer name per zoom level, with `{layer}` replaced by the layer
+    /// name and `{z}` by the zoom, e.g. `"{layer}_z{z}"` for debugging overzoom; the
+    /// metadata lists one layer per zoom (default: none, the same name at every zoom)
+    pub layer_name_template: Option<String>,
+    /// Description of each layer by name, for `vector_layers[].description` in the
+    /// TileJSON and MBTiles metadata (default: none, empty descriptions)
+    pub layer_descriptions: std::collections::HashMap<String, String>,
+    /// Fail on features whose geometry mixes 2D and 3D positions, which often indicates
+    /// corrupted data (default: false, such features are counted in the diagnostics)
+    pub strict_dimensions: bool,
+}
+
+impl Default for TilerOptions {
+    fn default() -> Self {
+        Self {
+            tile_compression: pmtiles_encoder::TileCompression::default(),
+            tile_compression_levels: Vec::new(),
+            split_antimeridian: false,
+            ring_classification: tiler::RingClassification::default(),
+            sanitize_layer_name: true,
+            feature_order: mvt_encoder::FeatureOrder::default(),
+            nested_values: mvt_encoder::NestedValues::default(),
+            null_values: mvt_encoder::NullValues::default(),
+            date_values: mvt_encoder::DateValues::default(),
+            max_unique_values: DEFAULT_MAX_UNIQUE_VALUES,
+            max_estimated_tiles: diagnostics::DEFAULT_MAX_ESTIMATED_TILES,
+            auto_cap_max_zoom: false,
+            embed_content_hash: false,
+            mvt_version: mvt_encoder::MvtVersion::default(),
+            validate_geometry: false,
+            simplify_tolerance: 0.0,
+            clamp_margin: None,
+            preserve_shared_boundaries: false,
+            projection_cache_size: 0,
+            center_zoom: None,
+            tileset_type: pmtiles_encoder::TilesetType::default(),
+            bounds_precision: DEFAULT_BOUNDS_PRECISION,
+            generator: None,
+            generator_options: None,
+            attribution: None,
+            coordinate_precision: DEFAULT_COORDINATE_PRECISION,
+            bounds_override: None,
+            center_override: None,
+            rename_fields: std::collections::HashMap::new(),
+            attribute_only_stats: false,
+            canonical_feature_order: false,
+            max_input_bytes: None,
+            polygons_as_lines: false,
+            point_below_zoom: None,
+            label_layer: None,
+            point_grid: None,
+            min_zoom_property: None,
+            max_zoom_property: None,
+            tilestats: true,
+            id_property: None,
+            keep_id_property: false,
+            order_by_id: false,
+            layer_by_field: None,
+            compress_tiles: false,
+            zip_tile_compression: pmtiles_encoder::TileCompression::None,
+            clustered: true,
+            fill_empty_tiles: false,
+            merge_identical_properties: false,
+            grid: projection::GridSpec::default(),
+            buffer_points: DEFAULT_BUFFER,
+            buffer_lines: DEFAULT_BUFFER,
+            buffer_polygons: DEFAULT_BUFFER,
+            min_line_length_px: 0.0,
+            clip_mask: None,
+            keep_geometry_type: None,
+            input_is_3857: false,
+            attributes_min_zoom: 0,
+            bbox_properties: geojson_parser::BboxProperties::Off,
+            max_tile_values: DEFAULT_MAX_TILE_VALUES,
+            value_overflow_placeholder: None,
+            layer_name_template: None,
+            layer_descriptions: std::collections::HashMap::new(),
+            strict_dimensions: false,
+        }
+    }
+}
+
+impl TilerOptions {
+    /// Read options from a JSON object keyed by field name, e.g. `{"clustered": false}`
+    /// 
+    /// Missing fields keep their default and unknown fields are rejected. Enum values are
+    /// snake_case names, e.g. `"gzip"`, or objects for variants with a value, e.g.
+    /// `{"attribute": "rank"}` for `feature_order`.
+    pub fn from_json(json: &str) -> Result<Self, ConverterError> {
+        serde_json::from_str(json).map_err(|e| ConverterError::InvalidInput(format!("Invalid options: {}", e)))
+    }
+}
+
+/// Validate a layer name
+/// 
+/// Allowed characters are letters and digits (including non-ASCII letters such as
+/// Japanese), `_` and `-`. Empty names are rejected, as are whitespace and `.`
+/// (which would clash with the `<name>.pmtiles` naming in the metadata).
+pub fn validate_layer_name(name: &str) -> Result<(), ConverterError> {
+    if name.is_empty() {
+        return Err(ConverterError::InvalidInput("Layer name must not be empty".to_string()));
+    }
+    
+    if let Some(c) = name.chars().find(|c| !(c.is_alphanumeric() || *c == '_' || *c == '-')) {
+        return Err(ConverterError::InvalidInput(format!(
+            "Layer name {:?} contains {:?}; only letters, digits, '_' and '-' are allowed",
+            name, c
+        )));
+    }
+    
+    Ok(())
+}
+
+/// Trim a layer name and replace runs of inner whitespace with `_`
+pub fn sanitize_layer_name(name: &str) -> String {
+    name.split_whitespace().collect::<Vec<_>>().join("_")
+}
+
+/// Apply the configured sanitizing to a layer name and validate the result
+pub(crate) fn resolve_layer_name(name: &str, options: &TilerOptions) -> Result<String, ConverterError> {
+    let name = if options.sanitize_layer_name {
+        sanitize_layer_name(name)
+    } else {
+        name.to_string()
+    };
+    validate_layer_name(&name)?;
+    if options.layer_name_template.is_some() {
+        validate_layer_name(&mvt_encoder::zoom_layer_name(&name, 0, options))?;
+    }
+    Ok(name)
+}
+
+/// Copies of `layers` for each zoom from `min_zoom` to `max_zoom`, named by
+/// `options.layer_name_template`
+/// 
+/// Layers limited to some zooms (label layers) are only copied for those.
+fn zoom_layers(layers: Vec<LayerMetadata>, min_zoom: u8, max_zoom: u8, options: &TilerOptions) -> Vec<LayerMetadata> {
+    (min_zoom..=max_zoom)
+        .flat_map(|zoom| {
+            layers
+                .iter()
+                .filter(move |layer| layer.zooms.is_none_or(|(min, max)| (min..=max).contains(&zoom)))
+                .map(move |layer| LayerMetadata {
+                    name: mvt_encoder::zoom_layer_name(&layer.name, zoom, options),
+                    zooms: Some((zoom, zoom)),
+                    ..layer.clone()
+                })
+        })
+        .collect()
+}
+
+/// The `<layer>_labels` point layer written for the polygons of `layer` with
+/// `options.label_layer`, if it has polygons and the label zooms overlap the tileset's
+fn label_layer(
+    layer: &LayerMetadata,
+    polygon_count: usize,
+    min_zoom: u8,
+    max_zoom: u8,
+    options: &TilerOptions,
+) -> Option<LayerMetadata> {
+    let (label_min, label_max) = options.label_layer?;
+    let zooms = (label_min.max(min_zoom), label_max.min(max_zoom));
+    if polygon_count == 0 || zooms.0 > zooms.1 {
+        return None;
+    }
+    Some(LayerMetadata {
+        name: format!("{}_labels", layer.name),
+        feature_count: polygon_count,
+        geometry_type: "Point".to_string(),
+        zooms: Some(zooms),
+        ..layer.clone()
+    })
+}
+
+/// Fail with `strict_dimensions` if any feature mixes 2D and 3D positions
+fn check_dimensions(mixed_dimensions: usize, options: &TilerOptions) -> Result<(), ConverterError> {
+    if options.strict_dimensions && mixed_dimensions > 0 {
+        return Err(ConverterError::InvalidInput(format!(
+            "{} feature(s) mix 2D and 3D coordinates",
+            mixed_dimensions
+        )));
+    }
+    Ok(())
+}
+
+/// Parse in-memory GeoJSON input, applying the size limit and feature order of the options
+pub(crate) fn parse_input(geojson_bytes: &[u8], options: &TilerOptions) -> Result<geojson_parser::ParsedGeoJson, ConverterError> {
+    check_input_size(geojson_bytes.len(), options)?;
+    let mut parsed = geojson_parser::parse_geojson_with_bbox(geojson_bytes, options.bbox_properties)?;
+    check_dimensions(parsed.mixed_dimensions, options)?;
+    if options.input_is_3857 {
+        geojson_parser::unproject_features(&mut parsed.features)?;
+    }
+    if options.canonical_feature_order {
+        geojson_parser::sort_features_canonically(&mut parsed.features);
+    }
+    Ok(parsed)
+}
+
+/// Check the input size (in bytes) against `options.max_input_bytes`
+fn check_input_size(size: usize, options: &TilerOptions) -> Result<(), ConverterError> {
+    match options.max_input_bytes {
+        Some(limit) if size > limit => Err(ConverterError::TooLarge(format!(
+            "Input is {} bytes, more than the limit of {} bytes",
+            size,
+            limit
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// Highest zoom level accepted by the generate functions; beyond it, tiles are smaller
+/// than the precision of typical input data
+pub const MAX_ZOOM: u8 = 24;
+
+/// Check that a zoom range is not inverted and stays within `MAX_ZOOM`
+fn check_zoom_range(min_zoom: u8, max_zoom: u8) -> Result<(), ConverterError> {
+    if min_zoom > max_zoom {
+        return Err(ConverterError::InvalidInput(format!(
+            "Min zoom {} is above max zoom {}",
+            min_zoom, max_zoom
+        )));
+    }
+    if max_zoom > MAX_ZOOM {
+        return Err(ConverterError::InvalidInput(format!(
+            "Max zoom {} exceeds the maximum of {}",
+            max_zoom, MAX_ZOOM
+        )));
+    }
+    Ok(())
+}
+
+/// Default maximum number of unique values listed per attribute (like tippecanoe)
+pub const DEFAULT_MAX_UNIQUE_VALUES: usize = 100;
+
+/// Default number of decimals of formatted bounds (like tippecanoe)
+pub const DEFAULT_BOUNDS_PRECISION: usize = 6;
+
+/// Default number of decimals of lon/lat in GeoJSON output (about 11 cm)
+pub const DEFAULT_COORDINATE_PRECISION: usize = 6;
+
+/// Default limit on the unique values of one tile layer before a warning is added
+pub const DEFAULT_MAX_TILE_VALUES: usize = 65536;
+
+/// Default tile buffer of every geometry type in MVT grid units (none: features are
+/// only added to the tiles their bounds touch)
+pub const DEFAULT_BUFFER: u32 = 0;
+
+/// Incrementally collected property statistics
+/// 
+/// Only the smallest `options.max_unique_values` unique values are retained per field,
+/// so memory stays bounded regardless of how many features are added.
+#[derive(Default)]
+struct PropertyStats {
+    fields: std::collections::HashMap<String, FieldStats>,
+    /// Options deciding how properties are written (nested values, renames)
+    options: TilerOptions,
+    /// Renamed keys dropped because another key had the same output name
+    collisions: std::collections::BTreeSet<(String, String)>,
 }
 
-/// Analyze properties from features to extract fields and attributes
-fn analyze_properties(features: &[geojson_parser::Feature]) -> (std::collections::HashMap<String, String>, Vec<serde_json::Value>) {
-    use std::collections::{HashMap, HashSet};
-    use serde_json::{json, Value};
-    
-    // Collect all field names and their types
-    let mut field_types: HashMap<String, HashSet<String>> = HashMap::new();
-    let mut field_values: HashMap<String, Vec<Value>> = HashMap::new();
-    
-    for feature in features {
-        for (key, value) in &feature.properties {
-            // Determine type
-            let value_type = match value {
-                Value::String(_) => "String",
-                Value::Number(_) => "Number",
-                Value::Bool(_) => "Boolean",
-                Value::Null => "String", // null is treated as String in tippecanoe
-                _ => "String",
+struct FieldStats {
+    types: std::collections::HashSet<&'static str>,
+    unique_values: std::collections::BTreeSet<String>,
+    /// Some unique values were dropped to stay within the limit
+    truncated: bool,
+    min: f64,
+    max: f64,
+}
+
+impl Default for FieldStats {
+    fn default() -> Self {
+        Self {
+            types: Default::default(),
+            unique_values: Default::default(),
+            truncated: false,
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+        }
+    }
+}
+
+impl PropertyStats {
+    /// Add the properties of one feature, as they will be written to tiles
+    fn add(&mut self, properties: &serde_json::Map<String, serde_json::Value>) {
+        let mut fields = std::mem::take(&mut self.fields);
+        let collisions = mvt_encoder::visit_properties(properties, &self.options, &mut |key, value, is_date| {
+            let stats = fields.entry(key.to_string()).or_default();
+            Self::add_value(stats, value, is_date, &self.options);
+        });
+        self.fields = fields;
+        self.collisions.extend(collisions);
+    }
+    
+    /// Warnings about properties that could not be written as configured
+    fn warnings(&self) -> Vec<String> {
+        self.collisions
+            .iter()
+            .map(|(key, output)| format!("Field {:?} dropped: another field is already written as {:?}", key, output))
+            .collect()
+    }
+    
+    fn add_value(stats: &mut FieldStats, value: &serde_json::Value, is_date: bool, options: &TilerOptions) {
+        use serde_json::Value;
+        
+        // Determine type
+        let value_type = match value {
+            _ if is_date => "Date",
+            Value::String(_) => "String",
+            Value::Number(_) => "Number",
+            Value::Bool(_) => "Boolean",
+            Value::Null => "String", // null is treated as String in tippecanoe
+            _ => "String",           // arrays and objects are written as JSON strings
+        };
+        stats.types.insert(value_type);
+        // Values and ranges only feed the tilestats attributes
+        if !options.tilestats {
+            return;
+        }
+        
+        // Collect unique values (keeping only the first ones in sort order)
+        let unique = if let Some(s) = value.as_str() {
+            s.to_string()
+        } else if let Some(n) = value.as_f64() {
+            stats.min = stats.min.min(n);
+            stats.max = stats.max.max(n);
+            n.to_string()
+        } else {
+            value.to_string()
+        };
+        stats.unique_values.insert(unique);
+        if stats.unique_values.len() > options.max_unique_values {
+            stats.unique_values.pop_last();
+            stats.truncated = true;
+        }
+    }
+    
+    /// Build the fields map (field name -> type) and the attributes array (empty without
+    /// `tilestats`)
+    fn finish(self) -> (std::collections::HashMap<String, String>, Vec<serde_json::Value>) {
+        use serde_json::json;
+        
+        let tilestats = self.options.tilestats;
+        let mut fields = std::collections::HashMap::new();
+        let mut attributes = Vec::new();
+        
+        for (key, stats) in self.fields {
+            // Use the single observed type, or "String" if multiple types
+            let field_type = if stats.types.len() == 1 {
+                stats.types.iter().next().unwrap().to_string()
+            } else {
+                "String".to_string()
             };
+            if !tilestats {
+                fields.insert(key, field_type);
+                continue;
+            }
+            // Dates are numbers when written as epoch seconds (the only case with a range)
+            let numeric = field_type == "Number" || (field_type == "Date" && stats.min.is_finite());
+            let attr_type = if numeric { "number" } else { "string" };
+            
+            let values_vec: Vec<String> = stats.unique_values.into_iter().collect();
+            let mut attribute = json!({
+                "attribute": key,
+                "count": values_vec.len(),
+                "type": attr_type,
+                "values": values_vec
+            });
+            
+            // Numeric fields also carry their range over all values (like tippecanoe)
+            if numeric && stats.min.is_finite() && stats.max.is_finite() {
+                attribute["min"] = json!(stats.min);
+                attribute["max"] = json!(stats.max);
+            }
             
-            field_types.entry(key.clone())
-                .or_insert_with(HashSet::new)
-                .insert(value_type.to_string());
+            // Flag value lists that do not hold every unique value
+            if stats.truncated {
+                attribute["truncated"] = json!(true);
+            }
             
-            // Collect values (for statistics)
-            field_values.entry(key.clone())
-                .or_insert_with(Vec::new)
-                .push(value.clone());
+            attributes.push(attribute);
+            fields.insert(key, field_type);
+        }
+        
+        // Sort attributes by field name
+        attributes.sort_by_key(|a| a["attribute"].as_str().unwrap_or("").to_string());
+        
+        (fields, attributes)
+    }
+}
+
+/// Feature count, geometry types and properties of the features of one layer
+#[derive(Default)]
+struct LayerStats {
+    feature_count: usize,
+    point_count: usize,
+    linestring_count: usize,
+    polygon_count: usize,
+    properties: PropertyStats,
+}
+
+impl LayerStats {
+    fn new(options: &TilerOptions) -> Self {
+        Self {
+            properties: PropertyStats {
+                options: options.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+    
+    fn add(&mut self, feature: &geojson_parser::Feature) {
+        self.feature_count += 1;
+        match feature.geometry {
+            geojson_parser::GeometryType::Point(_) => self.point_count += 1,
+            geojson_parser::GeometryType::LineString(_) => self.linestring_count += 1,
+            // Polygons encoded as lines are reported as lines
+            _ if self.properties.options.polygons_as_lines => self.linestring_count += 1,
+            geojson_parser::GeometryType::Polygon(_) => self.polygon_count += 1,
+            geojson_parser::GeometryType::MultiPolygon(_) => self.polygon_count += 1,
         }
+        self.properties.add(&feature.properties);
     }
     
-    // Build fields map (field name -> type)
-    let mut fields = HashMap::new();
-    for (key, types) in &field_types {
-        // Use the most common type, or "String" if multiple types
-        let field_type = if types.len() == 1 {
-            types.iter().next().unwrap().clone()
+    /// Most common geometry type: "Point", "LineString" or "Polygon"
+    fn geometry_type(&self) -> String {
+        if self.polygon_count >= self.point_count && self.polygon_count >= self.linestring_count {
+            "Polygon".to_string()
+        } else if self.linestring_count >= self.point_count {
+            "LineString".to_string()
         } else {
-            "String".to_string()
-        };
-        fields.insert(key.clone(), field_type);
-    }
-    
-    // Build attributes array (statistics for each field)
-    let mut attributes = Vec::new();
-    for (key, values) in &field_values {
-        // Collect unique values (up to a limit)
-        let mut unique_values = HashSet::new();
-        for value in values {
-            if let Some(s) = value.as_str() {
-                unique_values.insert(s.to_string());
-            } else if let Some(n) = value.as_f64() {
-                unique_values.insert(n.to_string());
-            } else if let Some(b) = value.as_bool() {
-                unique_values.insert(b.to_string());
-            } else if value.is_null() {
-                unique_values.insert("null".to_string());
+            "Point".to_string()
+        }
+    }
+}
+
+/// Incrementally collected dataset statistics used to build `TileMetadata`
+struct DatasetStats {
+    bounds: Option<(f64, f64, f64, f64)>,
+    /// Features without geometry (not tiled)
+    attribute_only_count: usize,
+    /// Statistics over all features
+    all: LayerStats,
+    /// Layer of features without the `layer_by_field` property
+    default_layer: String,
+    /// Statistics per layer, only collected with `layer_by_field`
+    layers: std::collections::BTreeMap<String, LayerStats>,
+    /// Bounds of the features other than points, for the tile count estimate
+    shape_bounds: Vec<(f64, f64, f64, f64)>,
+    /// Features dropped by `keep_geometry_type`
+    geometry_type_dropped: usize,
+    /// Features whose geometry mixes 2D and 3D positions
+    mixed_dimensions: usize,
+}
+
+impl DatasetStats {
+    fn new(options: &TilerOptions, default_layer: &str) -> Self {
+        Self {
+            bounds: None,
+            attribute_only_count: 0,
+            all: LayerStats::new(options),
+            default_layer: default_layer.to_string(),
+            layers: Default::default(),
+            shape_bounds: Vec::new(),
+            geometry_type_dropped: 0,
+            mixed_dimensions: 0,
+        }
+    }
+    
+    /// Add one feature (features dropped by `keep_geometry_type` are only counted)
+    fn add(&mut self, feature: &geojson_parser::Feature) -> Result<(), ConverterError> {
+        if !tiler::keeps_geometry_type(&feature.geometry, &self.all.properties.options) {
+            self.geometry_type_dropped += 1;
+            return Ok(());
+        }
+        let bounds = geojson_parser::feature_bounds(feature);
+        self.bounds = Some(geojson_parser::merge_bounds(
+            self.bounds.unwrap_or(geojson_parser::EMPTY_BOUNDS),
+            bounds,
+        ));
+        self.all.add(feature);
+        if !matches!(feature.geometry, geojson_parser::GeometryType::Point(_)) {
+            self.shape_bounds.push(bounds);
+        }
+        
+        let options = &self.all.properties.options;
+        if options.layer_by_field.is_some() {
+            let name = mvt_encoder::feature_layer_name(&feature.properties, options, &self.default_layer)?;
+            self.layers.entry(name).or_insert_with(|| LayerStats::new(options)).add(feature);
+        }
+        Ok(())
+    }
+    
+    /// Add the properties of a feature without geometry
+    /// 
+    /// They only count towards the attribute statistics when `attribute_only_stats` is set.
+    fn add_attribute_only(&mut self, properties: &serde_json::Map<String, serde_json::Value>) {
+        self.attribute_only_count += 1;
+        if self.all.properties.options.attribute_only_stats {
+            self.all.properties.add(properties);
+        }
+    }
+    
+    /// Check the zoom range and return the max zoom to generate (capped if requested)
+    fn preflight(&self, min_zoom: u8, max_zoom: u8, options: &TilerOptions) -> (u8, diagnostics::Diagnostics) {
+        let Some(bounds) = self.bounds else {
+            return (max_zoom, diagnostics::Diagnostics::default());
+        };
+        let points = self.all.feature_count - self.shape_bounds.len();
+        let diagnostics =
+            diagnostics::preflight(points, &self.shape_bounds, bounds, min_zoom, max_zoom, options.max_estimated_tiles);
+        let max_zoom = match diagnostics.suggested_max_zoom {
+            Some(suggested) if options.auto_cap_max_zoom => suggested,
+            _ => max_zoom,
+        };
+        (max_zoom, diagnostics)
+    }
+    
+    /// Build tile metadata from the collected statistics
+    fn into_metadata(
+        self,
+        min_zoom: u8,
+        max_zoom: u8,
+        layer_name: &str,
+        mut diagnostics: diagnostics::Diagnostics,
+        options: &TilerOptions,
+    ) -> Result<TileMetadata, ConverterError> {
+        let bounds = self.bounds.ok_or_else(|| ConverterError::EmptyInput("Features are empty".to_string()))?;
+        let bounds = match options.bounds_override {
+            Some(bounds) => validate_bounds(bounds)?,
+            None => bounds,
+        };
+        let (center, center_zoom) = match options.center_override {
+            Some(center) => {
+                let (lon, lat, zoom) = validate_center(center, min_zoom, max_zoom)?;
+                ((lon, lat), zoom)
+            }
+            None => {
+                // Zoom where the bounds fill the view (a single point is shown at max zoom)
+                let center_zoom = options.center_zoom.unwrap_or_else(|| match projection::fit_zoom(bounds) {
+                    Some(zoom) => zoom.floor().clamp(min_zoom as f64, max_zoom as f64) as u8,
+                    None => max_zoom,
+                });
+                (geojson_parser::calculate_center(bounds), center_zoom)
             }
+        };
+        
+        diagnostics.geometry_type_dropped = self.geometry_type_dropped;
+        if let Some(kind) = options.keep_geometry_type.filter(|_| self.geometry_type_dropped > 0) {
+            diagnostics.warnings.push(format!(
+                "{} feature(s) that are not {:?} geometries were dropped",
+                self.geometry_type_dropped, kind
+            ));
+        }
+        let geometry_type = self.all.geometry_type();
+        let description = |name: &str| options.layer_descriptions.get(name).cloned().unwrap_or_default();
+        let mut layers = Vec::new();
+        for (name, stats) in self.layers {
+            let geometry_type = stats.geometry_type();
+            let (fields, attributes) = stats.properties.finish();
+            let source = match &options.layer_by_field {
+                Some(field) if name != layer_name => format!("{}:{}={}", layer_name, field, name),
+                _ => layer_name.to_string(),
+            };
+            let layer = LayerMetadata {
+                description: description(&name),
+                name,
+                feature_count: stats.feature_count,
+                geometry_type,
+                fields,
+                attributes,
+                source,
+                zooms: None,
+            };
+            let labels = label_layer(&layer, stats.polygon_count, min_zoom, max_zoom, options);
+            layers.push(layer);
+            layers.extend(labels);
         }
         
-        // Limit to 100 unique values (like tippecanoe)
-        let mut values_vec: Vec<String> = unique_values.into_iter().collect();
-        values_vec.sort();
-        if values_vec.len() > 100 {
-            values_vec.truncate(100);
+        // Analyze properties to extract fields and attributes
+        if self.attribute_only_count > 0 {
+            diagnostics.warnings.push(format!(
+                "{} feature(s) without geometry were skipped",
+                self.attribute_only_count
+            ));
+        }
+        diagnostics.mixed_dimension_features = self.mixed_dimensions;
+        if self.mixed_dimensions > 0 {
+            diagnostics.warnings.push(format!(
+                "{} feature(s) mix 2D and 3D coordinates; z values were dropped",
+                self.mixed_dimensions
+            ));
         }
+        diagnostics.warnings.extend(self.all.properties.warnings());
+        let (fields, attributes) = self.all.properties.finish();
         
-        let field_type = fields.get(key).cloned().unwrap_or_else(|| "String".to_string());
-        let attr_type = if field_type == "Number" { "number" } else { "string" };
+        // The single layer is listed explicitly when it differs from the dataset-wide one
+        let explicit = options.layer_name_template.is_some()
+            || options.label_layer.is_some()
+            || !description(layer_name).is_empty();
+        if layers.is_empty() && explicit {
+            let layer = LayerMetadata {
+                name: layer_name.to_string(),
+                feature_count: self.all.feature_count,
+                geometry_type: geometry_type.clone(),
+                fields: fields.clone(),
+                attributes: attributes.clone(),
+                source: layer_name.to_string(),
+                description: description(layer_name),
+                zooms: None,
+            };
+            let labels = label_layer(&layer, self.all.polygon_count, min_zoom, max_zoom, options);
+            layers.push(layer);
+            layers.extend(labels);
+        }
+        // With a layer name template, every layer is listed once per zoom
+        if options.layer_name_template.is_some() {
+            layers = zoom_layers(layers, min_zoom, max_zoom, options);
+        }
         
-        attributes.push(json!({
-            "attribute": key,
-            "count": values_vec.len().min(100),
-            "type": attr_type,
-            "values": values_vec
-        }));
+        Ok(TileMetadata {
+            min_zoom,
+            max_zoom,
+            layer_name: layer_name.to_string(),
+            bounds,
+            center,
+            center_zoom,
+            feature_count: self.all.feature_count,
+            geometry_type,
+            fields,
+            attributes,
+            diagnostics,
+            tileset_type: options.tileset_type,
+            layers,
+            bounds_precision: options.bounds_precision,
+            generator: options.generator.clone(),
+            generator_options: options.generator_options.clone(),
+            attribution: options.attribution.clone(),
+            tilestats: options.tilestats,
+        })
     }
-    
-    // Sort attributes by field name
-    attributes.sort_by_key(|a| a["attribute"].as_str().unwrap_or("").to_string());
-    
-    (fields, attributes)
+}
+
+/// Check that `bounds` are finite lon/lat with min < max
+fn validate_bounds(bounds: (f64, f64, f64, f64)) -> Result<(f64, f64, f64, f64), ConverterError> {
+    let (min_lon, min_lat, max_lon, max_lat) = bounds;
+    let valid = [min_lon, min_lat, max_lon, max_lat].iter().all(|v| v.is_finite())
+        && (-180.0..=180.0).contains(&min_lon)
+        && (-180.0..=180.0).contains(&max_lon)
+        && (-90.0..=90.0).contains(&min_lat)
+        && (-90.0..=90.0).contains(&max_lat)
+        && min_lon < max_lon
+        && min_lat < max_lat;
+    if !valid {
+        return Err(ConverterError::InvalidInput(format!(
+            "Invalid bounds override {:?}: expected min_lon < max_lon and min_lat < max_lat in degrees",
+            bounds
+        )));
+    }
+    Ok(bounds)
+}
+
+/// Validate a center override (lon, lat, zoom) against the generated zoom range
+fn validate_center(center: (f64, f64, u8), min_zoom: u8, max_zoom: u8) -> Result<(f64, f64, u8), ConverterError> {
+    let (lon, lat, zoom) = center;
+    let valid = (-180.0..=180.0).contains(&lon) && (-90.0..=90.0).contains(&lat) && (min_zoom..=max_zoom).contains(&zoom);
+    if !valid {
+        return Err(ConverterError::InvalidInput(format!(
+            "Invalid center override {:?}: expected lon/lat in degrees and a zoom within {}-{}",
+            center, min_zoom, max_zoom
+        )));
+    }
+    Ok(center)
 }
 
 /// Main tile generation function (with metadata)
+/// 
+/// Fails with `InvalidInput` when `min_zoom` is above `max_zoom` or `max_zoom` exceeds
+/// `MAX_ZOOM`.
 pub fn generate_tiles_with_metadata(
     geojson_bytes: &[u8],
     min_zoom: u8,
     max_zoom: u8,
     layer_name: &str,
-) -> Result<(Vec<TileFile>, TileMetadata), String> {
-    // 1. Parse GeoJSON
-    let features = geojson_parser::parse_geojson(geojson_bytes)?;
+) -> Result<(Vec<TileFile>, TileMetadata), ConverterError> {
+    generate_tiles_with_options(geojson_bytes, min_zoom, max_zoom, layer_name, &TilerOptions::default())
+}
+
+/// Main tile generation function (with metadata and options)
+pub fn generate_tiles_with_options(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<(Vec<TileFile>, TileMetadata), ConverterError> {
+    check_zoom_range(min_zoom, max_zoom)?;
+    let layer_name = &resolve_layer_name(layer_name, options)?;
     
-    // 2. Calculate metadata
-    let bounds = geojson_parser::calculate_bounds(&features)?;
-    let center = geojson_parser::calculate_center(bounds);
+    // 1. Parse GeoJSON
+    let parsed = parse_input(geojson_bytes, options)?;
+    memory::checkpoint("parsed");
+    generate_tiles_from_parsed(parsed, min_zoom, max_zoom, layer_name, options)
+}
+
+/// Generate tiles from a shapefile (`.shp`, `.dbf` and optional `.prj`/`.cpg`)
+/// 
+/// Same as `generate_tiles_with_options` for GeoJSON; `options.max_input_bytes` applies
+/// to the `.shp` and `.dbf` together. The `.prj` decides the CRS, so `input_is_3857` and
+/// `bbox_properties` do not apply. Null shapes count as features without geometry.
+pub fn generate_tiles_from_shapefile(
+    sources: &shapefile_parser::ShapefileSources,
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<(Vec<TileFile>, TileMetadata), ConverterError> {
+    check_zoom_range(min_zoom, max_zoom)?;
+    let layer_name = &resolve_layer_name(layer_name, options)?;
+    check_input_size(sources.shp.len().saturating_add(sources.dbf.len()), options)?;
     
-    // Determine most common geometry type
-    let mut point_count = 0;
-    let mut linestring_count = 0;
-    let mut polygon_count = 0;
+    let mut parsed = shapefile_parser::read_shapefile(sources)?;
+    if options.canonical_feature_order {
+        geojson_parser::sort_features_canonically(&mut parsed.features);
+    }
+    memory::checkpoint("parsed");
+    generate_tiles_from_parsed(parsed, min_zoom, max_zoom, layer_name, options)
+}
+
+/// Tile parsed input; `layer_name` is already resolved
+fn generate_tiles_from_parsed(
+    parsed: geojson_parser::ParsedGeoJson,
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<(Vec<TileFile>, TileMetadata), ConverterError> {
+    let features = parsed.features;
     
+    // 2. Calculate metadata
+    let mut stats = DatasetStats::new(options, layer_name);
     for feature in &features {
-        match feature.geometry {
-            geojson_parser::GeometryType::Point(_) => point_count += 1,
-            geojson_parser::GeometryType::LineString(_) => linestring_count += 1,
-            geojson_parser::GeometryType::Polygon(_) => polygon_count += 1,
-        }
+        stats.add(feature)?;
     }
-    
-    let geometry_type = if polygon_count >= point_count && polygon_count >= linestring_count {
-        "Polygon".to_string()
-    } else if linestring_count >= point_count {
-        "LineString".to_string()
-    } else {
-        "Point".to_string()
-    };
-    
-    // Analyze properties to extract fields and attributes
-    let (fields, attributes) = analyze_properties(&features);
-    
-    let metadata = TileMetadata {
-        min_zoom,
-        max_zoom,
-        layer_name: layer_name.to_string(),
-        bounds,
-        center,
-        feature_count: features.len(),
-        geometry_type,
-        fields,
-        attributes,
-    };
+    for properties in &parsed.attribute_only {
+        stats.add_attribute_only(properties);
+    }
+    stats.mixed_dimensions = parsed.mixed_dimensions;
+    let (max_zoom, diagnostics) = stats.preflight(min_zoom, max_zoom, options);
+    let mut metadata = stats.into_metadata(min_zoom, max_zoom, layer_name, diagnostics, options)?;
+    let features = tiler::clip_features_to_mask(features, options);
     
     // 3. Generate tiles for each zoom level
     let mut tile_files = Vec::new();
+    let mut short_lines = 0;
     
     for zoom in min_zoom..=max_zoom {
         // 4. Assign features to tiles
-        let tiles = tiler::tile_features(&features, zoom)?;
+        let tiles = tiler::tile_features_in(&features, zoom, None, options, &mut short_lines)?;
+        memory::checkpoint(&format!("tiled zoom {}", zoom));
         
         // 5. Encode each tile in MVT format
         for (coord, features) in tiles {
-            let mvt_data = mvt_encoder::encode_tile(&features, layer_name)?;
+            let encoded = mvt_encoder::encode_tile_with_stats(&features, layer_name, coord.z, options)?;
+            let weight = coord.weight(encoded.data.len(), features.len() - encoded.thinned - encoded.invalid);
+            metadata.diagnostics.record_tile(weight);
+            metadata.diagnostics.points_thinned += encoded.thinned as u64;
+            metadata.diagnostics.invalid_geometry_dropped += encoded.invalid as u64;
+            if encoded.values > options.max_tile_values {
+                metadata.diagnostics.record_value_overflow(weight, encoded.values, options.max_tile_values, encoded.coerced);
+            }
             tile_files.push(TileFile {
                 path: coord.to_path(),
-                data: mvt_data,
+                data: encoded.data,
             });
         }
+        memory::checkpoint(&format!("encoded zoom {}", zoom));
     }
+    metadata.diagnostics.record_short_lines(short_lines, options.min_line_length_px);
     
     Ok((tile_files, metadata))
 }
 
+/// Generate tiles from a GeoJSON FeatureCollection read incrementally (native only)
+/// 
+/// Features are pulled from `reader` one at a time and assigned to the tiles of every
+/// zoom level immediately, so the input is never materialized as a whole. Memory use
+/// is driven by the generated tiles, not by the size of the input file. Features without
+/// geometry are counted in the diagnostics like in `generate_tiles_with_options`.
+#[cfg(all(feature = "streaming", not(target_arch = "wasm32")))]
+pub fn generate_tiles_from_reader<R: std::io::Read>(
+    reader: R,
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<(Vec<TileFile>, TileMetadata), ConverterError> {
+    let mut generator = TileGenerator::new(min_zoom, max_zoom, layer_name, options)?;
+    let mut features = geojson_stream::FeatureReader::with_options(reader, options);
+    projection::with_projection_cache(options.projection_cache_size, || {
+        while let Some(feature) = features.next() {
+            let feature = feature?;
+            generator.stats.mixed_dimensions = features.mixed_dimensions();
+            check_dimensions(generator.stats.mixed_dimensions, options)?;
+            generator.add_feature(feature)?;
+            generator.add_attribute_only(features.take_attribute_only());
+        }
+        generator.add_attribute_only(features.take_attribute_only());
+        Ok::<_, ConverterError>(())
+    })?;
+    generator.encode()
+}
+
+/// Incremental tile generation from GeoJSON pushed in chunks
+/// 
+/// The input (a FeatureCollection) can be split anywhere, e.g. into the chunks of a JS
+/// `ReadableStream`. Each feature is assigned to the tiles of every zoom level as soon as
+/// it is complete, so only the feature being read is buffered; `finish` encodes the tiles.
+/// 
+/// The result matches `generate_tiles_with_options` on the concatenated input, except that
+/// `canonical_feature_order` is not applied: features keep their input order, as sorting
+/// them would need the whole input. `max_input_bytes` limits the total size of the pushed
+/// chunks, and with `auto_cap_max_zoom`, zoom levels above the cap are no longer tiled as
+/// soon as the features seen so far exceed `max_estimated_tiles`.
+pub struct TileGenerator {
+    min_zoom: u8,
+    max_zoom: u8,
+    /// Highest zoom level still tiled (below `max_zoom` once `auto_cap_max_zoom` applies)
+    capped_max_zoom: u8,
+    /// Total size of the pushed chunks
+    input_bytes: usize,
+    layer_name: String,
+    options: TilerOptions,
+    parser: geojson_stream::FeatureStreamParser,
+    stats: DatasetStats,
+    tiles_by_zoom: Vec<std::collections::HashMap<TileCoord, Vec<tiler::TileFeature>>>,
+    /// Lines dropped by `min_line_length_px` per zoom level, alongside `tiles_by_zoom`
+    short_lines_by_zoom: Vec<u64>,
+}
+
+impl TileGenerator {
+    pub fn new(min_zoom: u8, max_zoom: u8, layer_name: &str, options: &TilerOptions) -> Result<Self, ConverterError> {
+        check_zoom_range(min_zoom, max_zoom)?;
+        let layer_name = resolve_layer_name(layer_name, options)?;
+        Ok(Self {
+            min_zoom,
+            max_zoom,
+            capped_max_zoom: max_zoom,
+            input_bytes: 0,
+            stats: DatasetStats::new(options, &layer_name),
+            layer_name,
+            options: options.clone(),
+            parser: geojson_stream::FeatureStreamParser::with_options(options),
+            tiles_by_zoom: (min_zoom..=max_zoom).map(|_| Default::default()).collect(),
+            short_lines_by_zoom: vec![0; (max_zoom - min_zoom) as usize + 1],
+        })
+    }
+    
+    /// Feed the next chunk of input
+    pub fn push_bytes(&mut self, bytes: &[u8]) -> Result<(), ConverterError> {
+        self.input_bytes += bytes.len();
+        check_input_size(self.input_bytes, &self.options)?;
+        let features = self.parser.push(bytes)?;
+        self.stats.mixed_dimensions = self.parser.mixed_dimensions();
+        check_dimensions(self.stats.mixed_dimensions, &self.options)?;
+        let attribute_only = self.parser.take_attribute_only();
+        self.add_attribute_only(attribute_only);
+        projection::with_projection_cache(self.options.projection_cache_size, || {
+            features.into_iter().try_for_each(|feature| self.add_feature(feature))
+        })
+    }
+    
+    /// Count features without geometry (see `DatasetStats::add_attribute_only`)
+    fn add_attribute_only(&mut self, attribute_only: Vec<serde_json::Map<String, serde_json::Value>>) {
+        for properties in &attribute_only {
+            self.stats.add_attribute_only(properties);
+        }
+    }
+    
+    /// Assign one parsed feature to the tiles of every zoom level
+    fn add_feature(&mut self, mut feature: geojson_parser::Feature) -> Result<(), ConverterError> {
+        if self.options.input_is_3857 {
+            geojson_parser::unproject_features(std::slice::from_mut(&mut feature))?;
+        }
+        self.stats.add(&feature)?;
+        if self.options.auto_cap_max_zoom {
+            // The tile estimate only grows with more features, so dropped zoom levels stay dropped
+            let (capped, _) = self.stats.preflight(self.min_zoom, self.max_zoom, &self.options);
+            self.capped_max_zoom = self.capped_max_zoom.min(capped);
+            self.tiles_by_zoom.truncate((self.capped_max_zoom - self.min_zoom) as usize + 1);
+            self.short_lines_by_zoom.truncate(self.tiles_by_zoom.len());
+        }
+        for part in tiler::clip_feature_to_mask(feature, &self.options) {
+            let levels = self.tiles_by_zoom.iter_mut().zip(self.short_lines_by_zoom.iter_mut());
+            for (zoom, (tiles, short_lines)) in (self.min_zoom..=self.capped_max_zoom).zip(levels) {
+                tiler::add_feature_to_tiles(&part, zoom, None, &self.options, tiles, short_lines)?;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Check that the input ended cleanly and encode the tiles
+    pub fn finish(self) -> Result<(Vec<TileFile>, TileMetadata), ConverterError> {
+        self.parser.finish()?;
+        self.encode()
+    }
+    
+    /// Encode the collected tiles (zoom levels above a capped max zoom are dropped)
+    fn encode(self) -> Result<(Vec<TileFile>, TileMetadata), ConverterError> {
+        let Self { min_zoom, max_zoom, layer_name, options, stats, mut tiles_by_zoom, mut short_lines_by_zoom, .. } = self;
+        memory::checkpoint("parsed and tiled");
+        
+        let (capped_max_zoom, diagnostics) = stats.preflight(min_zoom, max_zoom, &options);
+        let mut metadata = stats.into_metadata(min_zoom, capped_max_zoom, &layer_name, diagnostics, &options)?;
+        tiles_by_zoom.truncate((capped_max_zoom as usize + 1).saturating_sub(min_zoom as usize));
+        short_lines_by_zoom.truncate(tiles_by_zoom.len());
+        metadata.diagnostics.record_short_lines(short_lines_by_zoom.iter().sum(), options.min_line_length_px);
+        
+        let mut tile_files = Vec::new();
+        for tiles in tiles_by_zoom {
+            for (coord, mut features) in tiles {
+                simplify::simplify_tile(&mut features, &options);
+                tiler::clamp_tile(&mut features, &options);
+                let encoded = mvt_encoder::encode_tile_with_stats(&features, &layer_name, coord.z, &options)?;
+                let weight = coord.weight(encoded.data.len(), features.len() - encoded.thinned - encoded.invalid);
+                metadata.diagnostics.record_tile(weight);
+                metadata.diagnostics.points_thinned += encoded.thinned as u64;
+                metadata.diagnostics.invalid_geometry_dropped += encoded.invalid as u64;
+                if encoded.values > options.max_tile_values {
+                    metadata.diagnostics.record_value_overflow(weight, encoded.values, options.max_tile_values, encoded.coerced);
+                }
+                tile_files.push(TileFile {
+                    path: coord.to_path(),
+                    data: encoded.data,
+                });
+            }
+        }
+        memory::checkpoint("encoded");
+        
+        Ok((tile_files, metadata))
+    }
+}
+
+/// Estimate the tile count for a zoom range without generating tiles
+/// 
+/// Returns the same diagnostics that generation would attach to the metadata.
+pub fn preflight_geojson(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    options: &TilerOptions,
+) -> Result<diagnostics::Diagnostics, ConverterError> {
+    check_zoom_range(min_zoom, max_zoom)?;
+    let features = parse_input(geojson_bytes, options)?.features;
+    // Layer statistics are not part of the diagnostics, so no layer name is needed
+    let mut stats = DatasetStats::new(options, "");
+    for feature in &features {
+        stats.add(feature)?;
+    }
+    Ok(stats.preflight(min_zoom, max_zoom, options).1)
+}
+
 /// Main tile generation function (for backward compatibility)
+/// 
+/// Tiles are returned as raw MVT bytes; only the PMTiles encoder compresses them.
 pub fn generate_tiles(
     geojson_bytes: &[u8],
     min_zoom: u8,
     max_zoom: u8,
     layer_name: &str,
-) -> Result<Vec<TileFile>, String> {
+) -> Result<Vec<TileFile>, ConverterError> {
     let (tiles, _metadata) = generate_tiles_with_metadata(geojson_bytes, min_zoom, max_zoom, layer_name)?;
     Ok(tiles)
 }
 
-/// Generate PMTiles format (single file)
-pub fn generate_pmtiles(
+/// Generate tiles for writing a z/x/y tree, with metadata
+/// 
+/// Like `generate_tiles_with_options`, but with `options.compress_tiles` each tile is
+/// gzipped the same way the PMTiles encoder does, ready to be served with
+/// `Content-Encoding: gzip`. Paths keep the `.pbf` extension.
+pub fn generate_tile_tree(
     geojson_bytes: &[u8],
     min_zoom: u8,
     max_zoom: u8,
     layer_name: &str,
-) -> Result<Vec<u8>, String> {
-    // Generate tiles with metadata
-    let (tile_files, metadata) = generate_tiles_with_metadata(geojson_bytes, min_zoom, max_zoom, layer_name)?;
+    options: &TilerOptions,
+) -> Result<(Vec<TileFile>, TileMetadata), ConverterError> {
+    let (mut tile_files, metadata) = generate_tiles_with_options(geojson_bytes, min_zoom, max_zoom, layer_name, options)?;
+    if options.compress_tiles {
+        for tile in &mut tile_files {
+            tile.data = pmtiles_encoder::gzip_tile(&tile.data)?;
+        }
+    }
+    Ok((tile_files, metadata))
+}
+
+/// Generate a single tile (for on-demand tiling)
+/// 
+/// Only the features intersecting `coord` are converted, so the rest of the pyramid is
+/// never built. Returns `None` when no feature touches the tile.
+pub fn generate_single_tile(
+    geojson_bytes: &[u8],
+    coord: TileCoord,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<Option<Vec<u8>>, ConverterError> {
+    check_tile_coord(coord)?;
     
-    // Convert TileFile to (TileCoord, Vec<u8>) format
-    let tiles: Vec<(TileCoord, Vec<u8>)> = tile_files
-        .into_iter()
-        .map(|tile_file| {
-            // Parse path to extract z/x/y coordinates
-            let path_parts: Vec<&str> = tile_file.path.split('/').collect();
-            if path_parts.len() == 3 {
-                let z = path_parts[0].parse::<u8>().unwrap_or(0);
-                let x = path_parts[1].parse::<u32>().unwrap_or(0);
-                let y_pbf = path_parts[2];
-                let y = y_pbf.trim_end_matches(".pbf").parse::<u32>().unwrap_or(0);
-                (TileCoord::new(z, x, y), tile_file.data)
+    let layer_name = &resolve_layer_name(layer_name, options)?;
+    let features = tiler::clip_features_to_mask(parse_input(geojson_bytes, options)?.features, options);
+    
+    let only = std::collections::HashSet::from([coord]);
+    let mut tiles = tiler::tile_features_in(&features, coord.z, Some(&only), options, &mut 0)?;
+    
+    match tiles.remove(&coord) {
+        Some(tile_features) => Ok(Some(mvt_encoder::encode_tile_at_zoom(&tile_features, layer_name, coord.z, options)?)),
+        None => Ok(None),
+    }
+}
+
+/// Generate only the listed tiles (for partial rebuilds)
+/// 
+/// Features are assigned to the requested tiles only, and zoom levels without a
+/// requested tile are skipped entirely. Tiles come back in the order they were listed
+/// (duplicates once); tiles no feature touches are left out.
+pub fn generate_tiles_for_coords(
+    geojson_bytes: &[u8],
+    coords: &[TileCoord],
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<Vec<TileFile>, ConverterError> {
+    for &coord in coords {
+        check_tile_coord(coord)?;
+    }
+    
+    let layer_name = &resolve_layer_name(layer_name, options)?;
+    let features = tiler::clip_features_to_mask(parse_input(geojson_bytes, options)?.features, options);
+    
+    let mut by_zoom: std::collections::BTreeMap<u8, std::collections::HashSet<TileCoord>> = Default::default();
+    for &coord in coords {
+        by_zoom.entry(coord.z).or_default().insert(coord);
+    }
+    let mut tiles = std::collections::HashMap::new();
+    for (zoom, only) in &by_zoom {
+        tiles.extend(tiler::tile_features_in(&features, *zoom, Some(only), options, &mut 0)?);
+    }
+    
+    let mut tile_files = Vec::new();
+    for coord in coords {
+        if let Some(tile_features) = tiles.remove(coord) {
+            tile_files.push(TileFile {
+                path: coord.to_path(),
+                data: mvt_encoder::encode_tile_at_zoom(&tile_features, layer_name, coord.z, options)?,
+            });
+        }
+    }
+    Ok(tile_files)
+}
+
+/// Check that a tile coordinate lies within its zoom level
+fn check_tile_coord(coord: TileCoord) -> Result<(), ConverterError> {
+    check_zoom_range(coord.z, coord.z)?;
+    if coord.x >= projection::get_tile_count(coord.z) || coord.y >= projection::get_tile_count(coord.z) {
+        return Err(ConverterError::InvalidInput(format!(
+            "Tile coordinate out of range: {}/{}/{}",
+            coord.z, coord.x, coord.y
+        )));
+    }
+    Ok(())
+}
+
+/// Dump the tile-space geometry of one zoom level as a GeoJSON FeatureCollection (for QA)
+/// 
+/// Features are tiled exactly as for generation, then converted back from the MVT grid
+/// to lon/lat. Each output feature is one feature in one tile, carrying the source
+/// properties plus a `tile` property (`"z/x/y"`), so it can be compared against the
+/// source data in a GIS. Coordinates are rounded to `options.coordinate_precision` decimals.
+pub fn debug_dump_geojson(
+    geojson_bytes: &[u8],
+    zoom: u8,
+    options: &TilerOptions,
+) -> Result<String, ConverterError> {
+    check_zoom_range(zoom, zoom)?;
+    let features = tiler::clip_features_to_mask(parse_input(geojson_bytes, options)?.features, options);
+    let tiles = tiler::tile_features_in(&features, zoom, None, options, &mut 0)?;
+    
+    // Sort tiles so the dump is stable between runs
+    let mut coords: Vec<&TileCoord> = tiles.keys().collect();
+    coords.sort_by_key(|coord| (coord.x, coord.y));
+    
+    let mut dump = Vec::new();
+    for coord in coords {
+        for tile_feature in &tiles[coord] {
+            let mut properties = tile_feature.properties.clone();
+            properties.insert(
+                "tile".to_string(),
+                serde_json::Value::String(format!("{}/{}/{}", coord.z, coord.x, coord.y)),
+            );
+            dump.push(geojson::Feature {
+                bbox: None,
+                geometry: Some(tiler::tile_geometry_to_geojson(&tile_feature.geometry, *coord, options.coordinate_precision)),
+                id: None,
+                properties: Some(properties),
+                foreign_members: None,
+            });
+        }
+    }
+    
+    let collection = geojson::FeatureCollection {
+        bbox: None,
+        features: dump,
+        foreign_members: None,
+    };
+    Ok(collection.to_string())
+}
+
+/// Generate PMTiles format (single file)
+pub fn generate_pmtiles(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+) -> Result<Vec<u8>, ConverterError> {
+    generate_pmtiles_with_options(geojson_bytes, min_zoom, max_zoom, layer_name, &TilerOptions::default())
+}
+
+/// Generate PMTiles format (single file) with options
+pub fn generate_pmtiles_with_options(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<Vec<u8>, ConverterError> {
+    // Generate tiles with metadata
+    let (tile_files, metadata) = generate_tiles_with_options(geojson_bytes, min_zoom, max_zoom, layer_name, options)?;
+    encode_tile_files(tile_files, &metadata, options)
+}
+
+/// Generate a PMTiles archive from a shapefile (see `generate_tiles_from_shapefile`)
+pub fn generate_pmtiles_from_shapefile(
+    sources: &shapefile_parser::ShapefileSources,
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<Vec<u8>, ConverterError> {
+    let (tile_files, metadata) = generate_tiles_from_shapefile(sources, min_zoom, max_zoom, layer_name, options)?;
+    encode_tile_files(tile_files, &metadata, options)
+}
+
+/// Encode generated tiles as a PMTiles archive
+fn encode_tile_files(
+    tile_files: Vec<TileFile>,
+    metadata: &TileMetadata,
+    options: &TilerOptions,
+) -> Result<Vec<u8>, ConverterError> {
+    // Convert TileFile to (TileCoord, Vec<u8>) format
+    let tiles: Vec<(TileCoord, Vec<u8>)> = tile_files
+        .into_iter()
+        .map(|tile_file| {
+            // Parse path to extract z/x/y coordinates
+            let path_parts: Vec<&str> = tile_file.path.split('/').collect();
+            if path_parts.len() == 3 {
+                let z = path_parts[0].parse::<u8>().unwrap_or(0);
+                let x = path_parts[1].parse::<u32>().unwrap_or(0);
+                let y_pbf = path_parts[2];
+                let y = y_pbf.trim_end_matches(".pbf").parse::<u32>().unwrap_or(0);
+                (TileCoord::new(z, x, y), tile_file.data)
             } else {
                 (TileCoord::new(0, 0, 0), tile_file.data)
             }
@@ -243,16 +1542,1572 @@ pub fn generate_pmtiles(
         .collect();
     
     // Encode as PMTiles
-    pmtiles_encoder::encode_pmtiles(tiles, &metadata)
+    pmtiles_encoder::encode_pmtiles_with_options(tiles, metadata, options)
+}
+
+/// Generate a PMTiles archive with header and JSON metadata but no tiles
+/// 
+/// Only parses the input and computes bounds, center and attribute statistics, which
+/// makes it a cheap way to validate configuration for a huge dataset before a full run.
+pub fn generate_pmtiles_header_only(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+) -> Result<Vec<u8>, ConverterError> {
+    check_zoom_range(min_zoom, max_zoom)?;
+    let options = TilerOptions::default();
+    let layer_name = &resolve_layer_name(layer_name, &options)?;
+    
+    let parsed = parse_input(geojson_bytes, &options)?;
+    let mut stats = DatasetStats::new(&options, layer_name);
+    for feature in &parsed.features {
+        stats.add(feature)?;
+    }
+    for properties in &parsed.attribute_only {
+        stats.add_attribute_only(properties);
+    }
+    stats.mixed_dimensions = parsed.mixed_dimensions;
+    let (max_zoom, diagnostics) = stats.preflight(min_zoom, max_zoom, &options);
+    let metadata = stats.into_metadata(min_zoom, max_zoom, layer_name, diagnostics, &options)?;
+    
+    pmtiles_encoder::encode_pmtiles_header_only(&metadata, &options)
+}
+
+/// Generate a PMTiles archive from the GeoJSON file at `geojson_path` into `out_path` (native only)
+#[cfg(all(feature = "std-fs", not(target_arch = "wasm32")))]
+pub fn generate_pmtiles_from_path(
+    geojson_path: impl AsRef<std::path::Path>,
+    out_path: impl AsRef<std::path::Path>,
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<(), ConverterError> {
+    let geojson_bytes = read_file(geojson_path.as_ref())?;
+    let archive = generate_pmtiles_with_options(&geojson_bytes, min_zoom, max_zoom, layer_name, options)?;
+    write_file(out_path.as_ref(), &archive)
+}
+
+/// Generate a `z/x/y.pbf` tile tree from the GeoJSON file at `geojson_path` into
+/// `out_dir` (native only)
+/// 
+/// Directories are created as needed and existing tiles are overwritten; other files in
+/// `out_dir` are left alone. Tiles are written as by `generate_tile_tree`.
+#[cfg(all(feature = "std-fs", not(target_arch = "wasm32")))]
+pub fn generate_tile_tree_to_dir(
+    geojson_path: impl AsRef<std::path::Path>,
+    out_dir: impl AsRef<std::path::Path>,
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<TileMetadata, ConverterError> {
+    let geojson_bytes = read_file(geojson_path.as_ref())?;
+    let (tile_files, metadata) = generate_tile_tree(&geojson_bytes, min_zoom, max_zoom, layer_name, options)?;
+    for tile in &tile_files {
+        let path = out_dir.as_ref().join(&tile.path);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .map_err(|e| ConverterError::Io(format!("Failed to create {}: {}", parent.display(), e)))?;
+        }
+        write_file(&path, &tile.data)?;
+    }
+    Ok(metadata)
+}
+
+#[cfg(all(feature = "std-fs", not(target_arch = "wasm32")))]
+fn read_file(path: &std::path::Path) -> Result<Vec<u8>, ConverterError> {
+    std::fs::read(path).map_err(|e| ConverterError::Io(format!("Failed to read {}: {}", path.display(), e)))
+}
+
+#[cfg(all(feature = "std-fs", not(target_arch = "wasm32")))]
+fn write_file(path: &std::path::Path, data: &[u8]) -> Result<(), ConverterError> {
+    std::fs::write(path, data).map_err(|e| ConverterError::Io(format!("Failed to write {}: {}", path.display(), e)))
+}
+
+/// Generate a ZIP archive of `z/x/y.pbf` tiles plus a `metadata.json` (TileJSON)
+/// 
+/// Tiles are raw MVT unless `options.zip_tile_compression` is gzip, in which case they
+/// are gzipped and named `z/x/y.pbf.gz`. Entries are in tile generation order; raw tiles
+/// and the metadata are deflated, gzipped tiles are stored as is.
+pub fn generate_tiles_zip(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<Vec<u8>, ConverterError> {
+    let (tile_files, metadata) = generate_tiles_with_options(geojson_bytes, min_zoom, max_zoom, layer_name, options)?;
+    let gzip = options.zip_tile_compression == pmtiles_encoder::TileCompression::Gzip;
+    
+    let mut zip = zip::ZipWriter::new(std::io::Cursor::new(Vec::new()));
+    for tile in &tile_files {
+        if gzip {
+            let name = format!("{}.gz", tile.path);
+            add_zip_entry(&mut zip, &name, &pmtiles_encoder::gzip_tile(&tile.data)?, zip::CompressionMethod::Stored)?;
+        } else {
+            add_zip_entry(&mut zip, &tile.path, &tile.data, zip::CompressionMethod::Deflated)?;
+        }
+    }
+    
+    // TileJSON 3.0 fields on top of the tippecanoe-style metadata
+    let extension = if gzip { "pbf.gz" } else { "pbf" };
+    let tiles_url = format!("{{z}}/{{x}}/{{y}}.{}", extension);
+    let tilejson = pmtiles_encoder::build_standalone_tilejson(&metadata, &tiles_url, pmtiles_encoder::TileScheme::Xyz)?;
+    let json = serde_json::to_vec_pretty(&serde_json::Value::Object(tilejson))
+        .map_err(|e| ConverterError::Encode(format!("Failed to serialize JSON: {}", e)))?;
+    add_zip_entry(&mut zip, "metadata.json", &json, zip::CompressionMethod::Deflated)?;
+    
+    zip.finish()
+        .map(std::io::Cursor::into_inner)
+        .map_err(|e| ConverterError::Encode(format!("Failed to write ZIP archive: {}", e)))
+}
+
+/// Add one file to a ZIP archive (with the fixed 1980-01-01 timestamp, so archives are reproducible)
+fn add_zip_entry(
+    zip: &mut zip::ZipWriter<std::io::Cursor<Vec<u8>>>,
+    name: &str,
+    data: &[u8],
+    method: zip::CompressionMethod,
+) -> Result<(), ConverterError> {
+    let options = zip::write::SimpleFileOptions::default()
+        .compression_method(method)
+        .last_modified_time(zip::DateTime::default());
+    zip.start_file(name, options)
+        .map_err(std::io::Error::from)
+        .and_then(|_| std::io::Write::write_all(zip, data))
+        .map_err(|e| ConverterError::Encode(format!("Failed to add {} to the ZIP archive: {}", name, e)))
+}
+
+/// Generate PMTiles format and its SHA-256 digest (hex), e.g. for use as an ETag
+/// 
+/// Output is deterministic, so identical input and options always give the same hash.
+pub fn generate_pmtiles_with_hash(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<(Vec<u8>, String), ConverterError> {
+    let archive = generate_pmtiles_with_options(geojson_bytes, min_zoom, max_zoom, layer_name, options)?;
+    let hash = checksum::sha256_hex(&archive);
+    Ok((archive, hash))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use prost::Message;
 
     #[test]
     fn test_tile_coord() {
         let coord = TileCoord::new(5, 10, 12);
         assert_eq!(coord.to_path(), "5/10/12.pbf");
     }
+
+    #[test]
+    fn test_tile_coord_pyramid() {
+        let coord = TileCoord::new(5, 11, 12);
+        assert_eq!(coord.parent(), Some(TileCoord::new(4, 5, 6)));
+        assert_eq!(TileCoord::new(0, 0, 0).parent(), None);
+        
+        let children = coord.children().unwrap();
+        assert_eq!(children[3], TileCoord::new(6, 23, 25));
+        for child in &children {
+            assert_eq!(child.parent(), Some(coord));
+            assert!(coord.contains(child));
+        }
+        
+        assert!(coord.contains(&coord));
+        assert!(TileCoord::new(0, 0, 0).contains(&coord));
+        assert!(coord.parent().unwrap().contains(&TileCoord::new(9, 185, 200)));
+        assert!(!coord.contains(&TileCoord::new(6, 21, 25)));
+        assert!(!coord.contains(&coord.parent().unwrap()));
+        assert_eq!(TileCoord::new(u8::MAX, 0, 0).children(), None);
+        assert_eq!(TileCoord::new(31, u32::MAX, 0).children(), None);
+    }
+    
+    #[test]
+    fn test_validate_layer_name() {
+        assert!(validate_layer_name("").is_err());
+        assert!(validate_layer_name("roads.v2").is_err());
+        assert!(validate_layer_name("my layer").is_err());
+        assert!(validate_layer_name("roads_v2").is_ok());
+        assert!(validate_layer_name("道路").is_ok());
+        
+        assert_eq!(sanitize_layer_name("  my   layer "), "my_layer");
+    }
+
+    #[test]
+    fn test_generate_rejects_empty_layer_name() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}, "properties": {}}"#;
+        assert!(generate_tiles(geojson.as_bytes(), 0, 0, "").is_err());
+        assert!(generate_tiles(geojson.as_bytes(), 0, 0, "   ").is_err());
+        
+        let (_, metadata) = generate_tiles_with_metadata(geojson.as_bytes(), 0, 0, " my layer ").unwrap();
+        assert_eq!(metadata.layer_name, "my_layer");
+    }
+
+    #[test]
+    fn test_error_variants() {
+        let result = generate_tiles(b"{not json", 0, 0, "layer");
+        assert!(matches!(result, Err(ConverterError::Parse(_))));
+        
+        let empty = r#"{"type": "FeatureCollection", "features": []}"#;
+        let result = generate_tiles(empty.as_bytes(), 0, 0, "layer");
+        assert!(matches!(result, Err(ConverterError::EmptyInput(_))));
+        
+        let point = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}, "properties": {}}"#;
+        let result = generate_tiles(point.as_bytes(), 0, 0, "bad.name");
+        assert!(matches!(result, Err(ConverterError::InvalidInput(_))));
+        
+        let result = generate_single_tile(point.as_bytes(), TileCoord::new(1, 2, 0), "layer", &TilerOptions::default());
+        assert!(matches!(result, Err(ConverterError::InvalidInput(_))));
+        
+        assert!(matches!(projection::project_lonlat(f64::NAN, 0.0), Err(ConverterError::Projection(_))));
+    }
+
+    #[test]
+    fn test_auto_cap_max_zoom() {
+        let mut features = Vec::new();
+        for i in 0..50 {
+            features.push(format!(
+                r#"{{"type": "Feature", "geometry": {{"type": "Point", "coordinates": [{}, {}]}}, "properties": {{}}}}"#,
+                139.0 + i as f64 * 0.1,
+                35.0 + i as f64 * 0.05
+            ));
+        }
+        let geojson = format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, features.join(","));
+        
+        let mut options = TilerOptions {
+            max_estimated_tiles: 100,
+            ..Default::default()
+        };
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 14, "layer", &options).unwrap();
+        let suggested = metadata.diagnostics.suggested_max_zoom.unwrap();
+        assert!(suggested < 14);
+        assert_eq!(metadata.max_zoom, 14);
+        
+        options.auto_cap_max_zoom = true;
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 14, "layer", &options).unwrap();
+        assert_eq!(metadata.max_zoom, suggested);
+        assert!(tiles.len() <= 100);
+    }
+
+    #[test]
+    fn test_debug_dump_geojson() {
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}},
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[-10.0, 10.0], [10.0, 10.0]]}, "properties": {}}
+            ]
+        }"#;
+        
+        let dump = debug_dump_geojson(geojson.as_bytes(), 1, &TilerOptions::default()).unwrap();
+        let parsed: geojson::GeoJson = dump.parse().unwrap();
+        let geojson::GeoJson::FeatureCollection(collection) = parsed else {
+            panic!("FeatureCollection expected");
+        };
+        
+        // The point falls in one tile, the line touches two
+        assert_eq!(collection.features.len(), 3);
+        
+        let tokyo = collection
+            .features
+            .iter()
+            .find(|f| f.property("name").is_some())
+            .unwrap();
+        assert_eq!(tokyo.property("tile").unwrap(), "1/1/0");
+        let geojson::Value::Point(position) = &tokyo.geometry.as_ref().unwrap().value else {
+            panic!("Point expected");
+        };
+        // Within one grid cell at zoom 1 (~0.04°)
+        assert!((position[0] - 139.7671).abs() < 0.05);
+        assert!((position[1] - 35.6812).abs() < 0.05);
+    }
+
+    #[test]
+    fn test_debug_dump_coordinate_precision() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.7671, 35.6812], [139.8, 35.7]]}, "properties": {}}"#;
+        
+        for precision in [2, 6] {
+            let options = TilerOptions {
+                coordinate_precision: precision,
+                ..Default::default()
+            };
+            let dump = debug_dump_geojson(geojson.as_bytes(), 12, &options).unwrap();
+            let parsed: serde_json::Value = serde_json::from_str(&dump).unwrap();
+            let scale = 10f64.powi(precision as i32);
+            for feature in parsed["features"].as_array().unwrap() {
+                for position in feature["geometry"]["coordinates"].as_array().unwrap() {
+                    for value in position.as_array().unwrap() {
+                        let value = value.as_f64().unwrap();
+                        assert_eq!((value * scale).round() / scale, value);
+                        // The shortest representation has at most `precision` decimals
+                        let decimals = value.to_string().split('.').nth(1).map_or(0, str::len);
+                        assert!(decimals <= precision, "{}", value);
+                    }
+                }
+            }
+        }
+    }
+    
+    #[test]
+    fn test_generate_pmtiles_with_hash() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}}"#;
+        let options = TilerOptions {
+            embed_content_hash: true,
+            ..Default::default()
+        };
+        
+        let (archive, hash) = generate_pmtiles_with_hash(geojson.as_bytes(), 0, 3, "layer", &options).unwrap();
+        let (archive_again, hash_again) = generate_pmtiles_with_hash(geojson.as_bytes(), 0, 3, "layer", &options).unwrap();
+        assert_eq!(hash.len(), 64);
+        assert_eq!(hash, hash_again);
+        assert_eq!(archive, archive_again);
+        
+        let changed = geojson.replace("Tokyo", "Osaka");
+        let (_, changed_hash) = generate_pmtiles_with_hash(changed.as_bytes(), 0, 3, "layer", &options).unwrap();
+        assert_ne!(hash, changed_hash);
+    }
+
+    #[test]
+    fn test_generate_pmtiles_header_only() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.0, 35.0], [140.0, 36.0]]}, "properties": {"name": "road"}}"#;
+        let data = generate_pmtiles_header_only(geojson.as_bytes(), 2, 12, "roads").unwrap();
+        
+        let u64_at = |offset: usize| u64::from_le_bytes(data[offset..offset + 8].try_into().unwrap());
+        let i32_at = |offset: usize| i32::from_le_bytes(data[offset..offset + 4].try_into().unwrap());
+        assert_eq!(&data[0..8], b"PMTiles\x03");
+        
+        // Sections follow each other and the (empty) tile data ends the file
+        let (directory_offset, directory_length) = (u64_at(8), u64_at(16));
+        let (json_offset, json_length) = (u64_at(24), u64_at(32));
+        assert_eq!(directory_offset, 127);
+        assert_eq!(json_offset, directory_offset + directory_length);
+        assert_eq!(u64_at(56), json_offset + json_length);
+        assert_eq!(u64_at(64), 0);
+        assert_eq!(u64_at(56) as usize, data.len());
+        
+        // Addressed tiles, tile entries and tile contents are all zero
+        assert_eq!((u64_at(72), u64_at(80), u64_at(88)), (0, 0, 0));
+        assert_eq!((data[100], data[101]), (2, 12));
+        assert_eq!(i32_at(102), 1_390_000_000);
+        assert_eq!(i32_at(114), 360_000_000);
+        
+        let mut json = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(&data[json_offset as usize..(json_offset + json_length) as usize]),
+            &mut json,
+        )
+        .unwrap();
+        let tilejson: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(tilejson["vector_layers"][0]["id"], "roads");
+        assert_eq!(tilejson["vector_layers"][0]["fields"]["name"], "String");
+    }
+
+    #[test]
+    fn test_projection_cache_matches_uncached() {
+        // A grid of adjacent squares: inner vertices are shared by four polygons
+        let mut features = Vec::new();
+        for i in 0..20 {
+            for j in 0..20 {
+                let (lon, lat) = (139.0 + i as f64 * 0.01, 35.0 + j as f64 * 0.01);
+                features.push(format!(
+                    r#"{{"type": "Feature", "geometry": {{"type": "Polygon", "coordinates": [[[{0}, {1}], [{2}, {1}], [{2}, {3}], [{0}, {3}], [{0}, {1}]]]}}, "properties": {{"id": {4}}}}}"#,
+                    lon, lat, lon + 0.01, lat + 0.01, i * 20 + j
+                ));
+            }
+        }
+        let geojson = format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, features.join(","));
+        
+        let run = |projection_cache_size: usize| {
+            let options = TilerOptions {
+                projection_cache_size,
+                ..Default::default()
+            };
+            let (mut tiles, _) = generate_tiles_with_options(geojson.as_bytes(), 8, 12, "grid", &options).unwrap();
+            tiles.sort_by(|a, b| a.path.cmp(&b.path));
+            tiles.into_iter().map(|tile| (tile.path, tile.data)).collect::<Vec<_>>()
+        };
+        let uncached = run(0);
+        assert_eq!(run(100_000), uncached);
+        // A cache far smaller than the vertex count is cleared repeatedly but stays exact
+        assert_eq!(run(16), uncached);
+    }
+    
+    #[test]
+    fn test_canonical_feature_order() {
+        let features = [
+            r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.70, 35.60]}, "properties": {"name": "a"}}"#,
+            r#"{"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [[[139.7, 35.6], [139.8, 35.6], [139.8, 35.7], [139.7, 35.6]]]}, "properties": {}}"#,
+            r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.71, 35.61]}, "properties": {"name": "b"}}"#,
+            r#"{"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.7, 35.6], [139.75, 35.65]]}, "properties": {"name": "c"}}"#,
+        ];
+        let collection = |order: &[usize]| {
+            let members: Vec<&str> = order.iter().map(|&i| features[i]).collect();
+            format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, members.join(","))
+        };
+        let options = TilerOptions {
+            canonical_feature_order: true,
+            ..Default::default()
+        };
+        
+        let forward = collection(&[0, 1, 2, 3]);
+        let archive = generate_pmtiles_with_options(forward.as_bytes(), 0, 8, "mixed", &options).unwrap();
+        assert_eq!(archive, generate_pmtiles_with_options(forward.as_bytes(), 0, 8, "mixed", &options).unwrap());
+        
+        // Any input order gives the same archive, unlike the default input order
+        for order in [[3, 2, 1, 0], [2, 0, 3, 1]] {
+            let shuffled = collection(&order);
+            assert_eq!(archive, generate_pmtiles_with_options(shuffled.as_bytes(), 0, 8, "mixed", &options).unwrap());
+            assert_ne!(
+                generate_pmtiles(forward.as_bytes(), 0, 8, "mixed").unwrap(),
+                generate_pmtiles(shuffled.as_bytes(), 0, 8, "mixed").unwrap()
+            );
+        }
+    }
+    
+    #[test]
+    fn test_max_input_bytes() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {}}"#;
+        let limited = |max_input_bytes| TilerOptions {
+            max_input_bytes: Some(max_input_bytes),
+            ..Default::default()
+        };
+        
+        let result = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "layer", &limited(64));
+        let Err(ConverterError::TooLarge(message)) = result else {
+            panic!("expected TooLarge");
+        };
+        assert_eq!(message, format!("Input is {} bytes, more than the limit of 64 bytes", geojson.len()));
+        // Oversized input is rejected before parsing, even when it is not JSON at all
+        assert!(matches!(
+            preflight_geojson(&[b'x'; 100], 0, 2, &limited(64)),
+            Err(ConverterError::TooLarge(_))
+        ));
+        
+        assert!(generate_tiles_with_options(geojson.as_bytes(), 0, 2, "layer", &limited(geojson.len())).is_ok());
+    }
+    
+    #[test]
+    fn test_layer_by_field() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.70, 35.60], [139.71, 35.61]]}, "properties": {"kind": "roads", "lanes": 4}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.70, 35.61], [139.71, 35.60]]}, "properties": {"kind": "paths"}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.70, 35.605], [139.71, 35.605]]}, "properties": {"kind": "rail", "operator": "JR"}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.705, 35.60], [139.705, 35.61]]}, "properties": {"kind": "roads", "lanes": 2}}
+        ]}"#;
+        let options = TilerOptions {
+            layer_by_field: Some("kind".to_string()),
+            ..Default::default()
+        };
+        
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 10, 10, "transport", &options).unwrap();
+        assert_eq!(tiles.len(), 1);
+        let tile = mvt_encoder::vector_tile::Tile::decode(tiles[0].data.as_slice()).unwrap();
+        let layers: Vec<(&str, usize)> = tile.layers.iter().map(|l| (l.name.as_str(), l.features.len())).collect();
+        assert_eq!(layers, vec![("paths", 1), ("rail", 1), ("roads", 2)]);
+        
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        let ids: Vec<&str> = tilejson["vector_layers"].as_array().unwrap().iter().map(|l| l["id"].as_str().unwrap()).collect();
+        assert_eq!(ids, vec!["paths", "rail", "roads"]);
+        assert_eq!(tilejson["vector_layers"][1]["fields"]["operator"], "String");
+        assert!(tilejson["vector_layers"][1]["fields"].get("lanes").is_none());
+        assert_eq!(tilejson["vector_layers"][2]["fields"]["lanes"], "Number");
+        assert_eq!(tilejson["tilestats"]["layerCount"], 3);
+        assert_eq!(tilejson["tilestats"]["layers"][2]["layer"], "roads");
+        assert_eq!(tilejson["tilestats"]["layers"][2]["count"], 2);
+        assert_eq!(tilejson["tilestats"]["layers"][0]["geometry"], "LineString");
+        
+        // Property values are sanitized and validated like the layer name argument
+        let geojson = geojson.replace("\"paths\"", "\" foot paths \"");
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 10, 10, "transport", &options).unwrap();
+        let tile = mvt_encoder::vector_tile::Tile::decode(tiles[0].data.as_slice()).unwrap();
+        assert_eq!(tile.layers[0].name, "foot_paths");
+        assert_eq!(metadata.layers[0].name, "foot_paths");
+        let geojson = geojson.replace("\"rail\"", "\"rail.jr\"");
+        let invalid = generate_tiles_with_options(geojson.as_bytes(), 10, 10, "transport", &options);
+        assert!(matches!(invalid, Err(ConverterError::InvalidInput(m)) if m.contains("rail.jr")));
+    }
+    
+    #[test]
+    fn test_vector_layer_source() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.70, 35.60]}, "properties": {"kind": "station"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.71, 35.61]}, "properties": {}}
+        ]}"#;
+        let options = TilerOptions {
+            layer_by_field: Some("kind".to_string()),
+            ..Default::default()
+        };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 5, 5, "transit", &options).unwrap();
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        let sources: Vec<(&str, &str)> = tilejson["vector_layers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| (l["id"].as_str().unwrap(), l["source"].as_str().unwrap()))
+            .collect();
+        assert_eq!(sources, vec![("station", "transit:kind=station"), ("transit", "transit")]);
+        
+        let (_, metadata) = generate_tiles_with_metadata(geojson.as_bytes(), 5, 5, "stops").unwrap();
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        assert_eq!(tilejson["vector_layers"][0]["source"], "stops");
+    }
+    
+    #[test]
+    fn test_bounds_precision() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.12345678, 35.98765432], [140.5, 36.25]]}, "properties": {}}"#;
+        let options = TilerOptions {
+            bounds_precision: 3,
+            ..Default::default()
+        };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "layer", &options).unwrap();
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        assert_eq!(tilejson["antimeridian_adjusted_bounds"], "139.123,35.988,140.500,36.250");
+        let rows = mbtiles::mbtiles_metadata_rows(&metadata).unwrap();
+        assert!(rows.contains(&("bounds".to_string(), "139.123,35.988,140.500,36.250".to_string())));
+        
+        // The header keeps the full precision
+        let archive = generate_pmtiles_with_options(geojson.as_bytes(), 0, 2, "layer", &options).unwrap();
+        let i32_at = |offset: usize| i32::from_le_bytes(archive[offset..offset + 4].try_into().unwrap());
+        assert_eq!((i32_at(102), i32_at(106)), (1_391_234_567, 359_876_543));
+    }
+    
+    #[test]
+    fn test_generator_override() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {}}"#;
+        let options = TilerOptions {
+            generator: Some("acme-maps 2.1".to_string()),
+            generator_options: Some("acme-maps --basemap".to_string()),
+            ..Default::default()
+        };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "poi", &options).unwrap();
+        let json = serde_json::Value::Object(pmtiles_encoder::build_tilejson(&metadata, None)).to_string();
+        assert!(json.contains(r#""generator":"acme-maps 2.1""#));
+        assert!(json.contains(r#""generator_options":"acme-maps --basemap""#));
+        let rows = mbtiles::mbtiles_metadata_rows(&metadata).unwrap();
+        assert!(rows.contains(&("generator".to_string(), "acme-maps 2.1".to_string())));
+        
+        // Defaults are unchanged
+        let (_, metadata) = generate_tiles_with_metadata(geojson.as_bytes(), 0, 2, "poi").unwrap();
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        assert_eq!(tilejson["generator"], "web-vector-tile-maker");
+        assert_eq!(tilejson["generator_options"], "web-vector-tile-maker -o poi.pmtiles");
+    }
+    
+    #[test]
+    fn test_tilestats_disabled() {
+        let features: Vec<String> = (0..1000)
+            .map(|i| {
+                format!(
+                    r#"{{"type": "Feature", "geometry": {{"type": "Point", "coordinates": [139.{:03}, 35.6]}}, "properties": {{"id": "poi-{}", "rank": {}}}}}"#,
+                    i, i, i
+                )
+            })
+            .collect();
+        let geojson = format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, features.join(","));
+        let options = TilerOptions { tilestats: false, ..Default::default() };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "poi", &options).unwrap();
+        // No unique values are collected, but field types still are
+        assert!(metadata.attributes.is_empty());
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        assert!(tilejson.get("tilestats").is_none());
+        assert_eq!(tilejson["vector_layers"][0]["fields"], serde_json::json!({"id": "String", "rank": "Number"}));
+        let rows = mbtiles::mbtiles_metadata_rows(&metadata).unwrap();
+        let json = &rows.iter().find(|(name, _)| name == "json").unwrap().1;
+        assert!(json.contains("vector_layers") && !json.contains("tilestats"));
+        
+        let (_, metadata) = generate_tiles_with_metadata(geojson.as_bytes(), 0, 2, "poi").unwrap();
+        assert_eq!(pmtiles_encoder::build_tilejson(&metadata, None)["tilestats"]["layers"][0]["attributeCount"], 2);
+    }
+    
+    #[test]
+    fn test_attribution() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {}}"#;
+        let attribution = r#"<a href="https://www.openstreetmap.org/copyright">© OpenStreetMap contributors</a>"#;
+        let options = TilerOptions { attribution: Some(attribution.to_string()), ..Default::default() };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "poi", &options).unwrap();
+        assert_eq!(pmtiles_encoder::build_tilejson(&metadata, None)["attribution"], attribution);
+        let rows = mbtiles::mbtiles_metadata_rows(&metadata).unwrap();
+        assert!(rows.contains(&("attribution".to_string(), attribution.to_string())));
+        
+        // The archive metadata carries it too
+        let archive = generate_pmtiles_with_options(geojson.as_bytes(), 0, 2, "poi", &options).unwrap();
+        let u64_at = |offset: usize| u64::from_le_bytes(archive[offset..offset + 8].try_into().unwrap());
+        let (json_offset, json_length) = (u64_at(24) as usize, u64_at(32) as usize);
+        let mut json = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(&archive[json_offset..json_offset + json_length]),
+            &mut json,
+        )
+        .unwrap();
+        let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(json["attribution"], attribution);
+        
+        // Omitted by default
+        let (_, metadata) = generate_tiles_with_metadata(geojson.as_bytes(), 0, 2, "poi").unwrap();
+        assert!(pmtiles_encoder::build_tilejson(&metadata, None).get("attribution").is_none());
+        assert!(!mbtiles::mbtiles_metadata_rows(&metadata).unwrap().iter().any(|(name, _)| name == "attribution"));
+    }
+    
+    #[test]
+    fn test_tileset_type() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {}}"#;
+        let options = TilerOptions {
+            tileset_type: pmtiles_encoder::TilesetType::Baselayer,
+            ..Default::default()
+        };
+        
+        let archive = generate_pmtiles_with_options(geojson.as_bytes(), 0, 2, "base", &options).unwrap();
+        let u64_at = |offset: usize| u64::from_le_bytes(archive[offset..offset + 8].try_into().unwrap());
+        let (json_offset, json_length) = (u64_at(24) as usize, u64_at(32) as usize);
+        let mut json = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(&archive[json_offset..json_offset + json_length]),
+            &mut json,
+        )
+        .unwrap();
+        let tilejson: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(tilejson["type"], "baselayer");
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "base", &options).unwrap();
+        let rows = mbtiles::mbtiles_metadata_rows(&metadata).unwrap();
+        assert!(rows.contains(&("type".to_string(), "baselayer".to_string())));
+        
+        assert_eq!(pmtiles_encoder::TilesetType::parse("overlay").unwrap(), pmtiles_encoder::TilesetType::Overlay);
+        assert!(matches!(pmtiles_encoder::TilesetType::parse("basemap"), Err(ConverterError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_bounds_override() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [-70.0, -30.0]}, "properties": {}}
+        ]}"#;
+        let options = TilerOptions {
+            bounds_override: Some((138.0, 34.0, 141.0, 37.0)),
+            tile_compression: pmtiles_encoder::TileCompression::None,
+            ..Default::default()
+        };
+        
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "layer", &options).unwrap();
+        assert_eq!(metadata.bounds, (138.0, 34.0, 141.0, 37.0));
+        assert_eq!(metadata.center, (139.5, 35.5));
+        // The stray feature is still tiled
+        assert!(tiles.iter().any(|tile| tile.path == "2/1/2.pbf"));
+        
+        let archive = generate_pmtiles_with_options(geojson.as_bytes(), 0, 2, "layer", &options).unwrap();
+        let i32_at = |offset: usize| i32::from_le_bytes(archive[offset..offset + 4].try_into().unwrap());
+        assert_eq!(
+            (i32_at(102), i32_at(106), i32_at(110), i32_at(114)),
+            (1_380_000_000, 340_000_000, 1_410_000_000, 370_000_000)
+        );
+        
+        for bounds in [(141.0, 34.0, 138.0, 37.0), (138.0, 34.0, 141.0, 34.0), (0.0, f64::NAN, 1.0, 1.0)] {
+            let options = TilerOptions {
+                bounds_override: Some(bounds),
+                ..Default::default()
+            };
+            let result = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "layer", &options);
+            assert!(matches!(result, Err(ConverterError::InvalidInput(_))));
+        }
+    }
+
+    #[test]
+    fn test_center_override() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [135.5, 34.7]}, "properties": {}}
+        ]}"#;
+        let options = TilerOptions {
+            center_override: Some((139.75, 35.625, 3)),
+            center_zoom: Some(1),
+            ..Default::default()
+        };
+        
+        let archive = generate_pmtiles_with_options(geojson.as_bytes(), 0, 4, "layer", &options).unwrap();
+        let i32_at = |offset: usize| i32::from_le_bytes(archive[offset..offset + 4].try_into().unwrap());
+        assert_eq!(archive[118], 3);
+        assert_eq!((i32_at(119), i32_at(123)), (1_397_500_000, 356_250_000));
+        
+        for center in [(139.7, 95.0, 3), (200.0, 35.0, 3), (f64::NAN, 35.0, 3), (139.7, 35.6, 5)] {
+            let options = TilerOptions {
+                center_override: Some(center),
+                ..Default::default()
+            };
+            let result = generate_tiles_with_options(geojson.as_bytes(), 0, 4, "layer", &options);
+            assert!(matches!(result, Err(ConverterError::InvalidInput(_))));
+        }
+    }
+    
+    #[test]
+    fn test_keep_geometry_type() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.71, 35.61]}, "properties": {"stray": true}},
+            {"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [[[139.70, 35.60], [139.72, 35.60], [139.72, 35.62], [139.70, 35.60]]]}, "properties": {"name": "a"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.72, 35.62]}, "properties": {"stray": true}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.73, 35.63]}, "properties": {"stray": true}},
+            {"type": "Feature", "geometry": {"type": "MultiPolygon", "coordinates": [[[[139.73, 35.60], [139.75, 35.60], [139.75, 35.62], [139.73, 35.60]]]]}, "properties": {"name": "b"}}
+        ]}"#;
+        let options = TilerOptions {
+            keep_geometry_type: Some(tiler::GeometryKind::Polygon),
+            ..Default::default()
+        };
+        
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 5, 5, "areas", &options).unwrap();
+        assert_eq!(metadata.geometry_type, "Polygon");
+        assert_eq!(metadata.feature_count, 2);
+        assert!(!metadata.fields.contains_key("stray"));
+        assert_eq!(metadata.diagnostics.geometry_type_dropped, 3);
+        
+        let decoded = mvt_encoder::decode_tile(&tiles[0].data).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert!(decoded.iter().all(|f| f.geom_type == mvt_encoder::vector_tile::tile::GeomType::Polygon));
+    }
+    
+    #[test]
+    fn test_short_lines_dropped_counts_tiled_lines() {
+        // About 111m long, short at every zoom up to 5, but only tiled at zoom 0
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[10.0, 0.5], [10.001, 0.5]]}, "properties": {"maxz": 0}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [10.0, 0.5]}, "properties": {}}
+        ]}"#;
+        let options = TilerOptions {
+            min_line_length_px: 4.0,
+            max_zoom_property: Some("maxz".to_string()),
+            ..Default::default()
+        };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 5, "layer", &options).unwrap();
+        assert_eq!(metadata.diagnostics.short_lines_dropped, 1);
+        assert!(metadata.diagnostics.warnings.iter().any(|w| w.starts_with("1 line(s) shorter than 4px")));
+        
+        let mut generator = TileGenerator::new(0, 5, "layer", &options).unwrap();
+        generator.push_bytes(geojson.as_bytes()).unwrap();
+        let (_, streamed) = generator.finish().unwrap();
+        assert_eq!(streamed.diagnostics.short_lines_dropped, 1);
+    }
+    
+    #[test]
+    fn test_input_is_3857() {
+        // Tokyo Station in lon/lat and in WebMercator meters
+        let lonlat = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {}}"#;
+        let (mx, my) = projection::lonlat_to_meters(139.7671, 35.6812);
+        let meters = format!(
+            r#"{{"type": "Feature", "geometry": {{"type": "Point", "coordinates": [{}, {}]}}, "properties": {{}}}}"#,
+            mx, my
+        );
+        let options = TilerOptions { input_is_3857: true, ..Default::default() };
+        
+        let (expected, _) = generate_tiles_with_metadata(lonlat.as_bytes(), 14, 14, "stations").unwrap();
+        let (tiles, metadata) = generate_tiles_with_options(meters.as_bytes(), 14, 14, "stations", &options).unwrap();
+        assert_eq!(tiles[0].path, "14/14552/6451.pbf");
+        assert_eq!((&tiles[0].path, &tiles[0].data), (&expected[0].path, &expected[0].data));
+        assert!((metadata.bounds.0 - 139.7671).abs() < 1e-9 && (metadata.bounds.1 - 35.6812).abs() < 1e-9);
+        
+        let outside = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 4.0e7]}, "properties": {}}"#;
+        let result = generate_tiles_with_options(outside.as_bytes(), 14, 14, "stations", &options);
+        assert!(matches!(result, Err(ConverterError::Projection(_))));
+    }
+    
+    #[test]
+    fn test_attributes_min_zoom() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo", "rank": 1}}"#;
+        let options = TilerOptions { attributes_min_zoom: 8, ..Default::default() };
+        
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 12, "places", &options).unwrap();
+        let layer_at = |z: u8| {
+            let tile = tiles.iter().find(|t| t.zoom() == Some(z)).unwrap();
+            mvt_encoder::vector_tile::Tile::decode(tile.data.as_slice()).unwrap().layers.remove(0)
+        };
+        
+        let overview = layer_at(0);
+        assert!(overview.keys.is_empty() && overview.values.is_empty());
+        assert_eq!(overview.features.len(), 1);
+        assert!(overview.features[0].tags.is_empty());
+        assert!(layer_at(7).keys.is_empty());
+        
+        let detail = layer_at(12);
+        assert_eq!(detail.keys, vec!["name", "rank"]);
+        assert_eq!(detail.features[0].tags.len(), 4);
+        assert!(!layer_at(8).keys.is_empty());
+        
+        assert_eq!(metadata.fields["name"], "String");
+    }
+    
+    #[test]
+    fn test_bbox_properties() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "bbox": [139.5, 35.5, 140.0, 36.0], "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.6, 35.6], [139.8, 35.7]]}, "properties": {"name": "Line"}}
+        ]}"#;
+        let tags_of = |bbox_properties| {
+            let options = TilerOptions { bbox_properties, ..Default::default() };
+            let (tiles, _) = generate_tiles_with_options(geojson.as_bytes(), 0, 0, "places", &options).unwrap();
+            let layer = &mvt_encoder::vector_tile::Tile::decode(tiles[0].data.as_slice()).unwrap().layers[0];
+            layer
+                .features
+                .iter()
+                .map(|feature| {
+                    feature
+                        .tags
+                        .chunks(2)
+                        .filter(|tag| layer.keys[tag[0] as usize].starts_with('_'))
+                        .map(|tag| (layer.keys[tag[0] as usize].clone(), layer.values[tag[1] as usize].double_value))
+                        .collect::<Vec<_>>()
+                })
+                .collect::<Vec<_>>()
+        };
+        
+        // Tags are written in key order
+        let expected = |[min_x, min_y, max_x, max_y]: [f64; 4]| {
+            [("_maxx", max_x), ("_maxy", max_y), ("_minx", min_x), ("_miny", min_y)]
+                .map(|(key, value)| (key.to_string(), Some(value)))
+                .to_vec()
+        };
+        let tags = tags_of(geojson_parser::BboxProperties::Input);
+        assert_eq!(tags[0], expected([139.5, 35.5, 140.0, 36.0]));
+        assert!(tags[1].is_empty());
+        
+        let tags = tags_of(geojson_parser::BboxProperties::InputOrComputed);
+        assert_eq!(tags[0], expected([139.5, 35.5, 140.0, 36.0]));
+        assert_eq!(tags[1], expected([139.6, 35.6, 139.8, 35.7]));
+        
+        assert!(tags_of(geojson_parser::BboxProperties::Off).iter().all(Vec::is_empty));
+    }
+    
+    #[test]
+    fn test_tile_value_overflow() {
+        let features: Vec<String> = (0..100_000)
+            .map(|i| {
+                format!(
+                    r#"{{"type": "Feature", "geometry": {{"type": "Point", "coordinates": [{}, {}]}}, "properties": {{"name": "feature {}", "kind": "{}"}}}}"#,
+                    (i % 1000) as f64 * 0.1,
+                    (i / 1000) as f64 * 0.1,
+                    i,
+                    if i % 2 == 0 { "even" } else { "odd" }
+                )
+            })
+            .collect();
+        let geojson = format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, features.join(","));
+        
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 0, "points", &TilerOptions::default()).unwrap();
+        assert_eq!(metadata.diagnostics.value_overflow_tiles, 1);
+        assert!(metadata.diagnostics.warnings.iter().any(|w| w.contains("Tile 0/0/0 has 100002 unique property values")));
+        let layer = &mvt_encoder::vector_tile::Tile::decode(tiles[0].data.as_slice()).unwrap().layers[0];
+        assert_eq!(layer.values.len(), 100_002);
+        
+        let options = TilerOptions { value_overflow_placeholder: Some("…".to_string()), ..Default::default() };
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 0, "points", &options).unwrap();
+        assert_eq!(metadata.diagnostics.value_overflow_tiles, 1);
+        let layer = &mvt_encoder::vector_tile::Tile::decode(tiles[0].data.as_slice()).unwrap().layers[0];
+        let mut values: Vec<_> = layer.values.iter().map(|v| v.string_value.clone().unwrap()).collect();
+        values.sort();
+        assert_eq!(values, vec!["even", "odd", "…"]);
+        
+        let options = TilerOptions { max_tile_values: 200_000, ..Default::default() };
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 0, "points", &options).unwrap();
+        assert_eq!(metadata.diagnostics.value_overflow_tiles, 0);
+    }
+    
+    #[test]
+    fn test_layer_name_template() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.70, 35.60], [139.71, 35.61]]}, "properties": {"name": "road"}}"#;
+        let options = TilerOptions { layer_name_template: Some("{layer}_z{z}".to_string()), ..Default::default() };
+        
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 9, 10, "roads", &options).unwrap();
+        let layer_name = |z: u8| {
+            let tile = tiles.iter().find(|t| t.zoom() == Some(z)).unwrap();
+            mvt_encoder::vector_tile::Tile::decode(tile.data.as_slice()).unwrap().layers[0].name.clone()
+        };
+        assert_eq!(layer_name(10), "roads_z10");
+        assert_eq!(layer_name(9), "roads_z9");
+        
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        let layers: Vec<(&str, u64, u64)> = tilejson["vector_layers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| (l["id"].as_str().unwrap(), l["minzoom"].as_u64().unwrap(), l["maxzoom"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(layers, vec![("roads_z9", 9, 9), ("roads_z10", 10, 10)]);
+        assert_eq!(tilejson["vector_layers"][1]["fields"]["name"], "String");
+        
+        let invalid = TilerOptions { layer_name_template: Some("{layer}.z{z}".to_string()), ..Default::default() };
+        assert!(matches!(
+            generate_tiles_with_options(geojson.as_bytes(), 9, 10, "roads", &invalid),
+            Err(ConverterError::InvalidInput(_))
+        ));
+    }
+    
+    #[test]
+    fn test_geometry_type_per_layer() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [[[139.70, 35.60], [139.71, 35.60], [139.71, 35.61], [139.70, 35.60]]]}, "properties": {"kind": "parks"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.705, 35.605]}, "properties": {"kind": "stations"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.706, 35.606]}, "properties": {"kind": "stations"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.707, 35.607]}, "properties": {"kind": "stations"}}
+        ]}"#;
+        let options = TilerOptions { layer_by_field: Some("kind".to_string()), ..Default::default() };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 10, 10, "city", &options).unwrap();
+        assert_eq!(metadata.geometry_type, "Point");
+        let layers: Vec<(String, String)> = metadata.output_layers().into_iter().map(|l| (l.name, l.geometry_type)).collect();
+        assert_eq!(
+            layers,
+            vec![("parks".to_string(), "Polygon".to_string()), ("stations".to_string(), "Point".to_string())]
+        );
+        
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        assert_eq!(tilejson["tilestats"]["layers"][0]["geometry"], "Polygon");
+        assert_eq!(tilejson["tilestats"]["layers"][1]["geometry"], "Point");
+    }
+    
+    #[test]
+    fn test_layer_descriptions() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}}"#;
+        let options = TilerOptions {
+            layer_descriptions: [("stations".to_string(), "Railway stations in Tokyo".to_string())].into(),
+            ..Default::default()
+        };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 4, "stations", &options).unwrap();
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        assert_eq!(tilejson["vector_layers"][0]["id"], "stations");
+        assert_eq!(tilejson["vector_layers"][0]["description"], "Railway stations in Tokyo");
+        assert_eq!(tilejson["vector_layers"][0]["fields"]["name"], "String");
+        assert_eq!(tilejson["tilestats"]["layers"][0]["count"], 1);
+        
+        let rows = mbtiles::mbtiles_metadata_rows(&metadata).unwrap();
+        let json = rows.iter().find(|(name, _)| name == "json").map(|(_, value)| value).unwrap();
+        let json: serde_json::Value = serde_json::from_str(json).unwrap();
+        assert_eq!(json["vector_layers"][0]["description"], "Railway stations in Tokyo");
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 4, "other", &options).unwrap();
+        assert_eq!(pmtiles_encoder::build_tilejson(&metadata, None)["vector_layers"][0]["description"], "");
+    }
+    
+    #[test]
+    #[cfg(all(feature = "std-fs", not(target_arch = "wasm32")))]
+    fn test_file_path_api() {
+        let dir = std::env::temp_dir().join(format!("vector-tile-core-fs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let geojson_path = dir.join("input.geojson");
+        std::fs::write(
+            &geojson_path,
+            r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}}"#,
+        )
+        .unwrap();
+        
+        let pmtiles_path = dir.join("out.pmtiles");
+        generate_pmtiles_from_path(&geojson_path, &pmtiles_path, 0, 2, "places", &TilerOptions::default()).unwrap();
+        let archive = std::fs::read(&pmtiles_path).unwrap();
+        assert!(pmtiles_encoder::validate_pmtiles(&archive).is_ok());
+        
+        let tree = dir.join("tiles");
+        let metadata = generate_tile_tree_to_dir(&geojson_path, &tree, 0, 2, "places", &TilerOptions::default()).unwrap();
+        assert_eq!(metadata.layer_name, "places");
+        for path in ["0/0/0.pbf", "1/1/0.pbf", "2/3/1.pbf"] {
+            assert!(tree.join(path).is_file(), "{} missing", path);
+        }
+        
+        let missing =
+            generate_pmtiles_from_path(dir.join("missing.geojson"), &pmtiles_path, 0, 2, "places", &TilerOptions::default());
+        assert!(matches!(missing, Err(ConverterError::Io(_))));
+        
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+    
+    #[test]
+    fn test_standalone_tilejson() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}}"#;
+        let (_, metadata) = generate_tiles_with_metadata(geojson.as_bytes(), 0, 4, "stations").unwrap();
+        
+        let url = "https://tiles.example.com/stations/{z}/{x}/{y}.pbf";
+        let tilejson = pmtiles_encoder::build_standalone_tilejson(&metadata, url, pmtiles_encoder::TileScheme::Tms).unwrap();
+        assert_eq!(tilejson["tilejson"], "3.0.0");
+        assert_eq!(tilejson["scheme"], "tms");
+        assert_eq!(tilejson["tiles"], serde_json::json!([url]));
+        assert_eq!(tilejson["minzoom"], 0);
+        assert_eq!(tilejson["maxzoom"], 4);
+        assert_eq!(tilejson["vector_layers"][0]["id"], "stations");
+        
+        let xyz = pmtiles_encoder::build_standalone_tilejson(&metadata, url, Default::default()).unwrap();
+        assert_eq!(xyz["scheme"], "xyz");
+        
+        for invalid in ["https://tiles.example.com/stations.pbf", "{z}/{y}/{x}.pbf"] {
+            assert!(matches!(
+                pmtiles_encoder::build_standalone_tilejson(&metadata, invalid, pmtiles_encoder::TileScheme::Xyz),
+                Err(ConverterError::InvalidInput(_))
+            ));
+        }
+    }
+    
+    #[test]
+    fn test_label_layer() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [[[139.70, 35.60], [139.72, 35.60], [139.72, 35.62], [139.70, 35.62], [139.70, 35.60]]]}, "properties": {"name": "Park"}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.70, 35.61], [139.72, 35.61]]}, "properties": {"name": "Path"}}
+        ]}"#;
+        let options = TilerOptions { label_layer: Some((0, 10)), ..Default::default() };
+        
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 10, 11, "parks", &options).unwrap();
+        let tile_layers = |z: u8| {
+            let tile = tiles.iter().find(|t| t.zoom() == Some(z)).unwrap();
+            mvt_encoder::vector_tile::Tile::decode(tile.data.as_slice()).unwrap().layers
+        };
+        let layers = tile_layers(10);
+        let names: Vec<&str> = layers.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(names, vec!["parks", "parks_labels"]);
+        assert_eq!(layers[0].features.len(), 2);
+        let labels = &layers[1];
+        assert_eq!(labels.features.len(), 1);
+        assert_eq!(labels.features[0].r#type(), mvt_encoder::vector_tile::tile::GeomType::Point);
+        assert_eq!(labels.values[0].string_value.as_deref(), Some("Park"));
+        assert_eq!(tile_layers(11).len(), 1);
+        
+        let tilejson = pmtiles_encoder::build_tilejson(&metadata, None);
+        let layers: Vec<(&str, u64, u64)> = tilejson["vector_layers"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|l| (l["id"].as_str().unwrap(), l["minzoom"].as_u64().unwrap(), l["maxzoom"].as_u64().unwrap()))
+            .collect();
+        assert_eq!(layers, vec![("parks", 10, 11), ("parks_labels", 10, 10)]);
+        assert_eq!(tilejson["tilestats"]["layers"][1]["geometry"], "Point");
+        assert_eq!(tilejson["tilestats"]["layers"][1]["count"], 1);
+    }
+    
+    #[test]
+    fn test_memory_checkpoints() {
+        let stages = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        let recorded = stages.clone();
+        memory::set_checkpoint_hook(Some(Box::new(move |stage, _heap| recorded.borrow_mut().push(stage.to_string()))));
+        
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {}}"#;
+        let result = generate_tiles_with_metadata(geojson.as_bytes(), 3, 4, "layer");
+        memory::set_checkpoint_hook(None);
+        result.unwrap();
+        
+        assert_eq!(
+            *stages.borrow(),
+            vec!["parsed", "tiled zoom 3", "encoded zoom 3", "tiled zoom 4", "encoded zoom 4"]
+        );
+    }
+
+    #[test]
+    fn test_generate_tile_tree_compressed() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {"name": "Tokyo"}}"#;
+        let raw = generate_tiles(geojson.as_bytes(), 0, 2, "layer").unwrap();
+        let options = TilerOptions {
+            compress_tiles: true,
+            ..Default::default()
+        };
+        let (compressed, _) = generate_tile_tree(geojson.as_bytes(), 0, 2, "layer", &options).unwrap();
+        
+        assert_eq!(raw.len(), compressed.len());
+        for (raw, compressed) in raw.iter().zip(&compressed) {
+            assert_eq!(raw.path, compressed.path);
+            assert_eq!(&compressed.data[..2], &[0x1f, 0x8b]);
+            let mut decompressed = Vec::new();
+            std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(compressed.data.as_slice()), &mut decompressed)
+                .unwrap();
+            assert_eq!(decompressed, raw.data);
+        }
+        
+        // Raw by default
+        let (tree, _) = generate_tile_tree(geojson.as_bytes(), 0, 2, "layer", &TilerOptions::default()).unwrap();
+        assert_eq!(tree[0].data, raw[0].data);
+    }
+
+    #[test]
+    fn test_center_zoom_from_extent() {
+        let collection = |coordinates: &[(f64, f64)]| {
+            let features: Vec<String> = coordinates
+                .iter()
+                .map(|(lon, lat)| {
+                    format!(
+                        r#"{{"type": "Feature", "geometry": {{"type": "Point", "coordinates": [{}, {}]}}, "properties": {{}}}}"#,
+                        lon, lat
+                    )
+                })
+                .collect();
+            format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, features.join(","))
+        };
+        
+        let city = collection(&[(139.70, 35.65), (139.78, 35.70)]);
+        let continent = collection(&[(-10.0, 36.0), (40.0, 70.0)]);
+        let (_, city) = generate_tiles_with_metadata(city.as_bytes(), 0, 14, "layer").unwrap();
+        let (_, continent) = generate_tiles_with_metadata(continent.as_bytes(), 0, 14, "layer").unwrap();
+        assert!(city.center_zoom > continent.center_zoom);
+        assert!(city.center_zoom >= 10);
+        assert!(continent.center_zoom <= 3);
+        
+        let options = TilerOptions {
+            center_zoom: Some(5),
+            ..Default::default()
+        };
+        let geojson = collection(&[(139.70, 35.65)]);
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 14, "layer", &options).unwrap();
+        assert_eq!(metadata.center_zoom, 5);
+    }
+
+    #[test]
+    fn test_rename_fields_in_metadata() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}, "properties": {"NAME_JA": "東京", "name": "Tokyo"}}"#;
+        let options = TilerOptions {
+            rename_fields: std::collections::HashMap::from([("NAME_JA".to_string(), "name".to_string())]),
+            ..Default::default()
+        };
+        
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 0, "layer", &options).unwrap();
+        assert_eq!(metadata.fields.len(), 1);
+        assert_eq!(metadata.attributes[0]["values"][0], "東京");
+        assert_eq!(metadata.diagnostics.warnings.len(), 1);
+        assert!(metadata.diagnostics.warnings[0].contains("\"name\""));
+    }
+
+    #[test]
+    fn test_null_geometry_features_are_skipped() {
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": null, "properties": {"source": "survey 2024"}},
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [0, 0]}, "properties": {"name": "a"}}
+            ]
+        }"#;
+        
+        let (tiles, metadata) = generate_tiles_with_metadata(geojson.as_bytes(), 0, 0, "layer").unwrap();
+        assert_eq!(metadata.feature_count, 1);
+        assert!(!metadata.fields.contains_key("source"));
+        assert!(metadata.diagnostics.warnings.iter().any(|w| w.starts_with("1 feature(s) without geometry")));
+        
+        let tile = mvt_encoder::vector_tile::Tile::decode(tiles[0].data.as_slice()).unwrap();
+        assert_eq!(tile.layers[0].features.len(), 1);
+        assert_eq!(tile.layers[0].keys, vec!["name"]);
+        
+        let options = TilerOptions {
+            attribute_only_stats: true,
+            ..Default::default()
+        };
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 0, "layer", &options).unwrap();
+        assert_eq!(metadata.feature_count, 1);
+        assert_eq!(metadata.fields["source"], "String");
+    }
+
+    #[test]
+    fn test_heaviest_tiles_in_diagnostics() {
+        // A few scattered points plus a dense cluster in Tokyo (z2 tile 3/1)
+        let mut features: Vec<String> = [(-100.0, 40.0), (-60.0, -20.0), (20.0, 10.0), (30.0, -30.0)]
+            .iter()
+            .map(|(lon, lat)| format!(r#"{{"type":"Feature","geometry":{{"type":"Point","coordinates":[{},{}]}},"properties":{{}}}}"#, lon, lat))
+            .collect();
+        for i in 0..50 {
+            features.push(format!(
+                r#"{{"type":"Feature","geometry":{{"type":"Point","coordinates":[{},{}]}},"properties":{{"id":{}}}}}"#,
+                139.0 + i as f64 * 0.01, 35.0 + i as f64 * 0.01, i
+            ));
+        }
+        let geojson = format!(r#"{{"type":"FeatureCollection","features":[{}]}}"#, features.join(","));
+        
+        let (tiles, metadata) = generate_tiles_with_metadata(geojson.as_bytes(), 0, 2, "points").unwrap();
+        let diagnostics = &metadata.diagnostics;
+        
+        let densest = diagnostics.densest_tiles.iter().find(|t| t.z == 2).unwrap();
+        assert_eq!((densest.x, densest.y, densest.features), (3, 1, 50));
+        let largest = diagnostics.largest_tiles.iter().find(|t| t.z == 2).unwrap();
+        assert_eq!((largest.x, largest.y), (3, 1));
+        
+        // The heaviest tile overall is z0, which holds every feature
+        assert_eq!(diagnostics.largest_tiles[0].z, 0);
+        assert_eq!(diagnostics.largest_tiles[0].bytes, tiles.iter().find(|t| t.path == "0/0/0.pbf").unwrap().data.len());
+        assert!(diagnostics.largest_tiles.len() <= diagnostics::HEAVIEST_TILE_COUNT);
+    }
+
+    #[test]
+    fn test_generate_tiles_zip() {
+        let geojson = r#"{"type":"Feature","geometry":{"type":"Point","coordinates":[139.7,35.6]},"properties":{"name":"Tokyo"}}"#;
+        
+        let read_entries = |archive: Vec<u8>| -> Vec<(String, Vec<u8>)> {
+            let mut zip = zip::ZipArchive::new(std::io::Cursor::new(archive)).unwrap();
+            (0..zip.len())
+                .map(|i| {
+                    let mut file = zip.by_index(i).unwrap();
+                    let mut data = Vec::new();
+                    std::io::Read::read_to_end(&mut file, &mut data).unwrap();
+                    (file.name().to_string(), data)
+                })
+                .collect()
+        };
+        
+        let archive = generate_tiles_zip(geojson.as_bytes(), 0, 2, "places", &TilerOptions::default()).unwrap();
+        let entries = read_entries(archive);
+        let names: Vec<&str> = entries.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["0/0/0.pbf", "1/1/0.pbf", "2/3/1.pbf", "metadata.json"]);
+        
+        let tile = mvt_encoder::vector_tile::Tile::decode(entries[2].1.as_slice()).unwrap();
+        assert_eq!(tile.layers[0].name, "places");
+        let tilejson: serde_json::Value = serde_json::from_slice(&entries[3].1).unwrap();
+        assert_eq!(tilejson["tiles"][0], "{z}/{x}/{y}.pbf");
+        assert_eq!(tilejson["maxzoom"], 2);
+        
+        let options = TilerOptions {
+            zip_tile_compression: pmtiles_encoder::TileCompression::Gzip,
+            ..Default::default()
+        };
+        let archive = generate_tiles_zip(geojson.as_bytes(), 0, 0, "places", &options).unwrap();
+        let entries = read_entries(archive);
+        assert_eq!(entries[0].0, "0/0/0.pbf.gz");
+        assert_eq!(&entries[0].1[..2], &[0x1f, 0x8b]);
+    }
+
+    #[test]
+    fn test_property_stats_skip_null_values() {
+        let mut stats = PropertyStats::default();
+        stats.add(serde_json::json!({"population": null, "sparse": null}).as_object().unwrap());
+        stats.add(serde_json::json!({"population": 12}).as_object().unwrap());
+        
+        let (fields, _) = stats.finish();
+        assert_eq!(fields["population"], "Number");
+        assert!(!fields.contains_key("sparse"));
+    }
+
+    #[test]
+    fn test_date_values() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7, 35.6]}, "properties": {"at": "2023-01-02T03:04:05Z", "name": "2023 plan"}}"#;
+        let tag = |date_values| {
+            let options = TilerOptions { date_values, ..Default::default() };
+            let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 0, "events", &options).unwrap();
+            let tile = mvt_encoder::vector_tile::Tile::decode(tiles[0].data.as_slice()).unwrap();
+            let layer = &tile.layers[0];
+            let at = layer.keys.iter().position(|k| k == "at").unwrap() as u32;
+            let value_index = layer.features[0].tags.chunks(2).find(|t| t[0] == at).unwrap()[1];
+            (layer.values[value_index as usize].clone(), metadata.fields)
+        };
+        
+        let (value, fields) = tag(mvt_encoder::DateValues::EpochSeconds);
+        assert_eq!(value.int_value, Some(1672628645));
+        assert_eq!(fields["at"], "Date");
+        assert_eq!(fields["name"], "String");
+        
+        let (value, fields) = tag(mvt_encoder::DateValues::Iso);
+        assert_eq!(value.string_value.as_deref(), Some("2023-01-02T03:04:05Z"));
+        assert_eq!(fields["at"], "Date");
+        
+        let (value, fields) = tag(mvt_encoder::DateValues::Keep);
+        assert_eq!(value.string_value.as_deref(), Some("2023-01-02T03:04:05Z"));
+        assert_eq!(fields["at"], "String");
+    }
+    
+    #[test]
+    fn test_property_stats_numeric_min_max() {
+        let mut stats = PropertyStats::default();
+        for height in [12.5, -3.0, 40.0, 7.0] {
+            let mut properties = serde_json::Map::new();
+            properties.insert("height".to_string(), serde_json::json!(height));
+            properties.insert("name".to_string(), serde_json::json!("a"));
+            stats.add(&properties);
+        }
+        
+        let (fields, attributes) = stats.finish();
+        assert_eq!(fields["height"], "Number");
+        
+        let height = attributes.iter().find(|a| a["attribute"] == "height").unwrap();
+        assert_eq!(height["min"], -3.0);
+        assert_eq!(height["max"], 40.0);
+        assert_eq!(height["count"], 4);
+        
+        let name = attributes.iter().find(|a| a["attribute"] == "name").unwrap();
+        assert!(name.get("min").is_none());
+        assert!(name.get("max").is_none());
+    }
+
+    #[test]
+    fn test_property_stats_max_unique_values() {
+        let mut stats = PropertyStats {
+            options: TilerOptions {
+                max_unique_values: 5,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        for i in 0..20 {
+            stats.add(serde_json::json!({"code": format!("c{:02}", i), "kind": "a"}).as_object().unwrap());
+        }
+        
+        let (_, attributes) = stats.finish();
+        let code = attributes.iter().find(|a| a["attribute"] == "code").unwrap();
+        assert_eq!(code["values"], serde_json::json!(["c00", "c01", "c02", "c03", "c04"]));
+        assert_eq!(code["count"], 5);
+        assert_eq!(code["truncated"], true);
+        
+        let kind = attributes.iter().find(|a| a["attribute"] == "kind").unwrap();
+        assert!(kind.get("truncated").is_none());
+    }
+
+    #[test]
+    fn test_property_stats_nested_values() {
+        let mut properties = serde_json::Map::new();
+        properties.insert("prop".to_string(), serde_json::json!({ "a": 1 }));
+        
+        let mut stats = PropertyStats::default();
+        stats.add(&properties);
+        let (fields, attributes) = stats.finish();
+        assert_eq!(fields["prop"], "String");
+        assert_eq!(attributes[0]["values"][0], r#"{"a":1}"#);
+        
+        let mut stats = PropertyStats {
+            options: TilerOptions {
+                nested_values: mvt_encoder::NestedValues::Flatten,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        stats.add(&properties);
+        let (fields, _) = stats.finish();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields["prop.a"], "Number");
+    }
+
+    #[test]
+    fn test_generate_single_tile_matches_full_run() {
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}},
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[135.5, 34.7], [139.7, 35.7]]}, "properties": {"name": "Route"}},
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [-0.1276, 51.5072]}, "properties": {"name": "London"}}
+            ]
+        }"#;
+        let options = TilerOptions::default();
+        
+        let full = generate_tiles(geojson.as_bytes(), 4, 4, "places").unwrap();
+        let tokyo = full.iter().find(|t| t.path == "4/14/6.pbf").unwrap();
+        
+        let single = generate_single_tile(geojson.as_bytes(), TileCoord::new(4, 14, 6), "places", &options)
+            .unwrap()
+            .unwrap();
+        assert_eq!(single, tokyo.data);
+        
+        let empty = generate_single_tile(geojson.as_bytes(), TileCoord::new(4, 0, 15), "places", &options).unwrap();
+        assert!(empty.is_none());
+    }
+    
+    #[test]
+    fn test_tile_generator_chunks_match_one_shot() {
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}},
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[135.5, 34.7], [139.7, 35.7]]}, "properties": {"name": "Route"}}
+            ]
+        }"#;
+        let options = TilerOptions::default();
+        
+        // Split inside the coordinates of the first feature
+        let (head, tail) = geojson.as_bytes().split_at(geojson.find("35.68").unwrap());
+        let mut generator = TileGenerator::new(0, 6, "places", &options).unwrap();
+        generator.push_bytes(head).unwrap();
+        generator.push_bytes(tail).unwrap();
+        let (mut chunked, chunked_metadata) = generator.finish().unwrap();
+        let (mut one_shot, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 6, "places", &options).unwrap();
+        
+        chunked.sort_by(|a, b| a.path.cmp(&b.path));
+        one_shot.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(chunked.len(), one_shot.len());
+        for (a, b) in chunked.iter().zip(&one_shot) {
+            assert_eq!((&a.path, &a.data), (&b.path, &b.data));
+        }
+        assert_eq!(chunked_metadata.feature_count, metadata.feature_count);
+        assert_eq!(chunked_metadata.bounds, metadata.bounds);
+        
+        // A truncated stream is rejected
+        let mut truncated = TileGenerator::new(0, 6, "places", &options).unwrap();
+        truncated.push_bytes(head).unwrap();
+        assert!(matches!(truncated.finish(), Err(ConverterError::Parse(_))));
+    }
+    
+    #[test]
+    fn test_tile_generator_options() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[135.5, 34.7], [139.7, 35.7]]}, "properties": {}},
+            {"type": "Feature", "geometry": null, "properties": {"note": "survey"}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {}}
+        ]}"#;
+        let generate = |options: &TilerOptions| {
+            let mut generator = TileGenerator::new(0, 8, "places", options)?;
+            for chunk in geojson.as_bytes().chunks(16) {
+                generator.push_bytes(chunk)?;
+            }
+            generator.finish()
+        };
+        
+        let options = TilerOptions { auto_cap_max_zoom: true, max_estimated_tiles: 10, ..Default::default() };
+        let (tiles, metadata) = generate(&options).unwrap();
+        let (one_shot, one_shot_metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 8, "places", &options).unwrap();
+        assert_eq!(metadata.max_zoom, one_shot_metadata.max_zoom);
+        assert!(metadata.max_zoom < 8);
+        assert_eq!(tiles.len(), one_shot.len());
+        assert_eq!(metadata.diagnostics.warnings, one_shot_metadata.diagnostics.warnings);
+        
+        let limited = TilerOptions { max_input_bytes: Some(geojson.len() - 1), ..Default::default() };
+        assert!(matches!(generate(&limited), Err(ConverterError::TooLarge(_))));
+    }
+    
+    #[test]
+    fn test_index_tiles_by_zoom() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[135.5, 34.7], [139.7, 35.7]]}, "properties": {}}"#;
+        let tiles = generate_tiles(geojson.as_bytes(), 3, 7, "routes").unwrap();
+        
+        let by_zoom = index_tiles_by_zoom(&tiles);
+        assert_eq!(by_zoom.keys().copied().collect::<Vec<_>>(), vec![3, 4, 5, 6, 7]);
+        assert_eq!(by_zoom.values().map(Vec::len).sum::<usize>(), tiles.len());
+        for (zoom, indices) in &by_zoom {
+            let prefix = format!("{}/", zoom);
+            let expected = tiles.iter().filter(|tile| tile.path.starts_with(&prefix)).count();
+            assert_eq!(indices.len(), expected);
+            assert!(indices.iter().all(|&index| tiles[index].path.starts_with(&prefix)));
+        }
+    }
+    
+    #[test]
+    fn test_generate_tiles_for_coords() {
+        let geojson = r#"{
+            "type": "FeatureCollection",
+            "features": [
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}},
+                {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[135.5, 34.7], [139.7, 35.7]]}, "properties": {"name": "Route"}},
+                {"type": "Feature", "geometry": {"type": "Point", "coordinates": [-0.1276, 51.5072]}, "properties": {"name": "London"}}
+            ]
+        }"#;
+        let options = TilerOptions::default();
+        let full = generate_tiles(geojson.as_bytes(), 2, 6, "places").unwrap();
+        
+        let coords = [TileCoord::new(6, 56, 25), TileCoord::new(3, 3, 2), TileCoord::new(5, 0, 0)];
+        let tiles = generate_tiles_for_coords(geojson.as_bytes(), &coords, "places", &options).unwrap();
+        let paths: Vec<&str> = tiles.iter().map(|t| t.path.as_str()).collect();
+        // The empty tile is left out; the others match a full run
+        assert_eq!(paths, vec!["6/56/25.pbf", "3/3/2.pbf"]);
+        for tile in &tiles {
+            assert_eq!(tile.data, full.iter().find(|t| t.path == tile.path).unwrap().data);
+        }
+        
+        let out_of_range = generate_tiles_for_coords(geojson.as_bytes(), &[TileCoord::new(1, 2, 0)], "places", &options);
+        assert!(matches!(out_of_range, Err(ConverterError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_mixed_dimensions() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.6, 35.6, 10.0], [139.7, 35.65], [139.8, 35.7, 12.0]]}, "properties": {}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.6, 35.6, 10.0], [139.8, 35.7, 12.0]]}, "properties": {}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {}}
+        ]}"#;
+        let (_, metadata) = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "roads", &TilerOptions::default()).unwrap();
+        assert_eq!(metadata.diagnostics.mixed_dimension_features, 1);
+        assert!(metadata.diagnostics.warnings.iter().any(|w| w.contains("mix 2D and 3D")));
+        
+        let strict = TilerOptions { strict_dimensions: true, ..Default::default() };
+        let result = generate_tiles_with_options(geojson.as_bytes(), 0, 2, "roads", &strict);
+        assert!(matches!(result, Err(ConverterError::InvalidInput(_))));
+        let mut generator = TileGenerator::new(0, 2, "roads", &strict).unwrap();
+        assert!(matches!(generator.push_bytes(geojson.as_bytes()), Err(ConverterError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_options_from_json() {
+        let options = TilerOptions::from_json(r#"{
+            "tile_compression": "none",
+            "tile_compression_levels": [{"min_zoom": 0, "max_zoom": 4, "level": 9}],
+            "feature_order": {"attribute": "rank"},
+            "bbox_properties": "input_or_computed",
+            "mvt_version": "v1",
+            "simplify_tolerance": 0.5,
+            "clamp_margin": 64,
+            "bounds_override": [139.0, 35.0, 140.0, 36.0],
+            "rename_fields": {"NAME": "name"},
+            "attribution": "© Example",
+            "label_layer": [10, 14],
+            "clustered": false
+        }"#)
+        .unwrap();
+        assert_eq!(options.tile_compression, pmtiles_encoder::TileCompression::None);
+        assert_eq!(options.tile_compression_levels[0].level, 9);
+        assert_eq!(options.feature_order, mvt_encoder::FeatureOrder::Attribute("rank".to_string()));
+        assert_eq!(options.bbox_properties, geojson_parser::BboxProperties::InputOrComputed);
+        assert_eq!(options.mvt_version, mvt_encoder::MvtVersion::V1);
+        assert_eq!(options.simplify_tolerance, 0.5);
+        assert_eq!(options.clamp_margin, Some(64));
+        assert_eq!(options.bounds_override, Some((139.0, 35.0, 140.0, 36.0)));
+        assert_eq!(options.rename_fields["NAME"], "name");
+        assert_eq!(options.attribution.as_deref(), Some("© Example"));
+        assert_eq!(options.label_layer, Some((10, 14)));
+        assert!(!options.clustered);
+        // Missing fields keep their default
+        assert_eq!(options.max_tile_values, DEFAULT_MAX_TILE_VALUES);
+        assert!(options.sanitize_layer_name);
+        
+        let unknown = TilerOptions::from_json(r#"{"clusterd": false}"#);
+        assert!(matches!(unknown, Err(ConverterError::InvalidInput(m)) if m.contains("unknown field `clusterd`")));
+        let invalid = TilerOptions::from_json(r#"{"tile_compression": "brotli"}"#);
+        assert!(matches!(invalid, Err(ConverterError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_point_grid() {
+        // Two dense clusters of 50 points, in different 256-unit cells of tile 3/7/3
+        let features: Vec<String> = [(139.7, 35.68), (143.0, 35.68)]
+            .iter()
+            .flat_map(|&(lon, lat)| {
+                (0..50).map(move |i| {
+                    let (lon, lat) = (lon + (i % 10) as f64 * 0.001, lat + (i / 10) as f64 * 0.001);
+                    format!(r#"{{"type": "Feature", "geometry": {{"type": "Point", "coordinates": [{}, {}]}}, "properties": {{}}}}"#, lon, lat)
+                })
+            })
+            .collect();
+        let geojson = format!(r#"{{"type": "FeatureCollection", "features": [{}]}}"#, features.join(","));
+        let options = TilerOptions { point_grid: Some((256, 3)), ..Default::default() };
+        let (tiles, metadata) = generate_tiles_with_options(geojson.as_bytes(), 3, 4, "poi", &options).unwrap();
+        
+        let feature_count = |z: u8| -> usize {
+            tiles
+                .iter()
+                .filter(|tile| tile.path.starts_with(&format!("{}/", z)))
+                .map(|tile| mvt_encoder::vector_tile::Tile::decode(tile.data.as_slice()).unwrap().layers[0].features.len())
+                .sum()
+        };
+        // One point per cell at zoom 3, all points above the zoom limit
+        assert_eq!(feature_count(3), 2);
+        assert_eq!(feature_count(4), 100);
+        assert_eq!(metadata.diagnostics.points_thinned, 98);
+    }
+    
+    #[test]
+    fn test_zoom_range_validation() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {}}
+        ]}"#;
+        let inverted = generate_tiles_with_metadata(geojson.as_bytes(), 8, 4, "places");
+        assert!(matches!(inverted, Err(ConverterError::InvalidInput(m)) if m.contains("above max zoom")));
+        let excessive = generate_tiles_with_metadata(geojson.as_bytes(), 0, 31, "places");
+        assert!(matches!(excessive, Err(ConverterError::InvalidInput(m)) if m.contains("maximum of 24")));
+        assert!(TileGenerator::new(0, MAX_ZOOM + 1, "places", &TilerOptions::default()).is_err());
+        assert!(preflight_geojson(geojson.as_bytes(), 5, 2, &TilerOptions::default()).is_err());
+        let options = TilerOptions::default();
+        assert!(debug_dump_geojson(geojson.as_bytes(), MAX_ZOOM + 1, &options).is_err());
+        assert!(generate_single_tile(geojson.as_bytes(), TileCoord::new(40, 0, 0), "places", &options).is_err());
+        assert!(generate_tiles_for_coords(geojson.as_bytes(), &[TileCoord::new(32, 0, 0)], "places", &options).is_err());
+        
+        let (tiles, _) = generate_tiles_with_metadata(geojson.as_bytes(), MAX_ZOOM, MAX_ZOOM, "places").unwrap();
+        assert_eq!(tiles.len(), 1);
+    }
 }
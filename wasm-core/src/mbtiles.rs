@@ -0,0 +1,70 @@
+// MBTiles metadata module
+// Rows of the MBTiles `metadata` table (name/value pairs), shared with the PMTiles TileJSON
+
+use crate::pmtiles_encoder::{build_tilejson, format_bounds, generator, generator_options};
+use crate::{ConverterError, TileMetadata};
+
+/// Build the rows of the MBTiles `metadata` table
+/// 
+/// Includes `name`, `format`, `type`, `bounds`, `center`, `minzoom`, `maxzoom`,
+/// `generator`, `generator_options`, `attribution` (when set) and the
+/// tippecanoe-compatible `json` row holding
+/// `vector_layers` and `tilestats` (when enabled), built from the same TileJSON as the
+/// PMTiles metadata (so tools like mbview can read it).
+/// Rows are returned in insertion order for `INSERT INTO metadata (name, value)`.
+pub fn mbtiles_metadata_rows(metadata: &TileMetadata) -> Result<Vec<(String, String)>, ConverterError> {
+    let tilejson = build_tilejson(metadata, None);
+    
+    let mut json = serde_json::Map::new();
+    for key in ["vector_layers", "tilestats"] {
+        if let Some(value) = tilejson.get(key) {
+            json.insert(key.to_string(), value.clone());
+        }
+    }
+    let json = serde_json::to_string(&json)
+        .map_err(|e| ConverterError::Encode(format!("Failed to serialize JSON: {}", e)))?;
+    
+    let mut rows = vec![
+        ("name".to_string(), metadata.layer_name.clone()),
+        ("format".to_string(), "pbf".to_string()),
+        ("type".to_string(), metadata.tileset_type.as_str().to_string()),
+        ("bounds".to_string(), format_bounds(metadata)),
+        ("center".to_string(), format!("{:.6},{:.6},{}", metadata.center.0, metadata.center.1, metadata.center_zoom)),
+        ("minzoom".to_string(), metadata.min_zoom.to_string()),
+        ("maxzoom".to_string(), metadata.max_zoom.to_string()),
+        ("generator".to_string(), generator(metadata)),
+        ("generator_options".to_string(), generator_options(metadata)),
+    ];
+    if let Some(attribution) = &metadata.attribution {
+        rows.push(("attribution".to_string(), attribution.clone()));
+    }
+    rows.push(("json".to_string(), json));
+    Ok(rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_mbtiles_metadata_json_row() {
+        let geojson = r#"{"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.7671, 35.6812]}, "properties": {"name": "Tokyo"}}"#;
+        let (_, metadata) = crate::generate_tiles_with_metadata(geojson.as_bytes(), 0, 4, "stations").unwrap();
+        
+        let rows = mbtiles_metadata_rows(&metadata).unwrap();
+        let row = |name: &str| rows.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str()).unwrap();
+        
+        assert_eq!(row("name"), "stations");
+        assert_eq!(row("format"), "pbf");
+        assert_eq!(row("type"), "overlay");
+        assert_eq!(row("minzoom"), "0");
+        assert_eq!(row("maxzoom"), "4");
+        assert_eq!(row("generator"), "web-vector-tile-maker");
+        assert_eq!(row("center"), "139.767100,35.681200,4");
+        
+        let json: serde_json::Value = serde_json::from_str(row("json")).unwrap();
+        assert_eq!(json["vector_layers"][0]["id"], "stations");
+        assert_eq!(json["vector_layers"][0]["fields"]["name"], "String");
+        assert_eq!(json["tilestats"]["layers"][0]["count"], 1);
+    }
+}
@@ -0,0 +1,42 @@
+// Memory module
+// Heap size estimates and checkpoints at pipeline stages, to help size inputs
+
+use std::cell::RefCell;
+
+/// Callback receiving `(stage, heap estimate in bytes)` at each checkpoint
+pub type CheckpointHook = Box<dyn Fn(&str, usize)>;
+
+thread_local! {
+    static HOOK: RefCell<Option<CheckpointHook>> = const { RefCell::new(None) };
+}
+
+/// Size of the Wasm linear memory in bytes
+///
+/// Linear memory only grows, so this is the high-water mark of the heap rather than
+/// the live size. Always 0 on native targets, where no cheap estimate is available.
+pub fn current_heap_estimate() -> usize {
+    #[cfg(target_arch = "wasm32")]
+    {
+        core::arch::wasm32::memory_size(0) * 65536
+    }
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        0
+    }
+}
+
+/// Install (or remove, with `None`) the checkpoint hook of the current thread
+///
+/// Returns the previous hook.
+pub fn set_checkpoint_hook(hook: Option<CheckpointHook>) -> Option<CheckpointHook> {
+    HOOK.with(|current| current.replace(hook))
+}
+
+/// Report a pipeline stage to the checkpoint hook, if one is installed
+pub fn checkpoint(stage: &str) {
+    HOOK.with(|hook| {
+        if let Some(hook) = hook.borrow().as_ref() {
+            hook(stage, current_heap_estimate());
+        }
+    });
+}
@@ -1,8 +1,11 @@
 // MVT (Mapbox Vector Tile) encoder
 // Encode tiles to binary format using Protocol Buffers
 
+use crate::geometry::grid_ring_area;
 use crate::tiler::{TileFeature, TileGeometry};
+use crate::{ConverterError, TilerOptions};
 use prost::Message;
+use std::cmp::Ordering;
 use std::collections::HashMap;
 
 // Protocol Buffer generated code
@@ -12,38 +15,432 @@ pub mod vector_tile {
 
 use vector_tile::tile::{GeomType, Layer, Feature, Value};
 
+/// Order of features within an encoded tile
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FeatureOrder {
+    /// Keep the input order (default)
+    #[default]
+    Input,
+    /// Sort by the value of a property; features without it come last
+    Attribute(String),
+    /// Sort by the Hilbert index of the geometry centroid, so nearby features are adjacent
+    Hilbert,
+}
+
+/// How property values that are arrays or objects are written to tiles
+/// 
+/// MVT values cannot nest, so such values would otherwise be lost.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NestedValues {
+    /// Write the value as a JSON string tag (default)
+    #[default]
+    Stringify,
+    /// Flatten objects into dotted keys (`addr.city`); arrays are still stringified
+    Flatten,
+}
+
+/// How `null` property values are written to tiles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NullValues {
+    /// Leave the property out of the feature's tags and the field statistics (default)
+    #[default]
+    Omit,
+    /// Write a tag whose value has no field set, which readers decode as null
+    Keep,
+}
+
+/// How ISO 8601 date strings in properties are written to tiles
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DateValues {
+    /// Write the string as it is (default); no date detection
+    #[default]
+    Keep,
+    /// Normalize to a UTC `YYYY-MM-DDTHH:MM:SSZ` string
+    Iso,
+    /// Convert to an integer of seconds since the Unix epoch
+    EpochSeconds,
+}
+
+/// Normalized value of a date-like string property, if `mode` converts dates
+pub fn normalize_date(value: &serde_json::Value, mode: DateValues) -> Option<serde_json::Value> {
+    if mode == DateValues::Keep {
+        return None;
+    }
+    let seconds = crate::date::parse_iso8601(value.as_str()?)?;
+    Some(match mode {
+        DateValues::EpochSeconds => seconds.into(),
+        _ => crate::date::format_iso8601(seconds).into(),
+    })
+}
+
+/// Visit the properties as they are written to tiles
+/// 
+/// Applies the nested value strategy, skips `null` values unless `options.null_values`
+/// keeps them, skips an `options.id_property` promoted to the feature id unless
+/// `options.keep_id_property` is set, then applies `options.rename_fields`. When several source
+/// keys end up with the same output key, the first in key order is kept; the others are
+/// skipped and returned as `(source key, output key)` pairs. Date strings are normalized
+/// according to `options.date_values`; `visit` is told whether the value is such a date.
+pub fn visit_properties(
+    properties: &serde_json::Map<String, serde_json::Value>,
+    options: &TilerOptions,
+    visit: &mut dyn FnMut(&str, &serde_json::Value, bool),
+) -> Vec<(String, String)> {
+    let mut written = std::collections::HashSet::new();
+    let mut collisions = Vec::new();
+    let promoted = match &options.id_property {
+        Some(id_property) if !options.keep_id_property && promoted_id(properties, options).is_some() => {
+            Some(id_property.as_str())
+        }
+        _ => None,
+    };
+    
+    flatten_properties(properties, options.nested_values, &mut |key, value| {
+        if value.is_null() && options.null_values == NullValues::Omit {
+            return;
+        }
+        if promoted == Some(key) {
+            return;
+        }
+        let output = options.rename_fields.get(key).map(String::as_str).unwrap_or(key);
+        if written.insert(output.to_string()) {
+            match normalize_date(value, options.date_values) {
+                Some(date) => visit(output, &date, true),
+                None => visit(output, value, false),
+            }
+        } else {
+            collisions.push((key.to_string(), output.to_string()));
+        }
+    });
+    
+    collisions
+}
+
+/// MVT feature id taken from `options.id_property`
+/// 
+/// Only non-negative integer values are promoted, and only for MVT v2 (v1 has no ids).
+pub fn promoted_id(properties: &serde_json::Map<String, serde_json::Value>, options: &TilerOptions) -> Option<u64> {
+    if options.mvt_version == MvtVersion::V1 {
+        return None;
+    }
+    options.id_property.as_ref().and_then(|key| properties.get(key)).and_then(serde_json::Value::as_u64)
+}
+
+/// Visit properties, flattening object values into dotted keys when requested
+fn flatten_properties(
+    properties: &serde_json::Map<String, serde_json::Value>,
+    mode: NestedValues,
+    visit: &mut dyn FnMut(&str, &serde_json::Value),
+) {
+    for (key, value) in properties {
+        match value {
+            serde_json::Value::Object(object) if mode == NestedValues::Flatten => {
+                let mut prefixed = |sub_key: &str, sub_value: &serde_json::Value| {
+                    visit(&format!("{}.{}", key, sub_key), sub_value)
+                };
+                flatten_properties(object, mode, &mut prefixed);
+            }
+            _ => visit(key, value),
+        }
+    }
+}
+
+/// MVT specification version written to each layer
+/// 
+/// Differences in the encoded output:
+/// - `V2` (default) writes a feature `id` (the feature's position in the layer, or the
+///   value of `TilerOptions::id_property`).
+/// - `V1` omits feature ids, which v1 readers do not expect.
+/// 
+/// Values are written as string, int, double or bool in both versions; all of these are
+/// part of the v1 value set, so no further restriction is needed. Polygon winding follows
+/// the v2 rules either way, which v1 readers accept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MvtVersion {
+    V1 = 1,
+    #[default]
+    V2 = 2,
+}
+
 /// Encode tile in MVT format
-pub fn encode_tile(features: &[TileFeature], layer_name: &str) -> Result<Vec<u8>, String> {
+pub fn encode_tile(features: &[TileFeature], layer_name: &str) -> Result<Vec<u8>, ConverterError> {
+    encode_tile_with_options(features, layer_name, &TilerOptions::default())
+}
+
+/// Encode tile in MVT format, applying `options.feature_order`
+/// 
+/// Sorting is stable, so the same input always produces the same tile. Feature ids
+/// are assigned in output order. `options.order_by_id` takes precedence over
+/// `feature_order`, which then only orders features with equal or no ids. With `options.layer_by_field`, features are split into
+/// one layer per value of that property (layers sorted by name, see `feature_layer_name`).
+pub fn encode_tile_with_options(
+    features: &[TileFeature],
+    layer_name: &str,
+    options: &TilerOptions,
+) -> Result<Vec<u8>, ConverterError> {
+    Ok(encode_layers(features, layer_name, None, options)?.data)
+}
+
+/// Encode the tile of zoom level `zoom` in MVT format, as `encode_tile_with_options`
+/// 
+/// Below `options.attributes_min_zoom`, features are written without tags (the layers
+/// have empty keys and values); feature ids are still written.
+pub fn encode_tile_at_zoom(
+    features: &[TileFeature],
+    layer_name: &str,
+    zoom: u8,
+    options: &TilerOptions,
+) -> Result<Vec<u8>, ConverterError> {
+    Ok(encode_tile_with_stats(features, layer_name, zoom, options)?.data)
+}
+
+/// Tile encoded by `encode_tile_with_stats`
+#[derive(Debug, Clone, PartialEq)]
+pub struct EncodedTile {
+    /// MVT bytes
+    pub data: Vec<u8>,
+    /// Unique values of the layer with the most, before `value_overflow_placeholder`
+    pub values: usize,
+    /// Whether string values were replaced by `options.value_overflow_placeholder`
+    pub coerced: bool,
+    /// Points dropped by `options.point_grid`
+    pub thinned: usize,
+    /// Features dropped by `options.validate_geometry`
+    pub invalid: usize,
+}
+
+/// Encode the tile of zoom level `zoom` like `encode_tile_at_zoom`, reporting the size
+/// of its value dictionaries
+/// 
+/// When a layer has more than `options.max_tile_values` unique values and
+/// `options.value_overflow_placeholder` is set, string values occurring only once in
+/// that layer are written as the placeholder, which removes high-cardinality fields
+/// (names, ids) from the dictionary while keeping shared values.
+/// 
+/// Up to the zoom of `options.point_grid`, only the first point (in output order) of
+/// each grid cell is kept per layer; lines and polygons are never dropped.
+pub fn encode_tile_with_stats(
+    features: &[TileFeature],
+    layer_name: &str,
+    zoom: u8,
+    options: &TilerOptions,
+) -> Result<EncodedTile, ConverterError> {
+    encode_layers(features, layer_name, Some(zoom), options)
+}
+
+/// Encode the tile, applying the per-zoom options if its `zoom` is known
+fn encode_layers(
+    features: &[TileFeature],
+    layer_name: &str,
+    zoom: Option<u8>,
+    options: &TilerOptions,
+) -> Result<EncodedTile, ConverterError> {
+    let with_tags = zoom.is_none_or(|zoom| zoom >= options.attributes_min_zoom);
+    let point_cell = options
+        .point_grid
+        .filter(|&(cell, max_zoom)| cell > 0 && zoom.is_some_and(|zoom| zoom <= max_zoom))
+        .map(|(cell, _)| cell as i32);
     if features.is_empty() {
-        return Err("Features are empty".to_string());
+        return Err(ConverterError::EmptyInput("Features are empty".to_string()));
+    }
+    
+    let mut order: Vec<usize> = (0..features.len()).collect();
+    match &options.feature_order {
+        FeatureOrder::Input => {}
+        FeatureOrder::Attribute(key) => {
+            order.sort_by(|&a, &b| {
+                compare_property(features[a].properties.get(key), features[b].properties.get(key))
+            });
+        }
+        FeatureOrder::Hilbert => {
+            order.sort_by_cached_key(|&idx| centroid_hilbert(&features[idx].geometry));
+        }
+    }
+    if options.order_by_id {
+        // Features without a promoted id come last
+        order.sort_by_cached_key(|&idx| promoted_id(&features[idx].properties, options).map_or((1, 0), |id| (0, id)));
+    }
+    
+    // Group features by output layer (a single group unless `layer_by_field` is set)
+    let mut groups: std::collections::BTreeMap<String, Vec<usize>> = std::collections::BTreeMap::new();
+    for idx in order {
+        let mut name = feature_layer_name(&features[idx].properties, options, layer_name)?;
+        if features[idx].label {
+            name.push_str("_labels");
+        }
+        groups.entry(name).or_default().push(idx);
     }
     
+    // Build tile
+    let mut tile = vector_tile::Tile::default();
+    let (mut max_values, mut coerced, mut thinned, mut invalid) = (0, false, 0, 0);
+    for (name, mut members) in groups {
+        if let Some(cell) = point_cell {
+            let before = members.len();
+            thin_points(features, &mut members, cell);
+            thinned += before - members.len();
+        }
+        let name = match zoom {
+            Some(zoom) => zoom_layer_name(&name, zoom, options),
+            None => name,
+        };
+        let (layer, values, layer_coerced, layer_invalid) = encode_layer(features, &members, name, with_tags, options)?;
+        tile.layers.push(layer);
+        max_values = max_values.max(values);
+        coerced |= layer_coerced;
+        invalid += layer_invalid;
+    }
+    
+    // Encode to binary
+    let mut buf = Vec::new();
+    tile.encode(&mut buf)
+        .map_err(|e| ConverterError::Encode(format!("Encode error: {}", e)))?;
+    
+    Ok(EncodedTile {
+        data: buf,
+        values: max_values,
+        coerced,
+        thinned,
+        invalid,
+    })
+}
+
+/// Keep only the first point of `members` in each `cell` x `cell` square of the tile
+fn thin_points(features: &[TileFeature], members: &mut Vec<usize>, cell: i32) {
+    let mut occupied = std::collections::HashSet::new();
+    members.retain(|&idx| match features[idx].geometry {
+        TileGeometry::Point(x, y) => occupied.insert((x.div_euclid(cell), y.div_euclid(cell))),
+        _ => true,
+    });
+}
+
+/// Encode a valid MVT tile with a single layer and no features
+pub fn empty_tile(layer_name: &str, options: &TilerOptions) -> Vec<u8> {
+    let tile = vector_tile::Tile {
+        layers: vec![Layer {
+            version: options.mvt_version as u32,
+            name: layer_name.to_string(),
+            extent: Some(4096),
+            ..Default::default()
+        }],
+    };
+    tile.encode_to_vec()
+}
+
+/// Name of the layer a feature is written to
+/// 
+/// With `options.layer_by_field`, this is the value of that property (strings as-is,
+/// other values as JSON text), sanitized and validated like the layer name of the
+/// generate call. Features without it, or with `null`, go to `default`.
+pub fn feature_layer_name(
+    properties: &serde_json::Map<String, serde_json::Value>,
+    options: &TilerOptions,
+    default: &str,
+) -> Result<String, ConverterError> {
+    let value = options.layer_by_field.as_ref().and_then(|field| properties.get(field));
+    let name = match value {
+        None | Some(serde_json::Value::Null) => return Ok(default.to_string()),
+        Some(serde_json::Value::String(s)) => s.clone(),
+        Some(value) => value.to_string(),
+    };
+    crate::resolve_layer_name(&name, options)
+}
+
+/// Name of a layer in the tiles of zoom level `zoom`
+/// 
+/// This is `options.layer_name_template` with `{layer}` replaced by `name` and `{z}` by
+/// the zoom, or `name` itself without a template.
+pub fn zoom_layer_name(name: &str, zoom: u8, options: &TilerOptions) -> String {
+    match &options.layer_name_template {
+        Some(template) => template.replace("{layer}", name).replace("{z}", &zoom.to_string()),
+        None => name.to_string(),
+    }
+}
+
+/// Encode the features at `members` (in that order) as one layer, without tags unless `with_tags`
+/// 
+/// Also returns the number of unique values before `value_overflow_placeholder` is
+/// applied, whether it was, and the number of features dropped by `validate_geometry`.
+fn encode_layer(
+    features: &[TileFeature],
+    members: &[usize],
+    name: String,
+    with_tags: bool,
+    options: &TilerOptions,
+) -> Result<(Layer, usize, bool, usize), ConverterError> {
+    // With a placeholder, count value occurrences first to find rare strings on overflow
+    let mut occurrences: HashMap<ValueKey, usize> = HashMap::new();
+    if options.value_overflow_placeholder.is_some() && with_tags {
+        for &idx in members {
+            visit_properties(&features[idx].properties, options, &mut |_, value, _| {
+                *occurrences.entry(ValueKey::from_json(value)).or_default() += 1;
+            });
+        }
+    }
+    let placeholder = options
+        .value_overflow_placeholder
+        .as_ref()
+        .filter(|_| occurrences.len() > options.max_tile_values)
+        .map(|placeholder| serde_json::Value::String(placeholder.clone()));
+    
     // Build key and value dictionaries
     let mut keys: Vec<String> = Vec::new();
     let mut values: Vec<Value> = Vec::new();
     let mut key_index: HashMap<String, u32> = HashMap::new();
     let mut value_index: HashMap<ValueKey, u32> = HashMap::new();
     
-    // Encode features
+    // Encode features; with `merge_identical_properties`, features of one geometry type
+    // and identical tags are collected as parts of the first one
     let mut encoded_features = Vec::new();
+    let mut merged_parts: Vec<Vec<Vec<u32>>> = Vec::new();
+    let mut merge_target: HashMap<(GeomType, Vec<u32>), usize> = HashMap::new();
+    let mut invalid = 0;
     
-    for (idx, tile_feature) in features.iter().enumerate() {
+    for &idx in members {
+        let tile_feature = &features[idx];
+        
+        // Encode geometry (features failing validation are dropped before adding tags)
+        let (geom_type, geometry) = match (&tile_feature.geometry, options.polygons_as_lines) {
+            (TileGeometry::Polygon(rings), true) => (GeomType::Linestring, encode_rings_as_lines(rings.iter())),
+            (TileGeometry::MultiPolygon(polygons), true) => {
+                (GeomType::Linestring, encode_rings_as_lines(polygons.iter().flatten()))
+            }
+            (geometry, _) => encode_geometry(geometry)?,
+        };
+        if options.validate_geometry && validate_commands(geom_type, &geometry).is_err() {
+            invalid += 1;
+            continue;
+        }
+        
         let mut tags = Vec::new();
         
         // Convert properties to tags
-        for (key, value) in &tile_feature.properties {
+        let no_properties = serde_json::Map::new();
+        let properties = if with_tags { &tile_feature.properties } else { &no_properties };
+        visit_properties(properties, options, &mut |key, value, _| {
             // Get or add key index
             let key_idx = if let Some(&idx) = key_index.get(key) {
                 idx
             } else {
                 let idx = keys.len() as u32;
-                keys.push(key.clone());
-                key_index.insert(key.clone(), idx);
+                keys.push(key.to_string());
+                key_index.insert(key.to_string(), idx);
                 idx
             };
             
             // Get or add value index
             let value_key = ValueKey::from_json(value);
+            let (value, value_key) = match &placeholder {
+                Some(placeholder) if value.is_string() && occurrences.get(&value_key) == Some(&1) => {
+                    (placeholder, ValueKey::from_json(placeholder))
+                }
+                _ => (value, value_key),
+            };
             let value_idx = if let Some(&idx) = value_index.get(&value_key) {
                 idx
             } else {
@@ -55,60 +452,132 @@ pub fn encode_tile(features: &[TileFeature], layer_name: &str) -> Result<Vec<u8>
             
             tags.push(key_idx);
             tags.push(value_idx);
-        }
+        });
         
-        // Encode geometry
-        let (geom_type, geometry) = encode_geometry(&tile_feature.geometry)?;
+        if options.merge_identical_properties {
+            match merge_target.entry((geom_type, tags.clone())) {
+                std::collections::hash_map::Entry::Occupied(target) => {
+                    merged_parts[*target.get()].push(geometry);
+                    continue;
+                }
+                std::collections::hash_map::Entry::Vacant(slot) => {
+                    slot.insert(encoded_features.len());
+                    merged_parts.push(Vec::new());
+                }
+            }
+        }
         
         encoded_features.push(Feature {
-            id: Some(idx as u64),
+            id: match (options.mvt_version, &options.id_property) {
+                (MvtVersion::V1, _) => None,
+                (MvtVersion::V2, Some(_)) => promoted_id(&tile_feature.properties, options),
+                (MvtVersion::V2, None) => Some(encoded_features.len() as u64),
+            },
             tags,
             r#type: Some(geom_type as i32),
             geometry,
         });
     }
     
-    // Build layer
+    for (feature, parts) in encoded_features.iter_mut().zip(merged_parts) {
+        if !parts.is_empty() {
+            let first = std::mem::take(&mut feature.geometry);
+            feature.geometry = merge_geometries(feature.r#type(), std::iter::once(first).chain(parts));
+        }
+    }
+    
+    let unique_values = if placeholder.is_some() { occurrences.len() } else { values.len() };
     let layer = Layer {
-        version: 2,
-        name: layer_name.to_string(),
+        version: options.mvt_version as u32,
+        name,
         features: encoded_features,
         keys,
         values,
         extent: Some(4096),
     };
+    Ok((layer, unique_values, placeholder.is_some(), invalid))
+}
+
+/// Compare property values for attribute sorting (numbers, then strings, then others; missing last)
+fn compare_property(a: Option<&serde_json::Value>, b: Option<&serde_json::Value>) -> Ordering {
+    use serde_json::Value as Json;
     
-    // Build tile
-    let tile = vector_tile::Tile {
-        layers: vec![layer],
+    fn rank(value: &Json) -> u8 {
+        match value {
+            Json::Number(_) => 0,
+            Json::String(_) => 1,
+            Json::Bool(_) => 2,
+            _ => 3,
+        }
+    }
+    
+    match (a, b) {
+        (None, None) => Ordering::Equal,
+        (None, Some(_)) => Ordering::Greater,
+        (Some(_), None) => Ordering::Less,
+        (Some(Json::Number(x)), Some(Json::Number(y))) => {
+            let x = x.as_f64().unwrap_or(0.0);
+            let y = y.as_f64().unwrap_or(0.0);
+            x.total_cmp(&y)
+        }
+        (Some(Json::String(x)), Some(Json::String(y))) => x.cmp(y),
+        (Some(Json::Bool(x)), Some(Json::Bool(y))) => x.cmp(y),
+        (Some(x), Some(y)) => rank(x).cmp(&rank(y)).then_with(|| x.to_string().cmp(&y.to_string())),
+    }
+}
+
+/// Hilbert index of the geometry centroid on the tile grid (vertex average, clamped to the extent)
+fn centroid_hilbert(geometry: &TileGeometry) -> u64 {
+    let (mut sum_x, mut sum_y, mut count) = (0i64, 0i64, 0i64);
+    let mut add = |points: &[(i32, i32)]| {
+        for &(x, y) in points {
+            sum_x += x as i64;
+            sum_y += y as i64;
+            count += 1;
+        }
     };
     
-    // Encode to binary
-    let mut buf = Vec::new();
-    tile.encode(&mut buf)
-        .map_err(|e| format!("Encode error: {}", e))?;
+    match geometry {
+        TileGeometry::Point(x, y) => add(&[(*x, *y)]),
+        TileGeometry::LineString(coords) => add(coords),
+        TileGeometry::Polygon(rings) => {
+            if let Some(exterior) = rings.first() {
+                add(exterior);
+            }
+        }
+        TileGeometry::MultiPolygon(polygons) => {
+            for exterior in polygons.iter().filter_map(|rings| rings.first()) {
+                add(exterior);
+            }
+        }
+    }
     
-    Ok(buf)
+    if count == 0 {
+        return 0;
+    }
+    let max = crate::tiler::EXTENT as i64 - 1;
+    let x = (sum_x / count).clamp(0, max) as u32;
+    let y = (sum_y / count).clamp(0, max) as u32;
+    crate::pmtiles_encoder::xy_to_hilbert(x, y, 12)
 }
 
 /// Encode geometry in MVT format
-fn encode_geometry(geometry: &TileGeometry) -> Result<(GeomType, Vec<u32>), String> {
+fn encode_geometry(geometry: &TileGeometry) -> Result<(GeomType, Vec<u32>), ConverterError> {
     match geometry {
         TileGeometry::Point(x, y) => {
-            let mut commands = Vec::new();
-            
-            // MoveTo command (command=1, count=1)
-            commands.push(command_integer(1, 1));
-            
-            // Coordinates (zig-zag encoding)
-            commands.push(zigzag_encode(*x));
-            commands.push(zigzag_encode(*y));
+            let commands = vec![
+                // MoveTo command (command=1, count=1)
+                command_integer(1, 1),
+                // Coordinates (zig-zag encoding)
+                zigzag_encode(*x),
+                zigzag_encode(*y),
+            ];
             
             Ok((GeomType::Point, commands))
         }
         TileGeometry::LineString(coords) => {
             if coords.is_empty() {
-                return Err("LineString is empty".to_string());
+                return Err(ConverterError::Encode("LineString is empty".to_string()));
             }
             
             let mut commands = Vec::new();
@@ -134,41 +603,26 @@ fn encode_geometry(geometry: &TileGeometry) -> Result<(GeomType, Vec<u32>), Stri
         }
         TileGeometry::Polygon(rings) => {
             if rings.is_empty() {
-                return Err("Polygon is empty".to_string());
+                return Err(ConverterError::Encode("Polygon is empty".to_string()));
             }
             
             let mut commands = Vec::new();
+            let mut cursor = (0, 0);
+            encode_polygon_rings(rings, &mut cursor, &mut commands);
             
-            for (_ring_idx, ring) in rings.iter().enumerate() {
-                if ring.len() < 4 {
-                    // Polygon requires at least 4 points (first and last are the same)
-                    continue;
-                }
-                
-                // In GeoJSON, last point = first point, so exclude the last point
-                let point_count = ring.len() - 1;
-                
-                // MoveTo first point
-                commands.push(command_integer(1, 1));
-                commands.push(zigzag_encode(ring[0].0));
-                commands.push(zigzag_encode(ring[0].1));
-                
-                // LineTo remaining points (excluding last point)
-                if point_count > 1 {
-                    commands.push(command_integer(2, (point_count - 1) as u32));
-                    
-                    for i in 1..point_count {
-                        let dx = ring[i].0 - ring[i - 1].0;
-                        let dy = ring[i].1 - ring[i - 1].1;
-                        commands.push(zigzag_encode(dx));
-                        commands.push(zigzag_encode(dy));
-                    }
-                }
-                
-                // ClosePath command: command_id=7, count=1
-                // command_integer(7, 1) = (7 & 0x7) | (1 << 3) = 7 | 8 = 15
-                let closepath_cmd = command_integer(7, 1);
-                commands.push(closepath_cmd);
+            Ok((GeomType::Polygon, commands))
+        }
+        TileGeometry::MultiPolygon(polygons) => {
+            if polygons.is_empty() {
+                return Err(ConverterError::Encode("MultiPolygon is empty".to_string()));
+            }
+            
+            // Each polygon is emitted as its exterior ring followed by its holes,
+            // so decoders can regroup rings by the sign of their area
+            let mut commands = Vec::new();
+            let mut cursor = (0, 0);
+            for rings in polygons {
+                encode_polygon_rings(rings, &mut cursor, &mut commands);
             }
             
             Ok((GeomType::Polygon, commands))
@@ -176,6 +630,191 @@ fn encode_geometry(geometry: &TileGeometry) -> Result<(GeomType, Vec<u32>), Stri
     }
 }
 
+/// Join the encoded geometries of features into one multi geometry of the same type
+/// 
+/// Points become a single MoveTo with one parameter pair per point (MVT spec 4.3.5.1).
+/// Lines and polygons keep their commands; only the first MoveTo of each geometry is
+/// rebased on the cursor left by the previous one.
+fn merge_geometries(geom_type: GeomType, geometries: impl Iterator<Item = Vec<u32>>) -> Vec<u32> {
+    let mut commands = Vec::new();
+    let mut cursor = (0, 0);
+    if geom_type == GeomType::Point {
+        let geometries: Vec<Vec<u32>> = geometries.collect();
+        let points: Vec<(i32, i32)> = geometries
+            .iter()
+            .flat_map(|geometry| decode_geometry(geometry).unwrap_or_default())
+            .flatten()
+            .collect();
+        commands.push(command_integer(1, points.len() as u32));
+        for point in points {
+            commands.push(zigzag_encode(point.0 - cursor.0));
+            commands.push(zigzag_encode(point.1 - cursor.1));
+            cursor = point;
+        }
+        return commands;
+    }
+    
+    for geometry in geometries {
+        let parts = decode_geometry(&geometry).unwrap_or_default();
+        let first = parts.first().and_then(|part| part.first());
+        let last = parts.last().and_then(|part| part.last());
+        let (Some(&first), Some(&last)) = (first, last) else {
+            continue;
+        };
+        // The geometry starts with MoveTo(1) from (0, 0)
+        commands.push(geometry[0]);
+        commands.push(zigzag_encode(first.0 - cursor.0));
+        commands.push(zigzag_encode(first.1 - cursor.1));
+        commands.extend_from_slice(&geometry[3..]);
+        cursor = last;
+    }
+    commands
+}
+
+/// Encode polygon rings as a (multi) LineString: MoveTo + LineTo per ring, no ClosePath
+/// 
+/// Each line ends at its first point again (the closing point is kept or added), and
+/// ring orientation is left as is. Rings with fewer than two distinct points are skipped.
+fn encode_rings_as_lines<'a>(rings: impl Iterator<Item = &'a Vec<(i32, i32)>>) -> Vec<u32> {
+    let mut commands = Vec::new();
+    let mut cursor = (0, 0);
+    for ring in rings {
+        let Some(&first) = ring.first() else {
+            continue;
+        };
+        let mut points = ring.clone();
+        if points.last() != Some(&first) {
+            points.push(first);
+        }
+        if points.len() < 3 {
+            continue;
+        }
+        
+        commands.push(command_integer(1, 1));
+        commands.push(zigzag_encode(first.0 - cursor.0));
+        commands.push(zigzag_encode(first.1 - cursor.1));
+        commands.push(command_integer(2, (points.len() - 1) as u32));
+        for pair in points.windows(2) {
+            commands.push(zigzag_encode(pair[1].0 - pair[0].0));
+            commands.push(zigzag_encode(pair[1].1 - pair[0].1));
+        }
+        cursor = first;
+    }
+    commands
+}
+
+/// Encode one polygon (exterior ring + holes) as MoveTo/LineTo/ClosePath sequences
+/// 
+/// MVT spec 4.3.4.4: an exterior ring must have positive area and an interior ring
+/// negative area (surveyor's formula in tile coordinates, Y axis pointing down).
+/// Rings are reversed where needed so consumers can group MultiPolygon rings correctly.
+/// Closed rings (last point = first point) and unclosed rings are both accepted.
+/// If the exterior ring is degenerate, the whole polygon (including its holes) is skipped.
+fn encode_polygon_rings(rings: &[Vec<(i32, i32)>], cursor: &mut (i32, i32), commands: &mut Vec<u32>) {
+    for (ring_idx, ring) in rings.iter().enumerate() {
+        // ClosePath returns to the first point, so a closing duplicate is not written
+        let closed = ring.len() > 1 && ring.first() == ring.last();
+        let mut points = if closed {
+            ring[..ring.len() - 1].to_vec()
+        } else {
+            ring.clone()
+        };
+        
+        if points.len() < 3 {
+            // Polygon requires at least 3 distinct points
+            if ring_idx == 0 {
+                return;
+            }
+            continue;
+        }
+        
+        let area = grid_ring_area(&points);
+        if area == 0.0 {
+            // Zero-area rings cannot be grouped by sign
+            if ring_idx == 0 {
+                return;
+            }
+            continue;
+        }
+        
+        let is_exterior = ring_idx == 0;
+        if (area > 0.0) != is_exterior {
+            points.reverse();
+        }
+        
+        // MoveTo first point (relative to the cursor left by the previous ring)
+        commands.push(command_integer(1, 1));
+        commands.push(zigzag_encode(points[0].0 - cursor.0));
+        commands.push(zigzag_encode(points[0].1 - cursor.1));
+        
+        // LineTo remaining points
+        commands.push(command_integer(2, (points.len() - 1) as u32));
+        for i in 1..points.len() {
+            let dx = points[i].0 - points[i - 1].0;
+            let dy = points[i].1 - points[i - 1].1;
+            commands.push(zigzag_encode(dx));
+            commands.push(zigzag_encode(dy));
+        }
+        *cursor = points[points.len() - 1];
+        
+        // ClosePath command: command_id=7, count=1
+        // command_integer(7, 1) = (7 & 0x7) | (1 << 3) = 7 | 8 = 15
+        commands.push(command_integer(7, 1));
+    }
+}
+
+/// Check that a feature's command integers form a valid, non-degenerate geometry
+/// 
+/// MVT spec 4.3: points are one MoveTo; lines are MoveTo(1) + LineTo(n >= 1) sequences;
+/// polygon rings are MoveTo(1) + LineTo(n >= 2) + ClosePath(1). Every command must have
+/// its parameters, and there must be at least one part.
+pub fn validate_commands(geom_type: GeomType, commands: &[u32]) -> Result<(), ConverterError> {
+    let invalid = |message: String| ConverterError::Encode(format!("Invalid geometry commands: {}", message));
+    // Read the command at `i`, check its id and skip its parameters
+    let next = |i: &mut usize, expected: u32| -> Result<u32, ConverterError> {
+        let command = *commands.get(*i).ok_or_else(|| invalid("Geometry ends early".to_string()))?;
+        let (id, count) = (command & 0x7, command >> 3);
+        if id != expected {
+            return Err(invalid(format!("Expected command {} at {}, found {}", expected, i, id)));
+        }
+        let params = if id == 7 { 0 } else { 2 * count as usize };
+        if *i + 1 + params > commands.len() {
+            return Err(invalid(format!("Command {} at {} is missing parameters", id, i)));
+        }
+        *i += 1 + params;
+        Ok(count)
+    };
+    
+    let mut i = 0;
+    loop {
+        match geom_type {
+            GeomType::Point => {
+                if next(&mut i, 1)? == 0 {
+                    return Err(invalid("MoveTo without points".to_string()));
+                }
+            }
+            GeomType::Linestring => {
+                if next(&mut i, 1)? != 1 || next(&mut i, 2)? == 0 {
+                    return Err(invalid("LineString needs MoveTo(1) and at least one LineTo".to_string()));
+                }
+            }
+            GeomType::Polygon => {
+                if next(&mut i, 1)? != 1 || next(&mut i, 2)? < 2 || next(&mut i, 7)? != 1 {
+                    return Err(invalid("Polygon ring needs MoveTo(1), LineTo(>= 2) and ClosePath".to_string()));
+                }
+            }
+            GeomType::Unknown => return Err(invalid("Unknown geometry type".to_string())),
+        }
+        if i == commands.len() {
+            break;
+        }
+        if geom_type == GeomType::Point {
+            return Err(invalid("Point geometry has more than one MoveTo".to_string()));
+        }
+    }
+    Ok(())
+}
+
 /// Encode command and count
 fn command_integer(id: u32, count: u32) -> u32 {
     (id & 0x7) | (count << 3)
@@ -186,6 +825,206 @@ fn zigzag_encode(n: i32) -> u32 {
     ((n << 1) ^ (n >> 31)) as u32
 }
 
+/// Zig-zag decoding
+fn zigzag_decode(n: u32) -> i32 {
+    ((n >> 1) as i32) ^ -((n & 1) as i32)
+}
+
+/// A feature decoded from an MVT tile, with absolute tile-space coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct DecodedFeature {
+    pub layer: String,
+    pub id: Option<u64>,
+    pub geom_type: GeomType,
+    /// One entry per point, line or polygon ring (rings without the closing point)
+    pub geometry: Vec<Vec<(i32, i32)>>,
+    pub properties: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Decode an (uncompressed) MVT tile, resolving tags and geometry commands
+///
+/// Features are returned layer by layer in tile order. Intended for tests and
+/// debugging, so malformed input is reported rather than repaired.
+pub fn decode_tile(bytes: &[u8]) -> Result<Vec<DecodedFeature>, String> {
+    let tile = vector_tile::Tile::decode(bytes).map_err(|e| format!("Invalid MVT tile: {}", e))?;
+    
+    let mut decoded = Vec::new();
+    for layer in &tile.layers {
+        for feature in &layer.features {
+            if feature.tags.len() % 2 != 0 {
+                return Err(format!("Feature in layer {} has an odd number of tags", layer.name));
+            }
+            let mut properties = serde_json::Map::new();
+            for pair in feature.tags.chunks(2) {
+                let key = layer.keys.get(pair[0] as usize)
+                    .ok_or_else(|| format!("Key index {} out of range in layer {}", pair[0], layer.name))?;
+                let value = layer.values.get(pair[1] as usize)
+                    .ok_or_else(|| format!("Value index {} out of range in layer {}", pair[1], layer.name))?;
+                properties.insert(key.clone(), mvt_value_to_json(value));
+            }
+            
+            decoded.push(DecodedFeature {
+                layer: layer.name.clone(),
+                id: feature.id,
+                geom_type: feature.r#type(),
+                geometry: decode_geometry(&feature.geometry)?,
+                properties,
+            });
+        }
+    }
+    Ok(decoded)
+}
+
+/// Key and value dictionaries of one layer of an MVT tile
+#[derive(Debug, Clone, PartialEq)]
+pub struct LayerDictionary {
+    pub layer: String,
+    pub keys: Vec<String>,
+    /// Values in tile order, converted like the properties of `decode_tile`
+    pub values: Vec<serde_json::Value>,
+}
+
+/// Decode only the key and value dictionaries of each layer of an (uncompressed) MVT tile
+///
+/// For debugging attribute encoding (a missing key, a number written as a string)
+/// without decoding any feature, like `decode_tile`.
+pub fn decode_dictionaries(bytes: &[u8]) -> Result<Vec<LayerDictionary>, String> {
+    let tile = vector_tile::Tile::decode(bytes).map_err(|e| format!("Invalid MVT tile: {}", e))?;
+    Ok(tile
+        .layers
+        .into_iter()
+        .map(|layer| LayerDictionary {
+            values: layer.values.iter().map(mvt_value_to_json).collect(),
+            keys: layer.keys,
+            layer: layer.name,
+        })
+        .collect())
+}
+
+/// Convert an (uncompressed) MVT tile back to a lon/lat GeoJSON FeatureCollection
+///
+/// Tile-space coordinates with the given `extent` are projected back with the inverse
+/// WebMercator projection of `coord`. Tags become properties; the MVT id and the layer
+/// name are kept as the feature `id` and a `layer` member. Polygon rings are grouped
+/// into polygons by winding (positive area starts a polygon, as encoded). For tile
+/// inspectors and debugging, like `decode_tile`.
+pub fn mvt_to_geojson(tile_bytes: &[u8], coord: crate::TileCoord, extent: u32) -> Result<String, String> {
+    if extent == 0 {
+        return Err("Extent must be positive".to_string());
+    }
+    let position = |&(x, y): &(i32, i32)| {
+        let px = x as f64 / extent as f64 * 256.0;
+        let py = y as f64 / extent as f64 * 256.0;
+        let (mx, my) = crate::projection::pixel_in_tile_to_meters(px, py, coord.x, coord.y, coord.z);
+        let (lon, lat) = crate::projection::meters_to_lonlat(mx, my);
+        vec![lon, lat]
+    };
+    let line = |part: &Vec<(i32, i32)>| part.iter().map(position).collect::<Vec<_>>();
+    
+    let mut features = Vec::new();
+    for decoded in decode_tile(tile_bytes)? {
+        let parts = &decoded.geometry;
+        let value = match decoded.geom_type {
+            GeomType::Point => match &parts[..] {
+                [point] => geojson::Value::Point(position(&point[0])),
+                _ => geojson::Value::MultiPoint(parts.iter().map(|point| position(&point[0])).collect()),
+            },
+            GeomType::Linestring => match &parts[..] {
+                [part] => geojson::Value::LineString(line(part)),
+                _ => geojson::Value::MultiLineString(parts.iter().map(line).collect()),
+            },
+            GeomType::Polygon => {
+                let mut polygons: Vec<Vec<Vec<Vec<f64>>>> = Vec::new();
+                for ring in parts {
+                    let mut coordinates = line(ring);
+                    coordinates.push(coordinates[0].clone());
+                    match polygons.last_mut() {
+                        Some(polygon) if grid_ring_area(ring) < 0.0 => polygon.push(coordinates),
+                        _ => polygons.push(vec![coordinates]),
+                    }
+                }
+                if polygons.len() == 1 {
+                    geojson::Value::Polygon(polygons.remove(0))
+                } else {
+                    geojson::Value::MultiPolygon(polygons)
+                }
+            }
+            GeomType::Unknown => return Err(format!("Feature of unknown geometry type in layer {}", decoded.layer)),
+        };
+        
+        let mut layer = serde_json::Map::new();
+        layer.insert("layer".to_string(), decoded.layer.into());
+        features.push(geojson::Feature {
+            bbox: None,
+            geometry: Some(geojson::Geometry::new(value)),
+            id: decoded.id.map(|id| geojson::feature::Id::Number(id.into())),
+            properties: Some(decoded.properties),
+            foreign_members: Some(layer),
+        });
+    }
+    
+    let collection = geojson::FeatureCollection {
+        bbox: None,
+        features,
+        foreign_members: None,
+    };
+    Ok(collection.to_string())
+}
+
+/// Decode command integers into parts of absolute coordinates
+///
+/// Every MoveTo point starts a new part, so multipoints yield one part per point.
+fn decode_geometry(commands: &[u32]) -> Result<Vec<Vec<(i32, i32)>>, String> {
+    let mut parts: Vec<Vec<(i32, i32)>> = Vec::new();
+    let (mut x, mut y) = (0i32, 0i32);
+    let mut i = 0;
+    while i < commands.len() {
+        let (id, count) = (commands[i] & 0x7, commands[i] >> 3);
+        i += 1;
+        match id {
+            1 | 2 => {
+                if i + 2 * count as usize > commands.len() {
+                    return Err(format!("Command {} at {} is missing parameters", id, i - 1));
+                }
+                for _ in 0..count {
+                    x = x.wrapping_add(zigzag_decode(commands[i]));
+                    y = y.wrapping_add(zigzag_decode(commands[i + 1]));
+                    i += 2;
+                    match (id, parts.last_mut()) {
+                        (2, Some(part)) => part.push((x, y)),
+                        (2, None) => return Err("LineTo before MoveTo".to_string()),
+                        _ => parts.push(vec![(x, y)]),
+                    }
+                }
+            }
+            7 => {
+                if parts.is_empty() {
+                    return Err("ClosePath before MoveTo".to_string());
+                }
+            }
+            _ => return Err(format!("Unknown command {} at {}", id, i - 1)),
+        }
+    }
+    Ok(parts)
+}
+
+/// Convert MVT value to JSON value (empty values become null)
+fn mvt_value_to_json(value: &Value) -> serde_json::Value {
+    if let Some(s) = &value.string_value {
+        serde_json::Value::String(s.clone())
+    } else if let Some(b) = value.bool_value {
+        serde_json::Value::Bool(b)
+    } else if let Some(i) = value.int_value.or(value.sint_value) {
+        i.into()
+    } else if let Some(u) = value.uint_value {
+        u.into()
+    } else if let Some(f) = value.double_value.or(value.float_value.map(f64::from)) {
+        serde_json::Number::from_f64(f).map_or(serde_json::Value::Null, serde_json::Value::Number)
+    } else {
+        serde_json::Value::Null
+    }
+}
+
 /// Convert JSON value to MVT value
 fn json_to_mvt_value(value: &serde_json::Value) -> Value {
     match value {
@@ -212,7 +1051,12 @@ fn json_to_mvt_value(value: &serde_json::Value) -> Value {
             bool_value: Some(*b),
             ..Default::default()
         },
-        _ => Value::default(),
+        // Arrays and objects are written as their JSON text
+        serde_json::Value::Array(_) | serde_json::Value::Object(_) => Value {
+            string_value: Some(value.to_string()),
+            ..Default::default()
+        },
+        serde_json::Value::Null => Value::default(),
     }
 }
 
@@ -223,6 +1067,7 @@ enum ValueKey {
     Int(i64),
     Double(String), // f64 cannot be hashed, so convert to string
     Bool(bool),
+    Null,
 }
 
 impl ValueKey {
@@ -239,7 +1084,510 @@ impl ValueKey {
                 }
             }
             serde_json::Value::Bool(b) => ValueKey::Bool(*b),
-            _ => ValueKey::String(String::new()),
+            serde_json::Value::Array(_) | serde_json::Value::Object(_) => ValueKey::String(value.to_string()),
+            serde_json::Value::Null => ValueKey::Null,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        use std::io::Write;
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(data).unwrap();
+        encoder.finish().unwrap()
+    }
+    
+    #[test]
+    fn test_hilbert_feature_order() {
+        // Points on a coarse grid in pseudo-random order, classified by region
+        let mut seed = 12345u32;
+        let features: Vec<TileFeature> = (0..500)
+            .map(|_| {
+                seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+                let x = ((seed >> 8) % 64) as i32 * 64;
+                let y = ((seed >> 16) % 64) as i32 * 64;
+                let mut properties = serde_json::Map::new();
+                properties.insert("region".to_string(), serde_json::json!(format!("r{}{}", x / 1024, y / 1024)));
+                TileFeature {
+                    geometry: TileGeometry::Point(x, y),
+                    properties,
+                    label: false,
+                }
+            })
+            .collect();
+        
+        let unsorted = encode_tile(&features, "test").unwrap();
+        let options = TilerOptions {
+            feature_order: FeatureOrder::Hilbert,
+            ..Default::default()
+        };
+        let sorted = encode_tile_with_options(&features, "test", &options).unwrap();
+        assert_eq!(sorted, encode_tile_with_options(&features, "test", &options).unwrap());
+        
+        let tile = vector_tile::Tile::decode(sorted.as_slice()).unwrap();
+        let keys: Vec<u64> = tile.layers[0]
+            .features
+            .iter()
+            .map(|f| {
+                let point = decode_geometry(&f.geometry).unwrap()[0][0];
+                centroid_hilbert(&TileGeometry::Point(point.0, point.1))
+            })
+            .collect();
+        assert!(keys.windows(2).all(|w| w[0] <= w[1]));
+        let input_keys: Vec<u64> = features.iter().map(|f| centroid_hilbert(&f.geometry)).collect();
+        assert_ne!(keys, input_keys);
+        
+        assert!(gzip(&sorted).len() <= gzip(&unsorted).len());
+    }
+    
+    #[test]
+    fn test_attribute_feature_order() {
+        let features: Vec<TileFeature> = [Some(3), None, Some(1), Some(2)]
+            .iter()
+            .map(|rank| {
+                let mut properties = serde_json::Map::new();
+                if let Some(rank) = rank {
+                    properties.insert("rank".to_string(), serde_json::json!(rank));
+                }
+                TileFeature {
+                    geometry: TileGeometry::Point(0, 0),
+                    properties,
+                    label: false,
+                }
+            })
+            .collect();
+        
+        let options = TilerOptions {
+            feature_order: FeatureOrder::Attribute("rank".to_string()),
+            ..Default::default()
+        };
+        let tile = encode_tile_with_options(&features, "test", &options).unwrap();
+        let tile = vector_tile::Tile::decode(tile.as_slice()).unwrap();
+        let layer = &tile.layers[0];
+        let ranks: Vec<Option<i64>> = layer
+            .features
+            .iter()
+            .map(|f| f.tags.get(1).map(|&v| layer.values[v as usize].int_value.unwrap()))
+            .collect();
+        assert_eq!(ranks, vec![Some(1), Some(2), Some(3), None]);
+    }
+    
+    #[test]
+    fn test_order_by_id() {
+        let features: Vec<TileFeature> = [Some(30), None, Some(10), Some(20)]
+            .iter()
+            .map(|fid| {
+                let mut properties = serde_json::Map::new();
+                if let Some(fid) = fid {
+                    properties.insert("fid".to_string(), serde_json::json!(fid));
+                }
+                TileFeature {
+                    geometry: TileGeometry::Point(0, 0),
+                    properties,
+                    label: false,
+                }
+            })
+            .collect();
+        
+        let ids = |order_by_id| {
+            let options = TilerOptions {
+                id_property: Some("fid".to_string()),
+                order_by_id,
+                ..Default::default()
+            };
+            let tile = encode_tile_with_options(&features, "test", &options).unwrap();
+            let tile = vector_tile::Tile::decode(tile.as_slice()).unwrap();
+            tile.layers[0].features.iter().map(|f| f.id).collect::<Vec<_>>()
+        };
+        assert_eq!(ids(false), vec![Some(30), None, Some(10), Some(20)]);
+        assert_eq!(ids(true), vec![Some(10), Some(20), Some(30), None]);
+    }
+    
+    /// Decode the tags of the first feature into (key, value) pairs
+    fn decode_tags(tile: &[u8]) -> Vec<(String, Value)> {
+        let tile = vector_tile::Tile::decode(tile).unwrap();
+        let layer = &tile.layers[0];
+        layer.features[0]
+            .tags
+            .chunks(2)
+            .map(|tag| (layer.keys[tag[0] as usize].clone(), layer.values[tag[1] as usize].clone()))
+            .collect()
+    }
+    
+    #[test]
+    fn test_nested_property_values() {
+        let mut properties = serde_json::Map::new();
+        properties.insert("prop".to_string(), serde_json::json!({ "a": 1 }));
+        let features = vec![TileFeature {
+            geometry: TileGeometry::Point(0, 0),
+            properties,
+            label: false,
+        }];
+        
+        let tags = decode_tags(&encode_tile(&features, "test").unwrap());
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].0, "prop");
+        assert_eq!(tags[0].1.string_value.as_deref(), Some(r#"{"a":1}"#));
+        
+        let options = TilerOptions {
+            nested_values: NestedValues::Flatten,
+            ..Default::default()
+        };
+        let tags = decode_tags(&encode_tile_with_options(&features, "test", &options).unwrap());
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].0, "prop.a");
+        assert_eq!(tags[0].1.int_value, Some(1));
+    }
+    
+    #[test]
+    fn test_null_property_values() {
+        let mut properties = serde_json::Map::new();
+        properties.insert("name".to_string(), serde_json::json!("a"));
+        properties.insert("note".to_string(), serde_json::Value::Null);
+        let features = vec![TileFeature {
+            geometry: TileGeometry::Point(0, 0),
+            properties,
+            label: false,
+        }];
+        
+        let tags = decode_tags(&encode_tile(&features, "test").unwrap());
+        assert_eq!(tags.len(), 1);
+        assert_eq!(tags[0].0, "name");
+        
+        let options = TilerOptions {
+            null_values: NullValues::Keep,
+            ..Default::default()
+        };
+        let tags = decode_tags(&encode_tile_with_options(&features, "test", &options).unwrap());
+        assert_eq!(tags.len(), 2);
+        assert_eq!(tags[1], ("note".to_string(), Value::default()));
+    }
+    
+    #[test]
+    fn test_unclosed_polygon_ring() {
+        let ring = vec![(0, 0), (0, 10), (10, 10), (10, 0)];
+        let features = vec![TileFeature {
+            geometry: TileGeometry::Polygon(vec![ring.clone()]),
+            properties: serde_json::Map::new(),
+            label: false,
+        }];
+        
+        let tile = vector_tile::Tile::decode(encode_tile(&features, "test").unwrap().as_slice()).unwrap();
+        let geometry = &tile.layers[0].features[0].geometry;
+        // MoveTo(1), LineTo(3) with all four vertices, then ClosePath
+        assert_eq!(geometry.len(), 3 + 1 + 6 + 1);
+        assert_eq!(geometry[3], command_integer(2, 3));
+        assert_eq!(*geometry.last().unwrap(), command_integer(7, 1));
+        
+        // The closed form of the same ring encodes identically
+        let mut closed = ring;
+        closed.push((0, 0));
+        let closed_features = vec![TileFeature {
+            geometry: TileGeometry::Polygon(vec![closed]),
+            properties: serde_json::Map::new(),
+            label: false,
+        }];
+        let closed_tile = vector_tile::Tile::decode(encode_tile(&closed_features, "test").unwrap().as_slice()).unwrap();
+        assert_eq!(&closed_tile.layers[0].features[0].geometry, geometry);
+    }
+    
+    #[test]
+    fn test_mvt_to_geojson_roundtrip() {
+        let geojson = r#"{"type": "FeatureCollection", "features": [
+            {"type": "Feature", "geometry": {"type": "Polygon", "coordinates": [[[139.70, 35.60], [139.75, 35.60], [139.75, 35.65], [139.70, 35.65], [139.70, 35.60]]]}, "properties": {"name": "park"}},
+            {"type": "Feature", "geometry": {"type": "LineString", "coordinates": [[139.71, 35.61], [139.74, 35.64]]}, "properties": {"lanes": 2}},
+            {"type": "Feature", "geometry": {"type": "Point", "coordinates": [139.72, 35.62]}, "properties": {}}
+        ]}"#;
+        let tiles = crate::generate_tiles(geojson.as_bytes(), 10, 10, "places").unwrap();
+        assert_eq!(tiles.len(), 1);
+        let coord = crate::TileCoord::new(10, 909, 403);
+        assert_eq!(tiles[0].path, coord.to_path());
+        
+        let output = mvt_to_geojson(&tiles[0].data, coord, 4096).unwrap();
+        let collection: geojson::FeatureCollection = output.parse().unwrap();
+        let input: geojson::FeatureCollection = geojson.parse().unwrap();
+        assert_eq!(collection.features.len(), 3);
+        
+        // One MVT unit is about 0.00009° at zoom 10
+        let close = |a: &Vec<f64>, b: &Vec<f64>| (a[0] - b[0]).abs() < 1e-4 && (a[1] - b[1]).abs() < 1e-4;
+        for (output, input) in collection.features.iter().zip(&input.features) {
+            assert_eq!(output.properties, input.properties);
+            assert_eq!(output.foreign_members.as_ref().unwrap()["layer"], "places");
+            match (&output.geometry.as_ref().unwrap().value, &input.geometry.as_ref().unwrap().value) {
+                (geojson::Value::Polygon(a), geojson::Value::Polygon(b)) => {
+                    // The encoder rewinds the exterior, so compare vertex sets
+                    assert_eq!(a[0].len(), b[0].len());
+                    assert!(b[0].iter().all(|p| a[0].iter().any(|q| close(p, q))));
+                }
+                (geojson::Value::LineString(a), geojson::Value::LineString(b)) => {
+                    assert!(a.iter().zip(b).all(|(p, q)| close(p, q)));
+                }
+                (geojson::Value::Point(a), geojson::Value::Point(b)) => assert!(close(a, b)),
+                other => panic!("unexpected geometries {:?}", other),
+            }
         }
     }
+    
+    #[test]
+    fn test_decode_tile_roundtrip() {
+        // Already wound the way MVT expects, so the encoder keeps vertex order
+        let exterior = vec![(3000, 100), (3000, 3000), (100, 3000), (100, 100)];
+        let hole = vec![(500, 1000), (1000, 1000), (1000, 500), (500, 500)];
+        let mut properties = serde_json::Map::new();
+        properties.insert("name".to_string(), serde_json::json!("park"));
+        properties.insert("area".to_string(), serde_json::json!(12.5));
+        properties.insert("rank".to_string(), serde_json::json!(-3));
+        let features = vec![TileFeature {
+            geometry: TileGeometry::Polygon(vec![exterior.clone(), hole.clone()]),
+            properties: properties.clone(),
+            label: false,
+        }];
+        
+        let decoded = decode_tile(&encode_tile(&features, "parks").unwrap()).unwrap();
+        assert_eq!(decoded.len(), 1);
+        assert_eq!(decoded[0].layer, "parks");
+        assert_eq!(decoded[0].id, Some(0));
+        assert_eq!(decoded[0].geom_type, GeomType::Polygon);
+        assert_eq!(decoded[0].geometry, vec![exterior, hole]);
+        assert_eq!(decoded[0].properties, properties);
+        
+        assert!(decode_tile(b"not a tile").is_err());
+    }
+    
+    #[test]
+    fn test_decode_dictionaries() {
+        let mut properties = serde_json::Map::new();
+        properties.insert("name".to_string(), serde_json::json!("park"));
+        properties.insert("rank".to_string(), serde_json::json!(3));
+        let features = vec![TileFeature {
+            geometry: TileGeometry::Point(100, 200),
+            properties,
+            label: false,
+        }];
+        
+        let dictionaries = decode_dictionaries(&encode_tile(&features, "parks").unwrap()).unwrap();
+        assert_eq!(
+            dictionaries,
+            vec![LayerDictionary {
+                layer: "parks".to_string(),
+                keys: vec!["name".to_string(), "rank".to_string()],
+                values: vec![serde_json::json!("park"), serde_json::json!(3)],
+            }]
+        );
+        assert!(decode_dictionaries(b"not a tile").is_err());
+    }
+    
+    #[test]
+    fn test_merge_identical_properties() {
+        let feature = |geometry: TileGeometry, kind: &str| {
+            let mut properties = serde_json::Map::new();
+            properties.insert("kind".to_string(), serde_json::json!(kind));
+            TileFeature { geometry, properties, label: false }
+        };
+        let features = vec![
+            feature(TileGeometry::Point(10, 10), "shop"),
+            feature(TileGeometry::LineString(vec![(0, 0), (5, 5)]), "shop"),
+            feature(TileGeometry::Point(20, 30), "shop"),
+            feature(TileGeometry::Point(40, 40), "cafe"),
+            feature(TileGeometry::Point(50, 10), "shop"),
+            feature(TileGeometry::LineString(vec![(100, 100), (200, 100)]), "shop"),
+        ];
+        let options = TilerOptions {
+            merge_identical_properties: true,
+            ..Default::default()
+        };
+        
+        let decoded = decode_tile(&encode_tile_with_options(&features, "poi", &options).unwrap()).unwrap();
+        assert_eq!(decoded.len(), 3);
+        assert_eq!(decoded[0].geom_type, GeomType::Point);
+        assert_eq!(decoded[0].geometry, vec![vec![(10, 10)], vec![(20, 30)], vec![(50, 10)]]);
+        assert_eq!(decoded[0].properties["kind"], "shop");
+        // Lines merge separately from points, keeping absolute positions
+        assert_eq!(decoded[1].geom_type, GeomType::Linestring);
+        assert_eq!(decoded[1].geometry, vec![vec![(0, 0), (5, 5)], vec![(100, 100), (200, 100)]]);
+        assert_eq!(decoded[2].properties["kind"], "cafe");
+        
+        let tile = vector_tile::Tile::decode(encode_tile_with_options(&features, "poi", &options).unwrap().as_slice()).unwrap();
+        let points = &tile.layers[0].features[0];
+        assert_eq!(points.geometry[0], command_integer(1, 3));
+        assert!(validate_commands(GeomType::Point, &points.geometry).is_ok());
+        assert!(validate_commands(GeomType::Linestring, &tile.layers[0].features[1].geometry).is_ok());
+    }
+    
+    #[test]
+    fn test_polygons_as_lines() {
+        let ring = vec![(0, 0), (0, 10), (10, 10), (10, 0)];
+        let features = vec![TileFeature {
+            geometry: TileGeometry::Polygon(vec![ring.clone()]),
+            properties: serde_json::Map::new(),
+            label: false,
+        }];
+        let options = TilerOptions {
+            polygons_as_lines: true,
+            ..Default::default()
+        };
+        
+        let tile = encode_tile_with_options(&features, "contours", &options).unwrap();
+        let feature = &vector_tile::Tile::decode(tile.as_slice()).unwrap().layers[0].features[0];
+        assert_eq!(feature.r#type(), GeomType::Linestring);
+        assert!(!feature.geometry.contains(&command_integer(7, 1)));
+        assert_eq!(feature.geometry[3], command_integer(2, 4));
+        assert!(validate_commands(GeomType::Linestring, &feature.geometry).is_ok());
+        
+        // The line returns to its first point
+        let decoded = decode_tile(&tile).unwrap();
+        assert_eq!(decoded[0].geometry, vec![vec![(0, 0), (0, 10), (10, 10), (10, 0), (0, 0)]]);
+    }
+    
+    #[test]
+    fn test_id_property_promoted() {
+        let feature = |properties: serde_json::Value| TileFeature {
+            geometry: TileGeometry::Point(1, 1),
+            properties: properties.as_object().unwrap().clone(),
+            label: false,
+        };
+        let features = vec![
+            feature(serde_json::json!({"fid": 42, "name": "a"})),
+            feature(serde_json::json!({"fid": "x-1", "name": "b"})),
+        ];
+        let options = TilerOptions {
+            id_property: Some("fid".to_string()),
+            ..Default::default()
+        };
+        
+        let decoded = decode_tile(&encode_tile_with_options(&features, "test", &options).unwrap()).unwrap();
+        assert_eq!(decoded[0].id, Some(42));
+        assert_eq!(decoded[0].properties.get("fid"), None);
+        assert_eq!(decoded[0].properties["name"], "a");
+        // Values that cannot be ids stay tags, and the feature gets no id
+        assert_eq!(decoded[1].id, None);
+        assert_eq!(decoded[1].properties["fid"], "x-1");
+        
+        let keep = TilerOptions {
+            keep_id_property: true,
+            ..options
+        };
+        let decoded = decode_tile(&encode_tile_with_options(&features, "test", &keep).unwrap()).unwrap();
+        assert_eq!(decoded[0].id, Some(42));
+        assert_eq!(decoded[0].properties["fid"], 42);
+    }
+    
+    #[test]
+    fn test_validate_geometry_drops_degenerate_features() {
+        let features = vec![
+            TileFeature {
+                geometry: TileGeometry::LineString(vec![(5, 5)]),
+                properties: serde_json::Map::new(),
+                label: false,
+            },
+            TileFeature {
+                geometry: TileGeometry::Point(1, 2),
+                properties: serde_json::Map::new(),
+                label: false,
+            },
+        ];
+        
+        let (geom_type, commands) = encode_geometry(&features[0].geometry).unwrap();
+        assert!(validate_commands(geom_type, &commands).is_err());
+        let (geom_type, commands) = encode_geometry(&features[1].geometry).unwrap();
+        assert!(validate_commands(geom_type, &commands).is_ok());
+        
+        // Without validation the single-point line is written as a bare MoveTo
+        let tile = vector_tile::Tile::decode(encode_tile(&features, "test").unwrap().as_slice()).unwrap();
+        assert_eq!(tile.layers[0].features.len(), 2);
+        
+        let options = TilerOptions {
+            validate_geometry: true,
+            ..Default::default()
+        };
+        let tile = vector_tile::Tile::decode(encode_tile_with_options(&features, "test", &options).unwrap().as_slice())
+            .unwrap();
+        let layer = &tile.layers[0];
+        assert_eq!(layer.features.len(), 1);
+        assert_eq!(layer.features[0].r#type, Some(GeomType::Point as i32));
+        assert_eq!(layer.features[0].id, Some(0));
+        assert_eq!(encode_tile_with_stats(&features, "test", 0, &options).unwrap().invalid, 1);
+    }
+    
+    #[test]
+    fn test_mvt_version_1() {
+        let features = vec![TileFeature {
+            geometry: TileGeometry::Point(10, 20),
+            properties: serde_json::Map::new(),
+            label: false,
+        }];
+        
+        let tile = vector_tile::Tile::decode(encode_tile(&features, "test").unwrap().as_slice()).unwrap();
+        assert_eq!(tile.layers[0].version, 2);
+        assert_eq!(tile.layers[0].features[0].id, Some(0));
+        
+        let options = TilerOptions {
+            mvt_version: MvtVersion::V1,
+            ..Default::default()
+        };
+        let tile = encode_tile_with_options(&features, "test", &options).unwrap();
+        let tile = vector_tile::Tile::decode(tile.as_slice()).unwrap();
+        assert_eq!(tile.layers[0].version, 1);
+        assert_eq!(tile.layers[0].features[0].id, None);
+    }
+    
+    #[test]
+    fn test_rename_fields() {
+        let mut properties = serde_json::Map::new();
+        properties.insert("NAME_JA".to_string(), serde_json::json!("東京"));
+        properties.insert("name".to_string(), serde_json::json!("Tokyo"));
+        properties.insert("pop".to_string(), serde_json::json!(14));
+        let features = vec![TileFeature {
+            geometry: TileGeometry::Point(0, 0),
+            properties,
+            label: false,
+        }];
+        
+        let options = TilerOptions {
+            rename_fields: HashMap::from([("NAME_JA".to_string(), "name".to_string())]),
+            ..Default::default()
+        };
+        let tile = encode_tile_with_options(&features, "test", &options).unwrap();
+        let tile = vector_tile::Tile::decode(tile.as_slice()).unwrap();
+        assert_eq!(tile.layers[0].keys, vec!["name", "pop"]);
+        
+        let tags = decode_tags(&encode_tile_with_options(&features, "test", &options).unwrap());
+        assert_eq!(tags[0].1.string_value.as_deref(), Some("東京"));
+    }
+    
+    #[test]
+    fn test_multipolygon_rings_grouped_by_winding() {
+        // Polygon A: exterior wound the "wrong" way, with a hole wound the same way
+        let polygon_a = vec![
+            vec![(0, 0), (0, 100), (100, 100), (100, 0), (0, 0)],
+            vec![(20, 20), (20, 40), (40, 40), (40, 20), (20, 20)],
+        ];
+        // Polygon B: correctly wound exterior, no holes
+        let polygon_b = vec![
+            vec![(200, 200), (300, 200), (300, 300), (200, 300), (200, 200)],
+        ];
+        let geometry = TileGeometry::MultiPolygon(vec![polygon_a, polygon_b]);
+        
+        let (geom_type, commands) = encode_geometry(&geometry).unwrap();
+        assert_eq!(geom_type, GeomType::Polygon);
+        
+        // Regroup rings the way MVT consumers do: positive area starts a new polygon
+        let mut polygons: Vec<(Vec<(i32, i32)>, usize)> = Vec::new();
+        for ring in decode_geometry(&commands).unwrap() {
+            if grid_ring_area(&ring) > 0.0 {
+                polygons.push((ring, 0));
+            } else {
+                polygons.last_mut().expect("hole before exterior").1 += 1;
+            }
+        }
+        
+        assert_eq!(polygons.len(), 2);
+        assert_eq!(polygons[0].1, 1);
+        assert_eq!(polygons[1].1, 0);
+        assert_eq!(polygons[1].0[0], (200, 200));
+    }
 }
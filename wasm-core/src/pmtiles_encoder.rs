@@ -1,95 +1,270 @@
 // PMTiles encoder
 // Manual implementation of PMTiles v3 format for Wasm compatibility
 
-use crate::{TileCoord, TileMetadata};
+use crate::{ConverterError, LayerMetadata, TileCoord, TileMetadata, TilerOptions, MAX_ZOOM};
 use byteorder::{LittleEndian, WriteBytesExt};
 use flate2::write::GzEncoder;
 use flate2::Compression;
+use std::collections::HashSet;
 use std::io::{Cursor, Write};
 
+/// Compression applied to tile data inside a PMTiles archive
+/// 
+/// The discriminants are the PMTiles v3 header codes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TileCompression {
+    /// Raw MVT bytes (handy for feeding tiles straight to a decoder while debugging)
+    None = 1,
+    /// Gzip-compressed MVT (like tippecanoe)
+    #[default]
+    Gzip = 2,
+}
+
+/// Gzip level for the tiles of a zoom range inside PMTiles archives
+/// 
+/// Only the level varies by zoom: the header declares one tile compression, so the codec
+/// is `TilerOptions::tile_compression` for every zoom level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+pub struct ZoomCompressionLevel {
+    pub min_zoom: u8,
+    pub max_zoom: u8,
+    /// Gzip level from 0 (stored) to 9 (smallest)
+    pub level: u32,
+}
+
+/// TileJSON `type` of a tileset (also the MBTiles `type` metadata row)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TilesetType {
+    /// Data drawn on top of a basemap
+    #[default]
+    Overlay,
+    /// A basemap
+    Baselayer,
+}
+
+impl TilesetType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TilesetType::Overlay => "overlay",
+            TilesetType::Baselayer => "baselayer",
+        }
+    }
+    
+    /// Parse a TileJSON `type` value ("overlay" or "baselayer")
+    pub fn parse(value: &str) -> Result<Self, ConverterError> {
+        match value {
+            "overlay" => Ok(TilesetType::Overlay),
+            "baselayer" => Ok(TilesetType::Baselayer),
+            _ => Err(ConverterError::InvalidInput(format!(
+                "Invalid tileset type: {:?} (expected \"overlay\" or \"baselayer\")",
+                value
+            ))),
+        }
+    }
+}
+
+/// TileJSON `scheme`: the direction of the y axis in tile URLs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TileScheme {
+    /// y = 0 is the northernmost row (default, as in the generated tile paths)
+    #[default]
+    Xyz,
+    /// y = 0 is the southernmost row
+    Tms,
+}
+
+impl TileScheme {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            TileScheme::Xyz => "xyz",
+            TileScheme::Tms => "tms",
+        }
+    }
+}
+
 /// Encode tiles in PMTiles v3 format
 /// 
 /// PMTiles v3 spec: https://github.com/protomaps/PMTiles/blob/main/spec/v3/spec.md
 pub fn encode_pmtiles(
     tiles: Vec<(TileCoord, Vec<u8>)>,
     metadata: &TileMetadata,
-) -> Result<Vec<u8>, String> {
-    if tiles.is_empty() {
-        return Err("Tiles are empty".to_string());
-    }
-    
-    // Collect and sort tile entries
-    let mut tile_entries: Vec<TileEntry> = tiles
-        .into_iter()
-        .map(|(coord, data)| {
-            let tile_id = coord_to_tile_id(coord.z, coord.x, coord.y);
-            TileEntry {
-                tile_id,
-                offset: 0, // Will be calculated later
-                length: data.len() as u32,
-                data,
-            }
-        })
-        .collect();
-    
-    // Sort by tile_id (required by PMTiles spec)
-    tile_entries.sort_by_key(|e| e.tile_id);
-    
-    // Calculate offsets BEFORE encoding directory
-    // We need to estimate directory size first, then calculate exact offsets
-    let header_size = 127;
+) -> Result<Vec<u8>, ConverterError> {
+    encode_pmtiles_with_options(tiles, metadata, &TilerOptions::default())
+}
+
+/// Encode tiles in PMTiles v3 format with options
+/// 
+/// `tiles` must hold raw MVT bytes; they are compressed according to
+/// `options.tile_compression`, which is also recorded in the header.
+pub fn encode_pmtiles_with_options(
+    tiles: Vec<(TileCoord, Vec<u8>)>,
+    metadata: &TileMetadata,
+    options: &TilerOptions,
+) -> Result<Vec<u8>, ConverterError> {
+    let mut buffer = Vec::new();
+    write_pmtiles(tiles, metadata, options, &mut buffer)?;
+    Ok(buffer)
+}
+
+/// Gzip one tile's MVT bytes (default level, like tippecanoe)
+pub(crate) fn gzip_tile(data: &[u8]) -> Result<Vec<u8>, ConverterError> {
+    gzip_tile_with_level(data, Compression::default())
+}
+
+fn gzip_tile_with_level(data: &[u8], level: Compression) -> Result<Vec<u8>, ConverterError> {
+    let mut encoder = GzEncoder::new(Vec::new(), level);
+    encoder
+        .write_all(data)
+        .map_err(|e| ConverterError::Encode(format!("Failed to compress tile data: {}", e)))?;
+    encoder
+        .finish()
+        .map_err(|e| ConverterError::Encode(format!("Failed to finish tile compression: {}", e)))
+}
+
+/// Gzip level for tiles of zoom `z`: the first matching `tile_compression_levels` range,
+/// else the default level
+fn zoom_compression_level(z: u8, options: &TilerOptions) -> Compression {
+    options
+        .tile_compression_levels
+        .iter()
+        .find(|range| (range.min_zoom..=range.max_zoom).contains(&z))
+        .map_or(Compression::default(), |range| Compression::new(range.level))
+}
+
+/// Write tiles in PMTiles v3 format to `sink`, section by section
+/// 
+/// Each raw tile is compressed as soon as it is taken from `tiles`, and the archive is
+/// written as header, root directory, JSON metadata and tile data in separate writes,
+/// so it is never assembled in one buffer. The compressed tiles are still held until
+/// the end, because the header and directory precede them and depend on their sizes.
+/// 
+/// Tile ids are unique per archive: a coordinate given twice is rejected, so the tile
+/// order, and with it the directory bytes, depend only on the input tiles.
+pub fn write_pmtiles<W: Write>(
+    tiles: impl IntoIterator<Item = (TileCoord, Vec<u8>)>,
+    metadata: &TileMetadata,
+    options: &TilerOptions,
+    sink: &mut W,
+) -> Result<(), ConverterError> {
+    // Compress tile data (like tippecanoe for gzip)
+    if let Some(invalid) = options.tile_compression_levels.iter().find(|range| range.level > 9) {
+        return Err(ConverterError::InvalidInput(format!(
+            "Invalid gzip level {} for zooms {}-{} (expected 0-9)",
+            invalid.level, invalid.min_zoom, invalid.max_zoom
+        )));
+    }
+    let mut tile_entries = Vec::new();
+    let mut tile_ids = HashSet::new();
+    for (coord, data) in tiles {
+        let tile_id = coord_to_tile_id(coord.z, coord.x, coord.y)?;
+        if !tile_ids.insert(tile_id) {
+            return Err(ConverterError::InvalidInput(format!(
+                "Duplicate tile {}/{}/{}",
+                coord.z, coord.x, coord.y
+            )));
+        }
+        let data = match options.tile_compression {
+            TileCompression::None => data,
+            TileCompression::Gzip => gzip_tile_with_level(&data, zoom_compression_level(coord.z, options))?,
+        };
+        tile_entries.push(TileEntry {
+            tile_id,
+            offset: 0, // Calculated after sorting
+            length: data.len() as u32,
+            data,
+        });
+    }
+    if tile_entries.is_empty() {
+        return Err(ConverterError::EmptyInput("Tiles are empty".to_string()));
+    }
+    let (empty_tile, fill_ids) = if options.fill_empty_tiles {
+        fill_empty_tiles(&tile_entries, metadata, options)?
+    } else {
+        (Vec::new(), Vec::new())
+    };
     
-    // Estimate directory size (will be recalculated after encoding)
-    // For now, calculate tile data offsets assuming directory is at header_size
+    // Lay out the tile data section, sorted by tile_id for clustered archives and in
+    // input order otherwise. Empty fill tiles share one copy of their data, stored where
+    // the first of them falls (clustered) or after all other tiles.
+    if options.clustered {
+        tile_entries.sort_by_key(|e| e.tile_id);
+    }
+    let empty_position = fill_ids.first().map(|&first| {
+        if options.clustered {
+            tile_entries.partition_point(|e| e.tile_id < first)
+        } else {
+            tile_entries.len()
+        }
+    });
     let mut tile_data_length = 0usize;
-    let mut current_relative_offset = 0usize; // Offset relative to tile data section start
-    
-    // Compress tile data and update offsets
-    let mut compressed_tile_entries = Vec::new();
-    for entry in tile_entries {
-        // Compress tile data with gzip (like tippecanoe)
-        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-        encoder
-            .write_all(&entry.data)
-            .map_err(|e| format!("Failed to compress tile data: {}", e))?;
-        let compressed_data = encoder
-            .finish()
-            .map_err(|e| format!("Failed to finish tile compression: {}", e))?;
-        
-        let compressed_entry = TileEntry {
-            tile_id: entry.tile_id,
-            offset: current_relative_offset,
-            length: compressed_data.len() as u32,
-            data: compressed_data,
-        };
-        
-        current_relative_offset += compressed_entry.length as usize;
-        tile_data_length += compressed_entry.length as usize;
-        compressed_tile_entries.push(compressed_entry);
+    let mut empty_offset = 0;
+    for i in 0..=tile_entries.len() {
+        if empty_position == Some(i) {
+            empty_offset = tile_data_length;
+            tile_data_length += empty_tile.len();
+        }
+        if let Some(entry) = tile_entries.get_mut(i) {
+            entry.offset = tile_data_length;
+            tile_data_length += entry.length as usize;
+        }
     }
-    let tile_entries = compressed_tile_entries;
     
-    // Encode directory (now with correct offsets)
-    let directory_data = encode_directory(&tile_entries)?;
+    // Encode directory (now with correct offsets); its entries are always sorted by tile_id,
+    // and runs of consecutive tile ids sharing their data become one entry. Ids are unique
+    // (checked above, and fill ids are only the missing ones).
+    let mut sorted: Vec<(u64, usize, u32)> = tile_entries.iter().map(|e| (e.tile_id, e.offset, e.length)).collect();
+    sorted.extend(fill_ids.iter().map(|&tile_id| (tile_id, empty_offset, empty_tile.len() as u32)));
+    sorted.sort_unstable_by_key(|&(tile_id, ..)| tile_id);
+    let mut directory_entries: Vec<DirectoryEntry> = Vec::new();
+    for &(tile_id, offset, length) in &sorted {
+        match directory_entries.last_mut() {
+            Some(last) if last.offset == offset && last.tile_id + last.run_length as u64 == tile_id => {
+                last.run_length += 1;
+            }
+            _ => directory_entries.push(DirectoryEntry { tile_id, run_length: 1, offset, length }),
+        }
+    }
+    let directory_data = encode_directory(&directory_entries)?;
     let directory_length = directory_data.len();
+    let counts = TileCounts {
+        addressed: sorted.len(),
+        entries: directory_entries.len(),
+        contents: tile_entries.len() + usize::from(!fill_ids.is_empty()),
+    };
     
-    // Generate JSON metadata
-    let json_metadata = generate_json_metadata(metadata)?;
+    // Generate JSON metadata (optionally with a hash of the tile data for cache busting)
+    let content_hash = if options.embed_content_hash {
+        let mut hasher = crate::checksum::Sha256::new();
+        for entry in &tile_entries {
+            hasher.update(&entry.tile_id.to_le_bytes());
+            hasher.update(&entry.data);
+        }
+        if !fill_ids.is_empty() {
+            fill_ids.iter().for_each(|tile_id| hasher.update(&tile_id.to_le_bytes()));
+            hasher.update(&empty_tile);
+        }
+        Some(crate::checksum::to_hex(&hasher.finalize()[..8]))
+    } else {
+        None
+    };
+    let json_metadata = generate_json_metadata(metadata, content_hash.as_deref())?;
     
-    // Recalculate offsets based on actual directory size
+    // Section offsets: header, root directory, JSON metadata, tile data
+    let header_size = 127;
     let root_directory_offset = header_size;
     let json_metadata_offset = root_directory_offset + directory_length;
     let json_metadata_length = json_metadata.len();
     let tile_data_offset = json_metadata_offset + json_metadata_length;
     
-    // Create buffer and write everything
-    let mut buffer = Cursor::new(Vec::new());
-    
-    // Write header with correct offsets and lengths
+    let mut header = Cursor::new(Vec::with_capacity(header_size));
     write_header(
-        &mut buffer,
+        &mut header,
         metadata,
-        tile_entries.len(),
+        options.tile_compression,
+        options.clustered,
+        &counts,
         root_directory_offset,
         directory_length,
         json_metadata_offset,
@@ -98,36 +273,246 @@ pub fn encode_pmtiles(
         tile_data_length,
     )?;
     
-    // Write directory
+    let write = |sink: &mut W, data: &[u8], what: &str| {
+        sink.write_all(data)
+            .map_err(|e| ConverterError::Io(format!("Failed to write {}: {}", what, e)))
+    };
+    write(sink, header.get_ref(), "header")?;
+    write(sink, &directory_data, "directory")?;
+    write(sink, &json_metadata, "JSON metadata")?;
+    for i in 0..=tile_entries.len() {
+        if empty_position == Some(i) {
+            write(sink, &empty_tile, "tile data")?;
+        }
+        if let Some(entry) = tile_entries.get(i) {
+            write(sink, &entry.data, "tile data")?;
+        }
+    }
+    sink.flush().map_err(|e| ConverterError::Io(format!("Failed to flush output: {}", e)))
+}
+
+/// Pack externally generated MVT tiles (e.g. from tippecanoe) into a PMTiles archive
+/// 
+/// Gzipped tiles are decompressed first, so they are compressed once according to
+/// `options.tile_compression`. With `validate_mvt`, every tile must decode as an MVT
+/// tile with at least one layer. Coordinates outside their zoom level are rejected.
+pub fn pack_tiles(
+    tiles: Vec<(TileCoord, Vec<u8>)>,
+    metadata: &TileMetadata,
+    options: &TilerOptions,
+    validate_mvt: bool,
+) -> Result<Vec<u8>, ConverterError> {
+    let mut raw_tiles = Vec::with_capacity(tiles.len());
+    for (coord, data) in tiles {
+        let data = if data.starts_with(&[0x1f, 0x8b]) { gunzip_tile(coord, &data)? } else { data };
+        
+        if validate_mvt {
+            let tile = <crate::mvt_encoder::vector_tile::Tile as prost::Message>::decode(data.as_slice())
+                .map_err(|e| ConverterError::Parse(format!("Tile {} is not valid MVT: {}", coord.to_path(), e)))?;
+            if tile.layers.is_empty() {
+                return Err(ConverterError::Parse(format!("Tile {} has no layers", coord.to_path())));
+            }
+        }
+        raw_tiles.push((coord, data));
+    }
+    
+    encode_pmtiles_with_options(raw_tiles, metadata, options)
+}
+
+/// Decompress one gzipped tile
+fn gunzip_tile(coord: TileCoord, data: &[u8]) -> Result<Vec<u8>, ConverterError> {
+    let mut raw = Vec::new();
+    std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(data), &mut raw)
+        .map_err(|e| ConverterError::Parse(format!("Tile {}: invalid gzip data: {}", coord.to_path(), e)))?;
+    Ok(raw)
+}
+
+/// Most tiles an archive may address: the limit for filling empty tiles, and for
+/// `read_pmtiles_tiles` once runs are expanded
+const MAX_ADDRESSED_TILES: u64 = 1 << 22;
+
+/// Read the tiles of a PMTiles v3 archive as raw MVT, in tile id order
+/// 
+/// Tiles addressed by a run are returned once per coordinate, and gzipped tile data is
+/// decompressed. Only the root directory is read, so archives with leaf directories
+/// (which this module never writes) are rejected, as are brotli or zstd tile data, tiles
+/// above `MAX_ZOOM` and archives expanding to more than `MAX_ADDRESSED_TILES` tiles.
+pub fn read_pmtiles_tiles(bytes: &[u8]) -> Result<Vec<(TileCoord, Vec<u8>)>, ConverterError> {
+    if bytes.len() < 127 || &bytes[0..8] != b"PMTiles\x03" {
+        return Err(ConverterError::Parse("Not a PMTiles v3 archive".to_string()));
+    }
+    let field = |at: usize| {
+        usize::try_from(u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap())).unwrap_or(usize::MAX)
+    };
+    let section = |offset: usize, length: usize, name: &str| {
+        bytes
+            .get(offset..offset.saturating_add(length))
+            .ok_or_else(|| ConverterError::Parse(format!("{} lies outside the archive", name)))
+    };
+    let directory = section(field(8), field(16), "Root directory")?;
+    let tile_data = section(field(56), field(64), "Tile data")?;
+    
+    let directory = match bytes[97] {
+        1 => directory.to_vec(),
+        2 => {
+            let mut raw = Vec::new();
+            std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(directory), &mut raw)
+                .map_err(|e| ConverterError::Parse(format!("Root directory does not decompress: {}", e)))?;
+            raw
+        }
+        other => return Err(ConverterError::Parse(format!("Unsupported internal compression {}", other))),
+    };
+    let gzipped = match bytes[98] {
+        0 | 1 => false,
+        2 => true,
+        other => return Err(ConverterError::Parse(format!("Unsupported tile compression {}", other))),
+    };
+    let entries = decode_directory(&directory).map_err(ConverterError::Parse)?;
+    
+    // Tile ids from the first tile past `MAX_ZOOM` on are out of range
+    let end_tile_id = ((1u64 << (2 * (MAX_ZOOM as u32 + 1))) - 1) / 3;
+    let mut expanded = 0u64;
+    let mut tiles = Vec::new();
+    for entry in entries {
+        if entry.run_length == 0 {
+            return Err(ConverterError::Parse("Archives with leaf directories are not supported".to_string()));
+        }
+        let run_end = entry
+            .tile_id
+            .checked_add(entry.run_length as u64)
+            .filter(|&end| end <= end_tile_id)
+            .ok_or_else(|| {
+                ConverterError::Parse(format!("Tile {} lies above zoom {}", entry.tile_id, MAX_ZOOM))
+            })?;
+        expanded += entry.run_length as u64;
+        if expanded > MAX_ADDRESSED_TILES {
+            return Err(ConverterError::TooLarge(format!(
+                "Archive holds more than {} tiles once runs are expanded",
+                MAX_ADDRESSED_TILES
+            )));
+        }
+        let data = entry
+            .offset
+            .checked_add(entry.length as usize)
+            .and_then(|end| tile_data.get(entry.offset..end))
+            .ok_or_else(|| ConverterError::Parse(format!("Tile {} lies outside the tile data", entry.tile_id)))?;
+        for tile_id in entry.tile_id..run_end {
+            let coord = tile_id_to_coord(tile_id);
+            let data = if gzipped { gunzip_tile(coord, data)? } else { data.to_vec() };
+            tiles.push((coord, data));
+        }
+    }
+    Ok(tiles)
+}
+
+/// Rewrite a PMTiles archive with tiles added, replaced or removed
+/// 
+/// The tiles of `archive` (see `read_pmtiles_tiles`) are merged with `tiles`, which hold
+/// raw MVT and replace existing tiles at the same coordinate; tiles at `remove` are
+/// dropped. The merged set is re-sorted and re-indexed as by `encode_pmtiles_with_options`,
+/// with `metadata` (describing the merged set) replacing the archive's metadata.
+pub fn update_pmtiles(
+    archive: &[u8],
+    tiles: Vec<(TileCoord, Vec<u8>)>,
+    remove: &[TileCoord],
+    metadata: &TileMetadata,
+    options: &TilerOptions,
+) -> Result<Vec<u8>, ConverterError> {
+    let mut merged: std::collections::BTreeMap<u64, (TileCoord, Vec<u8>)> = std::collections::BTreeMap::new();
+    for (coord, data) in read_pmtiles_tiles(archive)?.into_iter().chain(tiles) {
+        merged.insert(coord_to_tile_id(coord.z, coord.x, coord.y)?, (coord, data));
+    }
+    for coord in remove {
+        merged.remove(&coord_to_tile_id(coord.z, coord.x, coord.y)?);
+    }
+    encode_pmtiles_with_options(merged.into_values().collect(), metadata, options)
+}
+
+/// Encode a PMTiles v3 archive with header, empty root directory and JSON metadata only
+/// 
+/// The tile data section is empty and all tile counts are zero, so the result is a
+/// small, valid archive for checking bounds, zooms and metadata before a full run.
+pub fn encode_pmtiles_header_only(metadata: &TileMetadata, options: &TilerOptions) -> Result<Vec<u8>, ConverterError> {
+    let header_size = 127;
+    let directory_data = encode_directory(&[])?;
+    let json_metadata = generate_json_metadata(metadata, None)?;
+    
+    let json_metadata_offset = header_size + directory_data.len();
+    let tile_data_offset = json_metadata_offset + json_metadata.len();
+    
+    let mut buffer = Cursor::new(Vec::new());
+    write_header(
+        &mut buffer,
+        metadata,
+        options.tile_compression,
+        options.clustered,
+        &TileCounts::default(),
+        header_size,
+        directory_data.len(),
+        json_metadata_offset,
+        json_metadata.len(),
+        tile_data_offset,
+        0,
+    )?;
     buffer
         .write_all(&directory_data)
-        .map_err(|e| format!("Failed to write directory: {}", e))?;
-    
-    // Write JSON metadata
+        .map_err(|e| ConverterError::Encode(format!("Failed to write directory: {}", e)))?;
     buffer
         .write_all(&json_metadata)
-        .map_err(|e| format!("Failed to write JSON metadata: {}", e))?;
-    
-    // Write tile data
-    for entry in &tile_entries {
-        buffer
-            .write_all(&entry.data)
-            .map_err(|e| format!("Failed to write tile data: {}", e))?;
-    }
+        .map_err(|e| ConverterError::Encode(format!("Failed to write JSON metadata: {}", e)))?;
     
     Ok(buffer.into_inner())
 }
 
-/// Generate JSON metadata (TileJSON format)
+/// Generate JSON metadata (TileJSON format), gzip-compressed
+fn generate_json_metadata(metadata: &TileMetadata, content_hash: Option<&str>) -> Result<Vec<u8>, ConverterError> {
+    let tilejson = build_tilejson(metadata, content_hash);
+    let json_str = serde_json::to_string(&serde_json::Value::Object(tilejson))
+        .map_err(|e| ConverterError::Encode(format!("Failed to serialize JSON: {}", e)))?;
+    
+    // Compress with gzip
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder
+        .write_all(json_str.as_bytes())
+        .map_err(|e| ConverterError::Encode(format!("Failed to compress JSON: {}", e)))?;
+    encoder
+        .finish()
+        .map_err(|e| ConverterError::Encode(format!("Failed to finish compression: {}", e)))
+}
+
+/// TileJSON `generator`: the configured one or this crate
+pub(crate) fn generator(metadata: &TileMetadata) -> String {
+    metadata.generator.clone().unwrap_or_else(|| "web-vector-tile-maker".to_string())
+}
+
+/// TileJSON `generator_options`: the configured ones or a tippecanoe-style command line
+pub(crate) fn generator_options(metadata: &TileMetadata) -> String {
+    metadata
+        .generator_options
+        .clone()
+        .unwrap_or_else(|| format!("web-vector-tile-maker -o {}.pmtiles", metadata.layer_name))
+}
+
+/// Format bounds as `min_lon,min_lat,max_lon,max_lat` with `metadata.bounds_precision` decimals
+pub(crate) fn format_bounds(metadata: &TileMetadata) -> String {
+    let (min_lon, min_lat, max_lon, max_lat) = metadata.bounds;
+    let precision = metadata.bounds_precision;
+    format!("{min_lon:.precision$},{min_lat:.precision$},{max_lon:.precision$},{max_lat:.precision$}")
+}
+
+/// Build the TileJSON metadata object
 /// Matches tippecanoe's JSON structure exactly for compatibility
-fn generate_json_metadata(metadata: &TileMetadata) -> Result<Vec<u8>, String> {
+/// 
+/// `vector_layers[].fields` and `tilestats` attributes are sorted by field name; `tilestats`
+/// is left out when `metadata.tilestats` is false.
+pub(crate) fn build_tilejson(
+    metadata: &TileMetadata,
+    content_hash: Option<&str>,
+) -> serde_json::Map<String, serde_json::Value> {
     use serde_json::{json, Map, Value};
     
     // Format antimeridian_adjusted_bounds as string (like tippecanoe)
-    let antimeridian_bounds = format!(
-        "{:.6},{:.6},{:.6},{:.6}",
-        metadata.bounds.0, metadata.bounds.1, metadata.bounds.2, metadata.bounds.3
-    );
+    let antimeridian_bounds = format_bounds(metadata);
     
     // Build JSON object manually to preserve exact order
     let mut tilejson = Map::new();
@@ -139,11 +524,16 @@ fn generate_json_metadata(metadata: &TileMetadata) -> Result<Vec<u8>, String> {
     tilejson.insert("format".to_string(), json!("pbf"));
     
     // 3. type
-    tilejson.insert("type".to_string(), json!("overlay"));
+    tilejson.insert("type".to_string(), json!(metadata.tileset_type.as_str()));
     
     // 4. description
     tilejson.insert("description".to_string(), json!(format!("{}.pmtiles", metadata.layer_name)));
     
+    // attribution (only when configured)
+    if let Some(attribution) = &metadata.attribution {
+        tilejson.insert("attribution".to_string(), json!(attribution));
+    }
+    
     // 5. version
     tilejson.insert("version".to_string(), json!("2"));
     
@@ -178,70 +568,274 @@ fn generate_json_metadata(metadata: &TileMetadata) -> Result<Vec<u8>, String> {
     tilejson.insert("strategies".to_string(), json!(strategies));
     
     // 7. generator
-    tilejson.insert("generator".to_string(), json!("web-vector-tile-maker"));
+    tilejson.insert("generator".to_string(), json!(generator(metadata)));
     
     // 8. generator_options
-    tilejson.insert("generator_options".to_string(), json!(format!("web-vector-tile-maker -o {}.pmtiles", metadata.layer_name)));
+    tilejson.insert("generator_options".to_string(), json!(generator_options(metadata)));
     
     // 9. antimeridian_adjusted_bounds
     tilejson.insert("antimeridian_adjusted_bounds".to_string(), json!(antimeridian_bounds));
     
     // 10. vector_layers
-    let mut vector_layer = Map::new();
-    vector_layer.insert("id".to_string(), json!(metadata.layer_name));
-    vector_layer.insert("description".to_string(), json!(""));
-    vector_layer.insert("minzoom".to_string(), json!(metadata.min_zoom));
-    vector_layer.insert("maxzoom".to_string(), json!(metadata.max_zoom));
-    // fields: map of field names to types
-    let mut fields_map = Map::new();
-    for (key, value_type) in &metadata.fields {
-        fields_map.insert(key.clone(), json!(value_type));
-    }
-    vector_layer.insert("fields".to_string(), json!(fields_map));
-    tilejson.insert("vector_layers".to_string(), json!(vec![Value::Object(vector_layer)]));
-    
-    // 11. tilestats
-    let mut tilestats_layer = Map::new();
-    tilestats_layer.insert("layer".to_string(), json!(metadata.layer_name));
-    tilestats_layer.insert("count".to_string(), json!(metadata.feature_count));
-    tilestats_layer.insert("geometry".to_string(), json!(metadata.geometry_type));
-    tilestats_layer.insert("attributeCount".to_string(), json!(metadata.attributes.len()));
-    tilestats_layer.insert("attributes".to_string(), json!(metadata.attributes));
+    let layers = metadata.output_layers();
+    let mut vector_layers = Vec::new();
+    for layer in &layers {
+        let mut vector_layer = Map::new();
+        vector_layer.insert("id".to_string(), json!(layer.name));
+        vector_layer.insert("description".to_string(), json!(layer.description));
+        let (min_zoom, max_zoom) = layer.zooms.unwrap_or((metadata.min_zoom, metadata.max_zoom));
+        vector_layer.insert("minzoom".to_string(), json!(min_zoom));
+        vector_layer.insert("maxzoom".to_string(), json!(max_zoom));
+        // source: input the layer came from (non-standard, ignored by other readers)
+        vector_layer.insert("source".to_string(), json!(layer.source));
+        // fields: map of field names to types, in sorted key order so the output is
+        // reproducible whether or not serde_json preserves insertion order
+        let mut fields_map = Map::new();
+        let sorted_fields: std::collections::BTreeMap<&String, &String> = layer.fields.iter().collect();
+        for (key, value_type) in sorted_fields {
+            fields_map.insert(key.clone(), json!(value_type));
+        }
+        vector_layer.insert("fields".to_string(), json!(fields_map));
+        vector_layers.push(Value::Object(vector_layer));
+    }
+    tilejson.insert("vector_layers".to_string(), json!(vector_layers));
+    
+    // 11. tilestats (optional)
+    if metadata.tilestats {
+        tilejson.insert("tilestats".to_string(), build_tilestats(&layers));
+    }
+    
+    // 12. content_hash (optional, first 16 hex digits of SHA-256 over the tile data)
+    if let Some(content_hash) = content_hash {
+        tilejson.insert("content_hash".to_string(), json!(content_hash));
+    }
+    
+    tilejson
+}
+
+/// Build a standalone TileJSON 3.0 document for tiles served from `tiles_url`
+/// 
+/// This is the PMTiles metadata plus the TileJSON 3.0 fields needed to serve raw tiles:
+/// `tilejson`, `tiles` (the single URL template), `scheme`, `minzoom`, `maxzoom`,
+/// `bounds` and `center`. The template must contain `{z}/{x}/{y}`.
+pub fn build_standalone_tilejson(
+    metadata: &TileMetadata,
+    tiles_url: &str,
+    scheme: TileScheme,
+) -> Result<serde_json::Map<String, serde_json::Value>, ConverterError> {
+    use serde_json::json;
+    
+    if !tiles_url.contains("{z}/{x}/{y}") {
+        return Err(ConverterError::InvalidInput(format!(
+            "Tile URL template {:?} must contain {{z}}/{{x}}/{{y}}",
+            tiles_url
+        )));
+    }
+    
+    let mut tilejson = build_tilejson(metadata, None);
+    let (min_lon, min_lat, max_lon, max_lat) = metadata.bounds;
+    tilejson.insert("tilejson".to_string(), json!("3.0.0"));
+    tilejson.insert("tiles".to_string(), json!([tiles_url]));
+    tilejson.insert("scheme".to_string(), json!(scheme.as_str()));
+    tilejson.insert("minzoom".to_string(), json!(metadata.min_zoom));
+    tilejson.insert("maxzoom".to_string(), json!(metadata.max_zoom));
+    tilejson.insert("bounds".to_string(), json!([min_lon, min_lat, max_lon, max_lat]));
+    tilejson.insert("center".to_string(), json!([metadata.center.0, metadata.center.1, metadata.center_zoom]));
+    Ok(tilejson)
+}
+
+/// Build the TileJSON `tilestats` object, with one entry per layer in the given order
+/// 
+/// Each entry carries the layer's own feature count, geometry type and attribute
+/// statistics; `layerCount` is the number of layers.
+pub fn build_tilestats(layers: &[LayerMetadata]) -> serde_json::Value {
+    use serde_json::{json, Map, Value};
+    
+    let mut tilestats_layers = Vec::new();
+    for layer in layers {
+        let mut tilestats_layer = Map::new();
+        tilestats_layer.insert("layer".to_string(), json!(layer.name));
+        tilestats_layer.insert("count".to_string(), json!(layer.feature_count));
+        tilestats_layer.insert("geometry".to_string(), json!(layer.geometry_type));
+        tilestats_layer.insert("attributeCount".to_string(), json!(layer.attributes.len()));
+        tilestats_layer.insert("attributes".to_string(), json!(layer.attributes));
+        tilestats_layers.push(Value::Object(tilestats_layer));
+    }
     
     let mut tilestats = Map::new();
-    tilestats.insert("layerCount".to_string(), json!(1));
-    tilestats.insert("layers".to_string(), json!(vec![Value::Object(tilestats_layer)]));
-    tilejson.insert("tilestats".to_string(), json!(tilestats));
+    tilestats.insert("layerCount".to_string(), json!(tilestats_layers.len()));
+    tilestats.insert("layers".to_string(), json!(tilestats_layers));
+    Value::Object(tilestats)
+}
+
+/// Check an archive against the PMTiles v3 spec
+/// 
+/// Checks the magic number and version, that all sections lie within the archive without
+/// overlapping, that the root directory decompresses and decodes, that its entries are
+/// sorted by tile id and point into the tile data section (in tile id order when the
+/// archive is clustered), that the header tile counts and zoom range match the directory,
+/// and that the JSON metadata is a JSON object. Leaf directories are not followed. Returns
+/// every violation found.
+pub fn validate_pmtiles(bytes: &[u8]) -> Result<(), Vec<String>> {
+    let mut violations = Vec::new();
+    if bytes.len() < 127 {
+        return Err(vec![format!("Archive is {} bytes, shorter than the 127-byte header", bytes.len())]);
+    }
+    if &bytes[0..7] != b"PMTiles" {
+        violations.push("Missing \"PMTiles\" magic number".to_string());
+    }
+    if bytes[7] != 3 {
+        violations.push(format!("Unsupported version {} (expected 3)", bytes[7]));
+    }
+    
+    let field = |at: usize| u64::from_le_bytes(bytes[at..at + 8].try_into().unwrap());
+    let sections = [
+        ("root directory", field(8), field(16)),
+        ("JSON metadata", field(24), field(32)),
+        ("leaf directories", field(40), field(48)),
+        ("tile data", field(56), field(64)),
+    ];
+    let mut in_bounds = Vec::new();
+    for &(name, offset, length) in &sections {
+        match offset.checked_add(length) {
+            _ if length == 0 => {}
+            Some(end) if offset >= 127 && end <= bytes.len() as u64 => in_bounds.push((name, offset, end)),
+            _ => violations.push(format!(
+                "{} ({} bytes at offset {}) lies outside the archive of {} bytes",
+                name, length, offset, bytes.len()
+            )),
+        }
+    }
+    in_bounds.sort_by_key(|&(_, offset, _)| offset);
+    for pair in in_bounds.windows(2) {
+        if pair[0].2 > pair[1].1 {
+            violations.push(format!("{} overlaps {}", pair[0].0, pair[1].0));
+        }
+    }
+    let (root_offset, root_length) = (field(8), field(16));
+    if root_offset.saturating_add(root_length) > 16384 {
+        violations.push("Root directory does not end within the first 16384 bytes".to_string());
+    }
+    let (min_zoom, max_zoom) = (bytes[100], bytes[101]);
+    if min_zoom > max_zoom {
+        violations.push(format!("Min zoom {} is above max zoom {}", min_zoom, max_zoom));
+    }
     
-    let json_str = serde_json::to_string(&Value::Object(tilejson))
-        .map_err(|e| format!("Failed to serialize JSON: {}", e))?;
+    let internal_compression = bytes[97];
+    let decompress = |name: &str, data: &[u8]| -> Result<Vec<u8>, String> {
+        match internal_compression {
+            1 => Ok(data.to_vec()),
+            2 => {
+                let mut raw = Vec::new();
+                std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(data), &mut raw)
+                    .map_err(|e| format!("{} does not decompress: {}", name, e))?;
+                Ok(raw)
+            }
+            other => Err(format!("Cannot decode {} with internal compression {}", name, other)),
+        }
+    };
+    let section = |offset: u64, length: u64| bytes.get(offset as usize..offset.saturating_add(length) as usize);
     
-    // Compress with gzip
-    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
-    encoder
-        .write_all(json_str.as_bytes())
-        .map_err(|e| format!("Failed to compress JSON: {}", e))?;
-    encoder
-        .finish()
-        .map_err(|e| format!("Failed to finish compression: {}", e))
+    if let Some(json) = section(field(24), field(32)) {
+        match decompress("JSON metadata", json) {
+            Ok(raw) => match serde_json::from_slice::<serde_json::Value>(&raw) {
+                Ok(serde_json::Value::Object(_)) => {}
+                _ => violations.push("JSON metadata is not a JSON object".to_string()),
+            },
+            Err(violation) => violations.push(violation),
+        }
+    }
+    
+    let Some(directory) = section(root_offset, root_length) else {
+        return Err(violations);
+    };
+    let entries = match decompress("Root directory", directory).and_then(|raw| decode_directory(&raw)) {
+        Ok(entries) => entries,
+        Err(violation) => {
+            violations.push(violation);
+            return Err(violations);
+        }
+    };
+    
+    let clustered = bytes[96] == 1;
+    let tile_data_length = field(64);
+    let mut next_tile_id = 0u64;
+    let mut data_end = 0u64;
+    let mut offsets = HashSet::new();
+    let mut addressed = 0u64;
+    let mut zooms = None;
+    for entry in &entries {
+        if entry.tile_id < next_tile_id {
+            violations.push(format!("Tile id {} is out of order or overlaps the previous run", entry.tile_id));
+        }
+        next_tile_id = entry.tile_id.saturating_add(entry.run_length.max(1) as u64);
+        if entry.run_length == 0 {
+            // Leaf directory pointer
+            continue;
+        }
+        
+        let (offset, end) = (entry.offset as u64, entry.offset as u64 + entry.length as u64);
+        if end > tile_data_length {
+            violations.push(format!("Tile {} ends at {}, past the tile data section", entry.tile_id, end));
+        }
+        // Clustered data is laid out in tile id order; only deduplicated tiles point back
+        let first_use = offsets.insert(offset);
+        if clustered && first_use && offset < data_end {
+            violations.push(format!("Tile {} breaks the clustered data order", entry.tile_id));
+        }
+        data_end = data_end.max(end);
+        addressed += entry.run_length as u64;
+        
+        let last_id = entry.tile_id + entry.run_length as u64 - 1;
+        let (low, high) = zooms.unwrap_or((u8::MAX, 0));
+        zooms = Some((low.min(tile_id_zoom(entry.tile_id)), high.max(tile_id_zoom(last_id))));
+    }
+    
+    // Counts of 0 mean "unknown" in the spec
+    for (name, header, actual) in [
+        ("addressed tiles", field(72), addressed),
+        ("tile entries", field(80), entries.len() as u64),
+        ("tile contents", field(88), offsets.len() as u64),
+    ] {
+        if header != 0 && header != actual {
+            violations.push(format!("Header counts {} {} but the directory has {}", header, name, actual));
+        }
+    }
+    if let Some((low, high)) = zooms {
+        if (low, high) != (min_zoom, max_zoom) {
+            violations.push(format!(
+                "Header zoom range {}-{} does not match the tiles' zoom range {}-{}",
+                min_zoom, max_zoom, low, high
+            ));
+        }
+    }
+    
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(violations)
+    }
 }
 
 /// PMTiles v3 header structure
+#[allow(clippy::too_many_arguments)]
 fn write_header(
     writer: &mut Cursor<Vec<u8>>,
     metadata: &TileMetadata,
-    tile_count: usize,
+    tile_compression: TileCompression,
+    clustered: bool,
+    counts: &TileCounts,
     root_directory_offset: usize,
     root_directory_length: usize,
     json_metadata_offset: usize,
     json_metadata_length: usize,
     tile_data_offset: usize,
     tile_data_length: usize,
-) -> Result<(), String> {
+) -> Result<(), ConverterError> {
     // Magic number "PMTiles" + version (0x03)
     writer
         .write_all(b"PMTiles\x03")
-        .map_err(|e| format!("Failed to write magic: {}", e))?;
+        .map_err(|e| ConverterError::Encode(format!("Failed to write magic: {}", e)))?;
     
     // Root directory offset and length
     writer.write_u64::<LittleEndian>(root_directory_offset as u64).unwrap();
@@ -260,26 +854,24 @@ fn write_header(
     writer.write_u64::<LittleEndian>(tile_data_length as u64).unwrap();
     
     // Addressed tiles count
-    writer.write_u64::<LittleEndian>(tile_count as u64).unwrap();
+    writer.write_u64::<LittleEndian>(counts.addressed as u64).unwrap();
     
     // Tile entries count
-    writer.write_u64::<LittleEndian>(tile_count as u64).unwrap();
+    writer.write_u64::<LittleEndian>(counts.entries as u64).unwrap();
     
     // Tile contents count
-    writer.write_u64::<LittleEndian>(tile_count as u64).unwrap();
+    writer.write_u64::<LittleEndian>(counts.contents as u64).unwrap();
     
-    // Clustered (1 = true, tiles are sorted by TileID)
-    // PMTiles v3 spec: Clustered means tiles are ordered by TileID
-    // We sort tiles by TileID, so this should be 1
-    writer.write_u8(1).unwrap();
+    // Clustered (1 = true, tile data is ordered by TileID; 0 = input order)
+    writer.write_u8(clustered as u8).unwrap();
     
     // Internal compression (2 = gzip)
     // PMTiles v3 spec: 0x00=Unknown, 0x01=None, 0x02=gzip, 0x03=brotli, 0x04=zstd
     writer.write_u8(2).unwrap();
     
-    // Tile compression (2 = gzip) - MVT tiles are gzip compressed
+    // Tile compression (1 = none, 2 = gzip)
     // PMTiles v3 spec: 0x00=Unknown, 0x01=None, 0x02=gzip, 0x03=brotli, 0x04=zstd
-    writer.write_u8(2).unwrap();
+    writer.write_u8(tile_compression as u8).unwrap();
     
     // Tile type (1 = MVT)
     writer.write_u8(1).unwrap();
@@ -289,6 +881,7 @@ fn write_header(
     writer.write_u8(metadata.max_zoom).unwrap();
     
     // Min/Max position (lon/lat in degrees * 10^7)
+    // From the unrounded bounds, whatever precision the metadata strings use
     let min_lon_e7 = (metadata.bounds.0 * 10_000_000.0) as i32;
     let min_lat_e7 = (metadata.bounds.1 * 10_000_000.0) as i32;
     let max_lon_e7 = (metadata.bounds.2 * 10_000_000.0) as i32;
@@ -300,7 +893,7 @@ fn write_header(
     writer.write_i32::<LittleEndian>(max_lat_e7).unwrap();
     
     // Center zoom, lon, lat
-    let center_zoom = ((metadata.min_zoom + metadata.max_zoom) / 2) as i8;
+    let center_zoom = metadata.center_zoom as i8;
     let center_lon_e7 = (metadata.center.0 * 10_000_000.0) as i32;
     let center_lat_e7 = (metadata.center.1 * 10_000_000.0) as i32;
     
@@ -313,7 +906,7 @@ fn write_header(
 
 /// Encode directory entries
 /// PMTiles v3 directory format - each field in separate sections
-fn encode_directory(entries: &[TileEntry]) -> Result<Vec<u8>, String> {
+fn encode_directory(entries: &[DirectoryEntry]) -> Result<Vec<u8>, ConverterError> {
     let mut dir_buffer = Vec::new();
     
     // Number of entries
@@ -326,39 +919,32 @@ fn encode_directory(entries: &[TileEntry]) -> Result<Vec<u8>, String> {
         last_tile_id = entry.tile_id;
     }
     
-    // Section 2: run_lengths (always 1 for non-RLE tiles)
-    for _ in entries {
-        write_varint(&mut dir_buffer, 1);
+    // Section 2: run_lengths (number of consecutive tile ids sharing the data)
+    for entry in entries {
+        write_varint(&mut dir_buffer, entry.run_length as u64);
     }
     
-    // Section 3: lengths (delta encoded)
-    let mut last_length = 0u32;
-    for (_idx, entry) in entries.iter().enumerate() {
-        let delta = (entry.length as i64) - (last_length as i64);
-        let zigzag_delta = zigzag_encode(delta);
-        
-        write_varint(&mut dir_buffer, zigzag_delta);
-        last_length = entry.length;
+    // Section 3: lengths
+    for entry in entries {
+        write_varint(&mut dir_buffer, entry.length as u64);
     }
     
-    // Section 4: offsets (delta encoded)
-    let mut last_offset = 0usize;
-    for (_idx, entry) in entries.iter().enumerate() {
-        let delta = (entry.offset as i64) - (last_offset as i64);
-        let zigzag_delta = zigzag_encode(delta);
-        
-        write_varint(&mut dir_buffer, zigzag_delta);
-        last_offset = entry.offset;
+    // Section 4: offsets (0 = directly after the previous entry's data, otherwise offset + 1)
+    let mut next_offset = None;
+    for entry in entries {
+        let value = if next_offset == Some(entry.offset) { 0 } else { entry.offset as u64 + 1 };
+        write_varint(&mut dir_buffer, value);
+        next_offset = Some(entry.offset + entry.length as usize);
     }
     
     // Compress directory with gzip
     let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
     encoder
         .write_all(&dir_buffer)
-        .map_err(|e| format!("Failed to compress directory: {}", e))?;
+        .map_err(|e| ConverterError::Encode(format!("Failed to compress directory: {}", e)))?;
     encoder
         .finish()
-        .map_err(|e| format!("Failed to finish compression: {}", e))
+        .map_err(|e| ConverterError::Encode(format!("Failed to finish compression: {}", e)))
 }
 
 /// Write varint (unsigned LEB128)
@@ -376,54 +962,156 @@ fn write_varint(buffer: &mut Vec<u8>, mut value: u64) {
     }
 }
 
-/// ZigZag encoding for signed integers
-fn zigzag_encode(value: i64) -> u64 {
-    ((value << 1) ^ (value >> 63)) as u64
+/// Read a varint (unsigned LEB128) at `*pos`, advancing it
+fn read_varint(buffer: &[u8], pos: &mut usize) -> Result<u64, String> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buffer.get(*pos).ok_or("Directory ends inside a varint")?;
+        *pos += 1;
+        value |= ((byte & 0x7F) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    Err("Varint longer than 64 bits".to_string())
 }
 
+/// Decode an uncompressed directory written by `encode_directory`
+fn decode_directory(buffer: &[u8]) -> Result<Vec<DirectoryEntry>, String> {
+    let mut pos = 0;
+    let count = read_varint(buffer, &mut pos)? as usize;
+    if count > buffer.len() {
+        return Err(format!("Directory claims {} entries in {} bytes", count, buffer.len()));
+    }
+    
+    let mut entries = Vec::with_capacity(count);
+    let mut tile_id = 0u64;
+    for _ in 0..count {
+        tile_id = tile_id
+            .checked_add(read_varint(buffer, &mut pos)?)
+            .ok_or("Tile id overflows 64 bits")?;
+        entries.push(DirectoryEntry { tile_id, run_length: 0, offset: 0, length: 0 });
+    }
+    for entry in &mut entries {
+        entry.run_length = u32::try_from(read_varint(buffer, &mut pos)?).map_err(|_| "Run length exceeds 32 bits")?;
+    }
+    for entry in &mut entries {
+        entry.length = u32::try_from(read_varint(buffer, &mut pos)?).map_err(|_| "Tile length exceeds 32 bits")?;
+    }
+    for index in 0..count {
+        let value = read_varint(buffer, &mut pos)?;
+        entries[index].offset = if value == 0 && index > 0 {
+            entries[index - 1].offset + entries[index - 1].length as usize
+        } else if value == 0 {
+            return Err("First directory entry has a relative offset".to_string());
+        } else {
+            (value - 1) as usize
+        };
+    }
+    if pos != buffer.len() {
+        return Err(format!("{} trailing bytes after the directory entries", buffer.len() - pos));
+    }
+    Ok(entries)
+}
+
+/// Zoom level of a tile id (inverse of the zoom part of `coord_to_tile_id`)
+fn tile_id_zoom(tile_id: u64) -> u8 {
+    let mut zoom = 0;
+    // Tiles on zoom levels 0..=zoom; saturates past zoom 31, the highest valid one
+    while zoom < MAX_TILE_ID_ZOOM && tile_id >= ((1u128 << (2 * (zoom as u32 + 1))) - 1) as u64 / 3 {
+        zoom += 1;
+    }
+    zoom
+}
+
+/// Convert a tile id back to Z/X/Y coordinates (inverse of `coord_to_tile_id`)
+fn tile_id_to_coord(tile_id: u64) -> TileCoord {
+    let z = tile_id_zoom(tile_id);
+    let base = ((1u128 << (2 * z as u32)) - 1) as u64 / 3;
+    let (x, y) = hilbert_to_xy(tile_id - base, z);
+    TileCoord::new(z, x, y)
+}
+
+/// Highest zoom level whose tile ids fit in 64 bits (PMTiles v3 limit)
+const MAX_TILE_ID_ZOOM: u8 = 31;
+
 /// Convert Z/X/Y coordinates to tile ID using Hilbert curve
 /// PMTiles v3 spec requires Hilbert curve for tile_id calculation
-/// Implementation based on: https://en.wikipedia.org/wiki/Hilbert_curve
-fn coord_to_tile_id(z: u8, x: u32, y: u32) -> u64 {
-    // Top 8 bits for zoom level
-    let mut id = (z as u64) << 56;
-    
-    // Calculate Hilbert curve index for x, y at this zoom level
-    let hilbert_index = xy_to_hilbert(x, y, z);
-    
-    // Store Hilbert index in the remaining 56 bits
-    id |= hilbert_index;
+/// 
+/// The id is the number of tiles on all lower zoom levels ((4^z - 1) / 3) plus the
+/// Hilbert index within zoom `z`. Coordinates outside the zoom level are an error
+/// rather than being clamped to the edge tile.
+fn coord_to_tile_id(z: u8, x: u32, y: u32) -> Result<u64, ConverterError> {
+    if z == 0 {
+        return if x == 0 && y == 0 {
+            Ok(0)
+        } else {
+            Err(ConverterError::Encode(format!("Tile 0/{}/{} is out of range", x, y)))
+        };
+    }
+    if z > MAX_TILE_ID_ZOOM {
+        return Err(ConverterError::Encode(format!(
+            "Zoom {} exceeds the PMTiles maximum of {}",
+            z, MAX_TILE_ID_ZOOM
+        )));
+    }
+    let max_coord = (1u64 << z) - 1;
+    if x as u64 > max_coord || y as u64 > max_coord {
+        return Err(ConverterError::Encode(format!("Tile {}/{}/{} is out of range", z, x, y)));
+    }
     
-    id
+    // Tiles on zoom levels 0..z
+    let base = ((1u64 << (2 * z as u32)) - 1) / 3;
+    Ok(base + xy_to_hilbert(x, y, z))
 }
 
 /// Convert (x, y) coordinates to Hilbert curve index
 /// Based on the algorithm from: https://en.wikipedia.org/wiki/Hilbert_curve
-fn xy_to_hilbert(mut x: u32, mut y: u32, z: u8) -> u64 {
-    // Clamp coordinates to valid range
-    let max_coord = if z > 0 { (1u32 << z) - 1 } else { 0 };
-    x = x.min(max_coord);
-    y = y.min(max_coord);
-    
+/// 
+/// `x` and `y` must be below `2^z`; callers validate or clamp them.
+pub(crate) fn xy_to_hilbert(mut x: u32, mut y: u32, z: u8) -> u64 {
     if z == 0 {
         return 0;
     }
+    debug_assert!(
+        x as u64 >> z == 0 && y as u64 >> z == 0,
+        "({}, {}) out of range at zoom {}",
+        x,
+        y,
+        z
+    );
     
-    let n = 1u32 << z;
+    let n = 1u64 << z;
     let mut d = 0u64;
-    let mut s = (n >> 1) as u64;
+    let mut s = n >> 1;
     
     while s > 0 {
         let rx = ((x as u64) & s) != 0;
         let ry = ((y as u64) & s) != 0;
         d += s * s * ((3 * rx as u64) ^ (ry as u64));
-        rot(n as u64, &mut x, &mut y, rx, ry);
+        rot(n, &mut x, &mut y, rx, ry);
         s >>= 1;
     }
     
     d
 }
 
+/// Convert a Hilbert curve index at zoom `z` back to (x, y) (inverse of `xy_to_hilbert`)
+fn hilbert_to_xy(mut d: u64, z: u8) -> (u32, u32) {
+    let (mut x, mut y) = (0u32, 0u32);
+    let mut s = 1u64;
+    while s < 1u64 << z {
+        let rx = (d / 2) & 1 != 0;
+        let ry = (d ^ rx as u64) & 1 != 0;
+        rot(s, &mut x, &mut y, rx, ry);
+        x += (s * rx as u64) as u32;
+        y += (s * ry as u64) as u32;
+        d /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
 /// Rotate/flip a quadrant
 fn rot(n: u64, x: &mut u32, y: &mut u32, rx: bool, ry: bool) {
     if !ry {
@@ -432,9 +1120,7 @@ fn rot(n: u64, x: &mut u32, y: &mut u32, rx: bool, ry: bool) {
             *y = (n - 1) as u32 - *y;
         }
         // Swap x and y
-        let temp = *x;
-        *x = *y;
-        *y = temp;
+        std::mem::swap(x, y);
     }
 }
 
@@ -445,11 +1131,118 @@ struct TileEntry {
     data: Vec<u8>,
 }
 
+/// Root directory entry: `run_length` consecutive tile ids addressing the same data
+#[derive(Debug)]
+struct DirectoryEntry {
+    tile_id: u64,
+    run_length: u32,
+    offset: usize,
+    length: u32,
+}
+
+/// Tile counts of the header
+#[derive(Default)]
+struct TileCounts {
+    /// Tile ids with data
+    addressed: usize,
+    /// Root directory entries
+    entries: usize,
+    /// Distinct tile data blobs
+    contents: usize,
+}
+
+/// Find every tile within the metadata bounds and zoom range that has no data, so they
+/// can address an empty MVT tile and readers never hit a missing tile
+/// 
+/// Returns the (compressed) empty tile and the sorted ids of the missing tiles; the caller
+/// stores the empty tile once. Bounds and zooms covering more than `MAX_ADDRESSED_TILES`
+/// tiles are rejected before any id is collected.
+fn fill_empty_tiles(
+    tile_entries: &[TileEntry],
+    metadata: &TileMetadata,
+    options: &TilerOptions,
+) -> Result<(Vec<u8>, Vec<u64>), ConverterError> {
+    let (min_lon, min_lat, max_lon, max_lat) = metadata.bounds;
+    let ranges: Vec<_> = (metadata.min_zoom..=metadata.max_zoom)
+        .map(|z| {
+            // Tile rows grow southwards, so the maximum latitude gives the minimum row
+            let (x_min, y_min) = crate::projection::lonlat_to_tile(min_lon, max_lat, z);
+            let (x_max, y_max) = crate::projection::lonlat_to_tile(max_lon, min_lat, z);
+            (z, (x_min, x_max), (y_min, y_max))
+        })
+        .collect();
+    let covered: u64 = ranges
+        .iter()
+        .map(|&(_, (x_min, x_max), (y_min, y_max))| {
+            (x_max as u64 + 1).saturating_sub(x_min as u64) * (y_max as u64 + 1).saturating_sub(y_min as u64)
+        })
+        .sum();
+    if covered > MAX_ADDRESSED_TILES {
+        return Err(ConverterError::TooLarge(format!(
+            "Filling empty tiles would address {} tiles, more than {}",
+            covered, MAX_ADDRESSED_TILES
+        )));
+    }
+    
+    let present: HashSet<u64> = tile_entries.iter().map(|e| e.tile_id).collect();
+    let mut missing = Vec::new();
+    for (z, (x_min, x_max), (y_min, y_max)) in ranges {
+        for x in x_min..=x_max {
+            for y in y_min..=y_max {
+                let tile_id = coord_to_tile_id(z, x, y)?;
+                if !present.contains(&tile_id) {
+                    missing.push(tile_id);
+                }
+            }
+        }
+    }
+    missing.sort_unstable();
+    
+    let empty = crate::mvt_encoder::empty_tile(&metadata.layer_name, options);
+    let empty = match options.tile_compression {
+        TileCompression::None => empty,
+        TileCompression::Gzip => gzip_tile(&empty)?,
+    };
+    Ok((empty, missing))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::{TileCoord, TileMetadata};
 
+    #[test]
+    fn test_build_tilestats_multiple_layers() {
+        let layer = |name: &str, count: usize, geometry: &str, attributes: Vec<serde_json::Value>| LayerMetadata {
+            name: name.to_string(),
+            feature_count: count,
+            geometry_type: geometry.to_string(),
+            fields: Default::default(),
+            attributes,
+            source: "transport".to_string(),
+            description: String::new(),
+            zooms: None,
+        };
+        let layers = vec![
+            layer("roads", 12, "LineString", vec![serde_json::json!({"attribute": "lanes", "type": "number"})]),
+            layer("stations", 3, "Point", vec![]),
+        ];
+        
+        let tilestats = build_tilestats(&layers);
+        assert_eq!(tilestats["layerCount"], 2);
+        let stats = tilestats["layers"].as_array().unwrap();
+        assert_eq!(stats.len(), 2);
+        assert_eq!(stats[0]["layer"], "roads");
+        assert_eq!(stats[0]["count"], 12);
+        assert_eq!(stats[0]["geometry"], "LineString");
+        assert_eq!(stats[0]["attributeCount"], 1);
+        assert_eq!(stats[0]["attributes"][0]["attribute"], "lanes");
+        assert_eq!(stats[1]["layer"], "stations");
+        assert_eq!(stats[1]["count"], 3);
+        assert_eq!(stats[1]["geometry"], "Point");
+        assert_eq!(stats[1]["attributeCount"], 0);
+    }
+    
     #[test]
     fn test_encode_pmtiles_basic() {
         let tiles = vec![
@@ -458,11 +1251,9 @@ mod tests {
         ];
         
         let metadata = TileMetadata {
-            min_zoom: 0,
             max_zoom: 1,
             layer_name: "test".to_string(),
-            bounds: (-180.0, -85.0, 180.0, 85.0),
-            center: (0.0, 0.0),
+            ..Default::default()
         };
         
         let result = encode_pmtiles(tiles, &metadata);
@@ -478,27 +1269,521 @@ mod tests {
     fn test_encode_pmtiles_empty() {
         let tiles = vec![];
         let metadata = TileMetadata {
-            min_zoom: 0,
             max_zoom: 1,
             layer_name: "test".to_string(),
-            bounds: (-180.0, -85.0, 180.0, 85.0),
-            center: (0.0, 0.0),
+            ..Default::default()
         };
         
         let result = encode_pmtiles(tiles, &metadata);
-        assert!(result.is_err());
+        assert!(matches!(result, Err(ConverterError::EmptyInput(_))));
+    }
+    
+    #[test]
+    fn test_encode_pmtiles_uncompressed_tiles() {
+        let features = vec![crate::tiler::TileFeature {
+            geometry: crate::tiler::TileGeometry::Point(100, 200),
+            properties: serde_json::Map::new(),
+            label: false,
+        }];
+        let mvt = crate::mvt_encoder::encode_tile(&features, "test").unwrap();
+        let tiles = vec![(TileCoord::new(0, 0, 0), mvt.clone())];
+        
+        let metadata = TileMetadata {
+            layer_name: "test".to_string(),
+            feature_count: 1,
+            ..Default::default()
+        };
+        let options = TilerOptions {
+            tile_compression: TileCompression::None,
+            ..Default::default()
+        };
+        
+        let data = encode_pmtiles_with_options(tiles, &metadata, &options).unwrap();
+        
+        // Header byte 98 is the tile compression (1 = None)
+        assert_eq!(data[98], 1);
+        
+        // The tile data section starts with the raw Tile message: field 3 (layers), wire type 2
+        let tile_data_offset = u64::from_le_bytes(data[56..64].try_into().unwrap()) as usize;
+        assert_ne!(&data[tile_data_offset..tile_data_offset + 2], &[0x1f, 0x8b]);
+        assert_eq!(data[tile_data_offset], 0x1a);
+        assert_eq!(&data[tile_data_offset..tile_data_offset + mvt.len()], &mvt[..]);
+    }
+    
+    #[test]
+    fn test_write_pmtiles_to_sink() {
+        /// Sink recording each write separately
+        #[derive(Default)]
+        struct ChunkSink(Vec<Vec<u8>>);
+        
+        impl Write for ChunkSink {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.push(buf.to_vec());
+                Ok(buf.len())
+            }
+            
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        
+        let tiles = vec![
+            (TileCoord::new(1, 1, 0), vec![9; 40]),
+            (TileCoord::new(0, 0, 0), vec![1, 2, 3, 4]),
+            (TileCoord::new(1, 0, 0), vec![5, 6, 7, 8]),
+        ];
+        let metadata = TileMetadata {
+            max_zoom: 1,
+            layer_name: "test".to_string(),
+            feature_count: 3,
+            ..Default::default()
+        };
+        let options = TilerOptions::default();
+        
+        let mut sink = ChunkSink::default();
+        write_pmtiles(tiles.clone(), &metadata, &options, &mut sink).unwrap();
+        let buffered = encode_pmtiles_with_options(tiles, &metadata, &options).unwrap();
+        
+        // Header, directory, metadata and one chunk per tile
+        assert_eq!(sink.0.len(), 6);
+        assert_eq!(sink.0[0].len(), 127);
+        assert_eq!(sink.0.concat(), buffered);
+    }
+    
+    /// Read every addressed tile of an archive through its root directory as `(tile_id, data offset, data)`
+    fn read_tiles(archive: &[u8]) -> Vec<(u64, usize, Vec<u8>)> {
+        use std::io::Read;
+        
+        let field = |at: usize| u64::from_le_bytes(archive[at..at + 8].try_into().unwrap()) as usize;
+        let (dir_offset, dir_length, data_offset) = (field(8), field(16), field(56));
+        let mut dir = Vec::new();
+        flate2::read::GzDecoder::new(&archive[dir_offset..dir_offset + dir_length])
+            .read_to_end(&mut dir)
+            .unwrap();
+        
+        let mut tiles = Vec::new();
+        for entry in decode_directory(&dir).unwrap() {
+            let start = data_offset + entry.offset;
+            let data = &archive[start..start + entry.length as usize];
+            for id in entry.tile_id..entry.tile_id + entry.run_length as u64 {
+                tiles.push((id, entry.offset, data.to_vec()));
+            }
+        }
+        tiles
+    }
+    
+    #[test]
+    fn test_validate_pmtiles() {
+        let metadata = TileMetadata {
+            max_zoom: 1,
+            layer_name: "test".to_string(),
+            feature_count: 3,
+            ..Default::default()
+        };
+        let tiles = vec![
+            (TileCoord::new(1, 1, 0), vec![9; 40]),
+            (TileCoord::new(0, 0, 0), vec![1, 2, 3, 4]),
+            (TileCoord::new(1, 0, 0), vec![5, 6, 7, 8]),
+        ];
+        let archive = encode_pmtiles(tiles.clone(), &metadata).unwrap();
+        assert_eq!(validate_pmtiles(&archive), Ok(()));
+        for options in [
+            TilerOptions { clustered: false, ..Default::default() },
+            TilerOptions { fill_empty_tiles: true, ..Default::default() },
+        ] {
+            let archive = encode_pmtiles_with_options(tiles.clone(), &metadata, &options).unwrap();
+            assert_eq!(validate_pmtiles(&archive), Ok(()));
+        }
+        
+        // Wrong magic, a max zoom without tiles and a tile count that does not match
+        let mut corrupted = archive.clone();
+        corrupted[0] = b'X';
+        corrupted[101] = 2;
+        corrupted[72] = 7;
+        let violations = validate_pmtiles(&corrupted).unwrap_err();
+        assert_eq!(violations.len(), 3, "{:?}", violations);
+        assert!(violations[0].contains("magic"));
+        assert!(violations.iter().any(|v| v.contains("addressed tiles")));
+        assert!(violations.iter().any(|v| v.contains("zoom range 0-2")));
+        
+        // A truncated archive loses its tile data and directory bytes
+        let truncated = &archive[..archive.len() - 10];
+        let violations = validate_pmtiles(truncated).unwrap_err();
+        assert!(violations.iter().any(|v| v.starts_with("tile data")), "{:?}", violations);
+        let mut garbled = archive.clone();
+        garbled[127..137].fill(0xff);
+        assert!(validate_pmtiles(&garbled).unwrap_err().iter().any(|v| v.contains("Root directory")));
+        assert_eq!(validate_pmtiles(b"PMTiles\x03").unwrap_err().len(), 1);
+    }
+    
+    #[test]
+    fn test_fill_empty_tiles() {
+        let metadata = TileMetadata {
+            min_zoom: 3,
+            max_zoom: 3,
+            layer_name: "test".to_string(),
+            bounds: (1.0, 1.0, 59.0, 59.0),
+            center: (30.0, 30.0),
+            center_zoom: 3,
+            feature_count: 1,
+            ..Default::default()
+        };
+        let tiles = vec![(TileCoord::new(3, 4, 2), vec![1, 2, 3])];
+        let options = TilerOptions {
+            tile_compression: TileCompression::None,
+            fill_empty_tiles: true,
+            ..Default::default()
+        };
+        let archive = encode_pmtiles_with_options(tiles, &metadata, &options).unwrap();
+        
+        // Bounds cover x 4..=5, y 2..=3 at zoom 3; all are addressed
+        let read = read_tiles(&archive);
+        let mut expected: Vec<u64> = (4..=5)
+            .flat_map(|x| (2..=3).map(move |y| coord_to_tile_id(3, x, y).unwrap()))
+            .collect();
+        expected.sort_unstable();
+        assert_eq!(read.iter().map(|t| t.0).collect::<Vec<_>>(), expected);
+        
+        // The gaps all point at one decodable, empty tile stored once
+        let empty: Vec<_> = read.iter().filter(|t| t.2 != [1, 2, 3]).collect();
+        assert_eq!(empty.len(), 3);
+        assert!(empty.iter().all(|t| t.1 == empty[0].1));
+        let tile = <crate::mvt_encoder::vector_tile::Tile as prost::Message>::decode(empty[0].2.as_slice()).unwrap();
+        assert_eq!(tile.layers.len(), 1);
+        assert!(tile.layers[0].features.is_empty());
+        
+        let count = |at: usize| u64::from_le_bytes(archive[at..at + 8].try_into().unwrap());
+        assert_eq!((count(72), count(88)), (4, 2));
+        assert!(count(80) < 4);
+        let data_length = count(64) as usize;
+        assert_eq!(data_length, 3 + empty[0].2.len());
+        
+        // Without the option, only the real tile is addressed
+        let plain = TilerOptions { fill_empty_tiles: false, ..options.clone() };
+        let tiles = vec![(TileCoord::new(3, 4, 2), vec![1, 2, 3])];
+        let archive = encode_pmtiles_with_options(tiles, &metadata, &plain).unwrap();
+        assert_eq!(read_tiles(&archive).len(), 1);
+        
+        // Gaps in the world at zoom 12 would be ~16.7M addressed tiles
+        let world = TileMetadata { min_zoom: 12, max_zoom: 12, bounds: (-180.0, -85.0, 180.0, 85.0), ..metadata };
+        let tiles = vec![(TileCoord::new(12, 0, 0), vec![1, 2, 3])];
+        let result = encode_pmtiles_with_options(tiles, &world, &options);
+        assert!(matches!(result, Err(ConverterError::TooLarge(_))));
+        
+        // Up to the limit, the directory stays compact: each Hilbert-contiguous gap is one run
+        let wide = TileMetadata { min_zoom: 8, max_zoom: 8, bounds: (-180.0, -85.0, 180.0, 85.0), ..world };
+        let tiles = vec![(TileCoord::new(8, 0, 0), vec![1, 2, 3])];
+        let archive = encode_pmtiles_with_options(tiles, &wide, &options).unwrap();
+        let count = |at: usize| u64::from_le_bytes(archive[at..at + 8].try_into().unwrap());
+        assert_eq!((count(72), count(88)), (1 << 16, 2));
+        assert!(count(80) <= 3);
+        assert!(validate_pmtiles(&archive).is_ok());
+    }
+    
+    #[test]
+    fn test_non_clustered_keeps_input_order() {
+        let tiles = vec![
+            (TileCoord::new(1, 1, 0), vec![9; 40]),
+            (TileCoord::new(0, 0, 0), vec![1, 2, 3, 4]),
+            (TileCoord::new(1, 0, 0), vec![5, 6, 7, 8]),
+        ];
+        let metadata = TileMetadata {
+            max_zoom: 1,
+            layer_name: "test".to_string(),
+            feature_count: 3,
+            ..Default::default()
+        };
+        let mut options = TilerOptions {
+            tile_compression: TileCompression::None,
+            ..Default::default()
+        };
+        
+        let clustered = encode_pmtiles_with_options(tiles.clone(), &metadata, &options).unwrap();
+        assert_eq!(clustered[96], 1);
+        let offsets: Vec<usize> = read_tiles(&clustered).iter().map(|t| t.1).collect();
+        assert_eq!(offsets, vec![0, 4, 8]);
+        
+        options.clustered = false;
+        let archive = encode_pmtiles_with_options(tiles.clone(), &metadata, &options).unwrap();
+        assert_eq!(archive[96], 0);
+        assert_eq!(archive.len(), clustered.len());
+        
+        // The directory is still sorted by tile id, but offsets point into the data in input order
+        let read = read_tiles(&archive);
+        let ids: Vec<u64> = read.iter().map(|t| t.0).collect();
+        assert_eq!(ids, vec![0, 1, 4]);
+        let offsets: Vec<usize> = read.iter().map(|t| t.1).collect();
+        assert_eq!(offsets, vec![40, 44, 0]);
+        for (coord, data) in &tiles {
+            let id = coord_to_tile_id(coord.z, coord.x, coord.y).unwrap();
+            assert_eq!(&read.iter().find(|t| t.0 == id).unwrap().2, data);
+        }
+    }
+    
+    #[test]
+    fn test_directory_is_deterministic() {
+        let tiles = vec![
+            (TileCoord::new(2, 1, 1), vec![7; 10]),
+            (TileCoord::new(2, 2, 1), vec![1, 2, 3]),
+            (TileCoord::new(2, 1, 2), vec![1, 2, 3]),
+            (TileCoord::new(0, 0, 0), vec![4, 5]),
+        ];
+        let metadata = TileMetadata {
+            max_zoom: 2,
+            layer_name: "test".to_string(),
+            bounds: (-90.0, -60.0, 90.0, 60.0),
+            feature_count: 4,
+            ..Default::default()
+        };
+        let options = TilerOptions {
+            tile_compression: TileCompression::None,
+            fill_empty_tiles: true,
+            ..Default::default()
+        };
+        
+        // Same tiles, in any input order, give byte-identical clustered archives
+        let archive = encode_pmtiles_with_options(tiles.clone(), &metadata, &options).unwrap();
+        assert_eq!(encode_pmtiles_with_options(tiles.clone(), &metadata, &options).unwrap(), archive);
+        let reversed: Vec<_> = tiles.iter().rev().cloned().collect();
+        assert_eq!(encode_pmtiles_with_options(reversed, &metadata, &options).unwrap(), archive);
+        
+        // Tile ids are unique per archive
+        let mut duplicated = tiles;
+        duplicated.push((TileCoord::new(2, 2, 1), vec![9]));
+        let result = encode_pmtiles_with_options(duplicated, &metadata, &options);
+        assert!(matches!(result, Err(ConverterError::InvalidInput(m)) if m.contains("2/2/1")));
+    }
+    
+    #[test]
+    fn test_compression_level_per_zoom() {
+        let tile = b"repetitive tile data ".repeat(20);
+        let tiles = vec![(TileCoord::new(0, 0, 0), tile.clone()), (TileCoord::new(5, 3, 4), tile.clone())];
+        let metadata = TileMetadata {
+            max_zoom: 5,
+            layer_name: "test".to_string(),
+            feature_count: 2,
+            ..Default::default()
+        };
+        let mut options = TilerOptions {
+            tile_compression_levels: vec![
+                ZoomCompressionLevel { min_zoom: 0, max_zoom: 3, level: 9 },
+                ZoomCompressionLevel { min_zoom: 4, max_zoom: 5, level: 1 },
+            ],
+            ..Default::default()
+        };
+        
+        let archive = encode_pmtiles_with_options(tiles, &metadata, &options).unwrap();
+        assert_eq!(archive[98], TileCompression::Gzip as u8);
+        let read = read_tiles(&archive);
+        // Gzip XFL header byte: 2 for the best compression, 4 for the fastest
+        assert_eq!(read[0].2[8], 2);
+        assert_eq!(read[1].2[8], 4);
+        for (_, _, data) in &read {
+            let mut raw = Vec::new();
+            std::io::Read::read_to_end(&mut flate2::read::GzDecoder::new(data.as_slice()), &mut raw).unwrap();
+            assert_eq!(raw, tile);
+        }
+        
+        options.tile_compression_levels[0].level = 10;
+        let invalid = encode_pmtiles_with_options(vec![(TileCoord::new(0, 0, 0), tile)], &metadata, &options);
+        assert!(matches!(invalid, Err(ConverterError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_json_metadata_is_reproducible() {
+        let metadata = |fields: &[&str]| TileMetadata {
+            max_zoom: 1,
+            layer_name: "test".to_string(),
+            feature_count: 1,
+            fields: fields.iter().map(|name| (name.to_string(), "String".to_string())).collect(),
+            ..Default::default()
+        };
+        let fields = ["zeta", "alpha", "mid", "beta", "omega", "gamma"];
+        let mut reversed = fields;
+        reversed.reverse();
+        
+        let json = generate_json_metadata(&metadata(&fields), None).unwrap();
+        assert_eq!(json, generate_json_metadata(&metadata(&fields), None).unwrap());
+        assert_eq!(json, generate_json_metadata(&metadata(&reversed), None).unwrap());
+        
+        let tilejson = serde_json::Value::Object(build_tilejson(&metadata(&fields), None)).to_string();
+        let positions: Vec<usize> = ["alpha", "beta", "gamma", "mid", "omega", "zeta"]
+            .iter()
+            .map(|name| tilejson.find(&format!("\"{}\":\"String\"", name)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+    
+    #[test]
+    fn test_pack_external_tiles() {
+        let features = vec![crate::tiler::TileFeature {
+            geometry: crate::tiler::TileGeometry::Point(100, 200),
+            properties: serde_json::Map::new(),
+            label: false,
+        }];
+        let mvt = crate::mvt_encoder::encode_tile(&features, "external").unwrap();
+        let gzipped = gzip_tile(&mvt).unwrap();
+        let metadata = TileMetadata {
+            max_zoom: 1,
+            layer_name: "external".to_string(),
+            feature_count: 2,
+            ..Default::default()
+        };
+        let options = TilerOptions {
+            tile_compression: TileCompression::None,
+            ..Default::default()
+        };
+        
+        // One raw and one gzipped tile, as tippecanoe writes them
+        let tiles = vec![(TileCoord::new(1, 1, 1), gzipped), (TileCoord::new(0, 0, 0), mvt.clone())];
+        let archive = pack_tiles(tiles, &metadata, &options, true).unwrap();
+        
+        assert_eq!(&archive[0..8], b"PMTiles\x03");
+        let u64_at = |offset: usize| u64::from_le_bytes(archive[offset..offset + 8].try_into().unwrap());
+        assert_eq!(u64_at(72), 2);
+        // Both tiles are stored raw, sorted by tile id
+        let tile_data_offset = u64_at(56) as usize;
+        assert_eq!(u64_at(64) as usize, 2 * mvt.len());
+        assert_eq!(&archive[tile_data_offset..], [mvt.as_slice(), mvt.as_slice()].concat().as_slice());
+        
+        let invalid = vec![(TileCoord::new(0, 0, 0), vec![0xff, 0xff, 0xff])];
+        assert!(matches!(pack_tiles(invalid, &metadata, &options, true), Err(ConverterError::Parse(_))));
+        let out_of_range = vec![(TileCoord::new(1, 2, 0), mvt)];
+        assert!(matches!(pack_tiles(out_of_range, &metadata, &options, false), Err(ConverterError::Encode(_))));
+    }
+    
+    #[test]
+    fn test_update_pmtiles() {
+        let metadata = TileMetadata {
+            max_zoom: 2,
+            layer_name: "test".to_string(),
+            feature_count: 3,
+            ..Default::default()
+        };
+        let options = TilerOptions::default();
+        let tiles = vec![(TileCoord::new(0, 0, 0), vec![1, 2, 3]), (TileCoord::new(1, 1, 0), vec![4, 5])];
+        let archive = encode_pmtiles_with_options(tiles, &metadata, &options).unwrap();
+        
+        // Append one tile; all three decode (gzipped tile data is decompressed)
+        let appended = update_pmtiles(&archive, vec![(TileCoord::new(2, 3, 1), vec![6])], &[], &metadata, &options).unwrap();
+        assert!(validate_pmtiles(&appended).is_ok());
+        let read = read_pmtiles_tiles(&appended).unwrap();
+        assert_eq!(
+            read,
+            vec![
+                (TileCoord::new(0, 0, 0), vec![1, 2, 3]),
+                (TileCoord::new(1, 1, 0), vec![4, 5]),
+                (TileCoord::new(2, 3, 1), vec![6]),
+            ]
+        );
+        
+        // Replace one tile and remove another
+        let updated = update_pmtiles(
+            &appended,
+            vec![(TileCoord::new(1, 1, 0), vec![7])],
+            &[TileCoord::new(0, 0, 0)],
+            &metadata,
+            &options,
+        )
+        .unwrap();
+        let read = read_pmtiles_tiles(&updated).unwrap();
+        assert_eq!(read, vec![(TileCoord::new(1, 1, 0), vec![7]), (TileCoord::new(2, 3, 1), vec![6])]);
+        
+        assert!(matches!(read_pmtiles_tiles(b"not an archive"), Err(ConverterError::Parse(_))));
+    }
+    
+    #[test]
+    fn test_read_pmtiles_tiles_rejects_malformed() {
+        let metadata = TileMetadata { layer_name: "test".to_string(), ..Default::default() };
+        let tiles = vec![(TileCoord::new(0, 0, 0), vec![1])];
+        let archive = encode_pmtiles_with_options(tiles, &metadata, &TilerOptions::default()).unwrap();
+        // Swap in an uncompressed root directory of (tile id, run length, offset, length)
+        // entries appended after the tile data, which is then read as uncompressed
+        let with_directory = |entries: &[(u64, u64, u64, u64)]| {
+            let mut directory = Vec::new();
+            write_varint(&mut directory, entries.len() as u64);
+            let mut last_tile_id = 0;
+            for &(tile_id, ..) in entries {
+                write_varint(&mut directory, tile_id - last_tile_id);
+                last_tile_id = tile_id;
+            }
+            entries.iter().for_each(|&(_, run_length, ..)| write_varint(&mut directory, run_length));
+            entries.iter().for_each(|&(.., length)| write_varint(&mut directory, length));
+            entries.iter().for_each(|&(_, _, offset, _)| write_varint(&mut directory, offset + 1));
+            let mut bytes = archive.clone();
+            bytes[8..16].copy_from_slice(&(archive.len() as u64).to_le_bytes());
+            bytes[16..24].copy_from_slice(&(directory.len() as u64).to_le_bytes());
+            bytes[97] = 1;
+            bytes[98] = 1;
+            bytes.extend(directory);
+            bytes
+        };
+        
+        let valid = with_directory(&[(0, 5, 0, 1)]);
+        assert_eq!(read_pmtiles_tiles(&valid).unwrap().len(), 5);
+        
+        let mut brotli = valid.clone();
+        brotli[98] = 3;
+        assert!(matches!(read_pmtiles_tiles(&brotli), Err(ConverterError::Parse(_))));
+        
+        let overflowing_offset = with_directory(&[(0, 1, u64::MAX - 1, 2)]);
+        assert!(matches!(read_pmtiles_tiles(&overflowing_offset), Err(ConverterError::Parse(_))));
+        let overflowing_run = with_directory(&[(u64::MAX - 1, 4, 0, 1)]);
+        assert!(matches!(read_pmtiles_tiles(&overflowing_run), Err(ConverterError::Parse(_))));
+        let above_max_zoom = with_directory(&[(coord_to_tile_id(MAX_ZOOM + 1, 0, 0).unwrap(), 1, 0, 1)]);
+        assert!(matches!(read_pmtiles_tiles(&above_max_zoom), Err(ConverterError::Parse(_))));
+        let huge_run = with_directory(&[(0, u32::MAX as u64, 0, 1)]);
+        assert!(matches!(read_pmtiles_tiles(&huge_run), Err(ConverterError::TooLarge(_))));
+    }
+    
+    #[test]
+    fn test_tile_id_to_coord() {
+        for z in 0..=5u8 {
+            for x in 0..1u32 << z {
+                for y in 0..1u32 << z {
+                    assert_eq!(tile_id_to_coord(coord_to_tile_id(z, x, y).unwrap()), TileCoord::new(z, x, y));
+                }
+            }
+        }
+        assert_eq!(tile_id_to_coord(coord_to_tile_id(31, 5, 1 << 30).unwrap()), TileCoord::new(31, 5, 1 << 30));
     }
     
     #[test]
     fn test_coord_to_tile_id() {
-        let id1 = coord_to_tile_id(0, 0, 0);
-        let id2 = coord_to_tile_id(1, 0, 0);
-        let id3 = coord_to_tile_id(1, 1, 0);
-        
-        // Different zoom levels should have different top bytes
-        assert_ne!(id1 >> 56, id2 >> 56);
-        // Same zoom, different coords should have different IDs
-        assert_ne!(id2, id3);
+        let id1 = coord_to_tile_id(0, 0, 0).unwrap();
+        let id2 = coord_to_tile_id(1, 0, 0).unwrap();
+        let id3 = coord_to_tile_id(1, 1, 0).unwrap();
+        
+        // Ids of the PMTiles v3 spec
+        assert_eq!(id1, 0);
+        assert_eq!(id2, 1);
+        assert_eq!(id3, 4);
+        assert_eq!(coord_to_tile_id(1, 0, 1).unwrap(), 2);
+        assert_eq!(coord_to_tile_id(2, 0, 0).unwrap(), 5);
+        assert_eq!(coord_to_tile_id(12, 3423, 1763).unwrap(), 19078479);
+        
+        // High zooms stay ordered by zoom level
+        let last_z30 = coord_to_tile_id(30, (1 << 30) - 1, 0).unwrap();
+        assert!(last_z30 < coord_to_tile_id(31, 0, 0).unwrap());
+    }
+    
+    #[test]
+    fn test_coord_to_tile_id_rejects_out_of_range() {
+        assert!(matches!(coord_to_tile_id(0, 1, 0), Err(ConverterError::Encode(_))));
+        assert!(matches!(coord_to_tile_id(3, 8, 0), Err(ConverterError::Encode(_))));
+        assert!(matches!(coord_to_tile_id(3, 0, 8), Err(ConverterError::Encode(_))));
+        assert!(coord_to_tile_id(32, 0, 0).is_err());
+        
+        let tiles = vec![(TileCoord::new(2, 4, 0), vec![1, 2, 3])];
+        let metadata = TileMetadata {
+            min_zoom: 2,
+            max_zoom: 2,
+            layer_name: "test".to_string(),
+            center_zoom: 2,
+            feature_count: 1,
+            ..Default::default()
+        };
+        assert!(encode_pmtiles(tiles, &metadata).is_err());
     }
 }
 
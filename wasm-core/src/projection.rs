@@ -1,14 +1,100 @@
 // Coordinate projection module
 // WGS84 (lon/lat) → WebMercator (x/y) conversion
 
+use crate::ConverterError;
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::f64::consts::PI;
 
 /// WebMercator projection constants
 const EARTH_RADIUS: f64 = 6378137.0; // Earth radius in meters
 const ORIGIN_SHIFT: f64 = 2.0 * PI * EARTH_RADIUS / 2.0;
 
+/// Latitude limit of the WebMercator square (atan(sinh(PI)) in degrees)
+pub const MAX_LATITUDE: f64 = 85.051_128_779_806_59;
+
+thread_local! {
+    static CACHE: RefCell<Option<ProjectionCache>> = const { RefCell::new(None) };
+}
+
+/// Memoized lon/lat → WebMercator projections
+/// 
+/// Keys are the exact bit patterns of the input, so a cached result is identical to
+/// projecting again. The cache is cleared whenever it reaches `capacity` entries,
+/// which bounds memory while still catching vertices shared by neighboring features.
+pub struct ProjectionCache {
+    capacity: usize,
+    entries: HashMap<(u64, u64), (f64, f64)>,
+}
+
+impl ProjectionCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: HashMap::new(),
+        }
+    }
+    
+    /// Project lon/lat, reusing the result for coordinates seen before
+    pub fn project(&mut self, lon: f64, lat: f64) -> (f64, f64) {
+        let key = (lon.to_bits(), lat.to_bits());
+        if let Some(&projected) = self.entries.get(&key) {
+            return projected;
+        }
+        if self.entries.len() >= self.capacity {
+            self.entries.clear();
+        }
+        let projected = lonlat_to_meters(lon, lat);
+        self.entries.insert(key, projected);
+        projected
+    }
+    
+    /// Number of cached coordinates
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+/// Run `f` with a projection cache of `capacity` entries used by `project_lonlat`
+/// 
+/// The cache lives on the current thread until `f` returns. A capacity of 0 runs `f`
+/// without caching; when a cache is already active (nested calls) it is reused.
+pub fn with_projection_cache<T>(capacity: usize, f: impl FnOnce() -> T) -> T {
+    let installed = capacity > 0
+        && CACHE.with(|cache| {
+            let mut cache = cache.borrow_mut();
+            cache.is_none() && cache.replace(ProjectionCache::new(capacity)).is_none()
+        });
+    let result = f();
+    if installed {
+        CACHE.with(|cache| cache.borrow_mut().take());
+    }
+    result
+}
+
+/// Convert lon/lat (WGS84) to WebMercator meters, rejecting non-finite input
+/// 
+/// Latitude is clamped to ±`MAX_LATITUDE`, so the poles map to the edge of the
+/// projected square instead of infinity. Inside `with_projection_cache`, results are memoized.
+pub fn project_lonlat(lon: f64, lat: f64) -> Result<(f64, f64), ConverterError> {
+    if !lon.is_finite() || !lat.is_finite() {
+        return Err(ConverterError::Projection(format!("Non-finite coordinate: ({}, {})", lon, lat)));
+    }
+    Ok(CACHE.with(|cache| match cache.borrow_mut().as_mut() {
+        Some(cache) => cache.project(lon, lat),
+        None => lonlat_to_meters(lon, lat),
+    }))
+}
+
 /// Convert lon/lat (WGS84) to WebMercator meters
+/// 
+/// Latitude is clamped to ±`MAX_LATITUDE`; use `project_lonlat` to reject NaN/Inf.
 pub fn lonlat_to_meters(lon: f64, lat: f64) -> (f64, f64) {
+    let lat = lat.clamp(-MAX_LATITUDE, MAX_LATITUDE);
     let mx = lon * ORIGIN_SHIFT / 180.0;
     let my = ((90.0 + lat) * PI / 360.0).tan().ln() / (PI / 180.0);
     let my = my * ORIGIN_SHIFT / 180.0;
@@ -37,7 +123,7 @@ pub fn lonlat_to_tile(lon: f64, lat: f64, zoom: u8) -> (u32, u32) {
     let tx = tx_raw.floor() as u32;
     
     // Y coordinate: WebMercator projection
-    let lat_rad = lat * PI / 180.0;
+    let lat_rad = lat.clamp(-MAX_LATITUDE, MAX_LATITUDE) * PI / 180.0;
     let ty_raw = (1.0 - (lat_rad.tan() + (1.0 / lat_rad.cos())).ln() / PI) / 2.0 * n;
     let ty = ty_raw.floor() as u32;
     
@@ -73,10 +159,168 @@ pub fn meters_to_pixel_in_tile(mx: f64, my: f64, tx: u32, ty: u32, zoom: u8) ->
     (px, py)
 }
 
+/// Convert pixel coordinates within tile back to WebMercator meters (inverse of `meters_to_pixel_in_tile`)
+pub fn pixel_in_tile_to_meters(px: f64, py: f64, tx: u32, ty: u32, zoom: u8) -> (f64, f64) {
+    let (tile_min_x, _tile_min_y, _, tile_max_y) = tile_bounds(tx, ty, zoom);
+    let resolution = get_resolution(zoom);
+    
+    (tile_min_x + px * resolution, tile_max_y - py * resolution)
+}
+
+/// Convert WebMercator meters to lon/lat (WGS84)
+pub fn meters_to_lonlat(mx: f64, my: f64) -> (f64, f64) {
+    let lon = mx / ORIGIN_SHIFT * 180.0;
+    let lat = my / ORIGIN_SHIFT * 180.0;
+    let lat = 180.0 / PI * (2.0 * (lat * PI / 180.0).exp().atan() - PI / 2.0);
+    (lon, lat)
+}
+
+/// Convert EPSG:3857 input coordinates (WebMercator meters) to lon/lat
+/// 
+/// Rejects non-finite coordinates and coordinates outside the projected square.
+pub fn unproject_meters(mx: f64, my: f64) -> Result<(f64, f64), ConverterError> {
+    let limit = ORIGIN_SHIFT * (1.0 + 1e-9);
+    if !(mx.abs() <= limit && my.abs() <= limit) {
+        return Err(ConverterError::Projection(format!(
+            "Coordinate ({}, {}) is outside the WebMercator (EPSG:3857) extent",
+            mx, my
+        )));
+    }
+    Ok(meters_to_lonlat(mx, my))
+}
+
+/// Zoom at which `bounds` roughly fills a viewport of one 512px tile
+/// 
+/// Based on the larger of the longitude extent and the WebMercator latitude extent.
+/// Returns `None` for an empty (single point) extent.
+pub fn fit_zoom(bounds: (f64, f64, f64, f64)) -> Option<f64> {
+    let (min_lon, min_lat, max_lon, max_lat) = bounds;
+    let (min_x, min_y) = lonlat_to_meters(min_lon, min_lat);
+    let (max_x, max_y) = lonlat_to_meters(max_lon, max_lat);
+    
+    // Fraction of the world covered by the bounds (the world is 2 * ORIGIN_SHIFT wide)
+    let fraction = ((max_x - min_x).max(max_y - min_y) / (2.0 * ORIGIN_SHIFT)).min(1.0);
+    if fraction <= 0.0 || !fraction.is_finite() {
+        return None;
+    }
+    Some(-fraction.log2())
+}
+
+/// How lon/lat input is converted to the units of a tile grid
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum GridProjection {
+    /// WGS84 lon/lat to WebMercator meters
+    #[default]
+    WebMercator,
+    /// Coordinates are already in grid units (e.g. a local flat CRS); used as-is
+    Identity,
+}
+
+/// Tile grid used to bin features into tiles
+/// 
+/// Tile (0, 0) has its top-left corner at `origin`; columns grow along +X and rows along
+/// -Y. A pixel is `resolution` units wide at zoom 0 and halves with every zoom level.
+/// The default is the standard WebMercator grid (`GridSpec::web_mercator`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct GridSpec {
+    /// Projection of input coordinates to grid units
+    pub projection: GridProjection,
+    /// Top-left corner of tile (0, 0) in grid units
+    pub origin: (f64, f64),
+    /// Pixel size at zoom 0 in grid units
+    pub resolution: f64,
+    /// Tile width and height in pixels
+    pub tile_size: u32,
+}
+
+impl Default for GridSpec {
+    fn default() -> Self {
+        Self::web_mercator()
+    }
+}
+
+impl GridSpec {
+    /// The standard WebMercator grid: one 256px tile covers the world at zoom 0
+    pub fn web_mercator() -> Self {
+        Self {
+            projection: GridProjection::WebMercator,
+            origin: (-ORIGIN_SHIFT, ORIGIN_SHIFT),
+            resolution: get_resolution(0),
+            tile_size: 256,
+        }
+    }
+    
+    fn is_web_mercator(&self) -> bool {
+        *self == Self::web_mercator()
+    }
+    
+    /// Project input coordinates to grid units, rejecting non-finite input
+    pub fn project(&self, x: f64, y: f64) -> Result<(f64, f64), ConverterError> {
+        match self.projection {
+            GridProjection::WebMercator => project_lonlat(x, y),
+            GridProjection::Identity if x.is_finite() && y.is_finite() => Ok((x, y)),
+            GridProjection::Identity => {
+                Err(ConverterError::Projection(format!("Non-finite coordinate: ({}, {})", x, y)))
+            }
+        }
+    }
+    
+    /// Size of one tile at `zoom` in grid units
+    fn tile_span(&self, zoom: u8) -> f64 {
+        self.resolution * self.tile_size as f64 / 2_f64.powi(zoom as i32)
+    }
+    
+    /// Tile containing input coordinates at `zoom` (clamped to the grid)
+    pub fn tile_for(&self, x: f64, y: f64, zoom: u8) -> (u32, u32) {
+        if self.is_web_mercator() {
+            return lonlat_to_tile(x, y, zoom);
+        }
+        let (gx, gy) = match self.projection {
+            GridProjection::WebMercator => lonlat_to_meters(x, y),
+            GridProjection::Identity => (x, y),
+        };
+        let span = self.tile_span(zoom);
+        let last = self.last_tile(zoom) as f64;
+        let tx = ((gx - self.origin.0) / span).floor().clamp(0.0, last);
+        let ty = ((self.origin.1 - gy) / span).floor().clamp(0.0, last);
+        (tx as u32, ty as u32)
+    }
+    
+    /// Highest tile column or row at `zoom`
+    /// 
+    /// The WebMercator grid has 2^zoom tiles per side; custom grids are unbounded.
+    pub fn last_tile(&self, zoom: u8) -> u32 {
+        if self.is_web_mercator() {
+            get_tile_count(zoom) - 1
+        } else {
+            u32::MAX
+        }
+    }
+    
+    /// Position of projected coordinates in pixels from the top-left corner of a tile
+    pub fn pixel_in_tile(&self, gx: f64, gy: f64, tx: u32, ty: u32, zoom: u8) -> (f64, f64) {
+        if self.is_web_mercator() {
+            return meters_to_pixel_in_tile(gx, gy, tx, ty, zoom);
+        }
+        let span = self.tile_span(zoom);
+        let resolution = span / self.tile_size as f64;
+        let tile_min_x = self.origin.0 + tx as f64 * span;
+        let tile_max_y = self.origin.1 - ty as f64 * span;
+        ((gx - tile_min_x) / resolution, (tile_max_y - gy) / resolution)
+    }
+}
+
 /// Get resolution (meters/pixel) at specified zoom level
 fn get_resolution(zoom: u8) -> f64 {
-    let initial_resolution = 2.0 * PI * EARTH_RADIUS / 256.0;
-    initial_resolution / 2_f64.powi(zoom as i32)
+    resolution(zoom, 256)
+}
+
+/// Size of one tile unit in meters at the equator, for tiles of `extent` units per side
+/// 
+/// E.g. about 9.55 m per unit of a 4096 extent tile at zoom 10; use it to convert
+/// ground distances to tile units for simplification tolerances or area filters.
+pub fn resolution(zoom: u8, extent: u32) -> f64 {
+    2.0 * PI * EARTH_RADIUS / extent as f64 / 2_f64.powi(zoom as i32)
 }
 
 /// Get tile count for given zoom level
@@ -88,6 +332,16 @@ pub fn get_tile_count(zoom: u8) -> u32 {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_resolution() {
+        // Published WebMercator resolutions for 256px tiles (meters per pixel)
+        assert!((resolution(0, 256) - 156_543.033_928_041).abs() < 1e-6);
+        assert!((resolution(10, 256) - 152.874_056_570_353_5).abs() < 1e-9);
+        // Finer extents divide the same tile span into more units
+        assert!((resolution(0, 4096) - 9_783.939_620_502_56).abs() < 1e-6);
+        assert!((resolution(10, 4096) * 4096.0 - resolution(10, 256) * 256.0).abs() < 1e-6);
+    }
+    
     #[test]
     fn test_lonlat_to_meters() {
         // Tokyo (139.7671, 35.6812)
@@ -96,6 +350,65 @@ mod tests {
         assert!(my > 4_200_000.0 && my < 4_300_000.0);
     }
 
+    #[test]
+    fn test_project_lonlat_near_limit() {
+        let (_, my) = project_lonlat(0.0, 85.05).unwrap();
+        assert!(my.is_finite());
+        assert!(my < ORIGIN_SHIFT);
+    }
+
+    #[test]
+    fn test_project_lonlat_clamps_pole() {
+        let (mx, my) = project_lonlat(0.0, 90.0).unwrap();
+        assert_eq!(mx, 0.0);
+        assert!((my - ORIGIN_SHIFT).abs() < 1e-3);
+        
+        let (_, my) = project_lonlat(0.0, -90.0).unwrap();
+        assert!((my + ORIGIN_SHIFT).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_project_lonlat_rejects_nan() {
+        assert!(project_lonlat(f64::NAN, 35.0).is_err());
+        assert!(project_lonlat(139.0, f64::INFINITY).is_err());
+    }
+
+    #[test]
+    fn test_projection_cache_bounded() {
+        let mut cache = ProjectionCache::new(3);
+        for lon in [1.0, 2.0, 1.0, 3.0, 2.0] {
+            assert_eq!(cache.project(lon, 10.0), lonlat_to_meters(lon, 10.0));
+        }
+        assert_eq!(cache.len(), 3);
+        // A new coordinate starts over instead of growing past the capacity
+        cache.project(4.0, 10.0);
+        assert_eq!(cache.len(), 1);
+        
+        with_projection_cache(10, || {
+            assert_eq!(project_lonlat(5.0, 6.0).unwrap(), lonlat_to_meters(5.0, 6.0));
+            assert!(project_lonlat(f64::NAN, 6.0).is_err());
+            assert_eq!(CACHE.with(|cache| cache.borrow().as_ref().map(ProjectionCache::len)), Some(1));
+        });
+        assert!(CACHE.with(|cache| cache.borrow().is_none()));
+    }
+
+    #[test]
+    fn test_meters_to_lonlat_roundtrip() {
+        let (mx, my) = lonlat_to_meters(139.7671, 35.6812);
+        let (lon, lat) = meters_to_lonlat(mx, my);
+        assert!((lon - 139.7671).abs() < 1e-9);
+        assert!((lat - 35.6812).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_fit_zoom() {
+        let world = fit_zoom((-180.0, -85.0, 180.0, 85.0)).unwrap();
+        let tokyo = fit_zoom((139.6, 35.6, 139.9, 35.8)).unwrap();
+        assert!(world < 0.1);
+        assert!(tokyo > 9.0 && tokyo < 11.0);
+        assert_eq!(fit_zoom((139.7, 35.6, 139.7, 35.6)), None);
+    }
+
     #[test]
     fn test_lonlat_to_tile() {
         // At zoom level 0, entire world is 1 tile
@@ -104,7 +417,7 @@ mod tests {
         assert_eq!(ty, 0);
         
         // Eastern hemisphere at zoom level 1
-        let (tx, ty) = lonlat_to_tile(90.0, 0.0, 1);
+        let (tx, _ty) = lonlat_to_tile(90.0, 0.0, 1);
         assert_eq!(tx, 1);
     }
 
@@ -115,4 +428,30 @@ mod tests {
         assert_eq!(get_tile_count(2), 4);
         assert_eq!(get_tile_count(5), 32);
     }
+    
+    #[test]
+    fn test_custom_flat_grid() {
+        // 1000 x 1000 unit tiles at zoom 0 (4 px of 250 units), starting at (5000, 9000)
+        let grid = GridSpec {
+            projection: GridProjection::Identity,
+            origin: (5000.0, 9000.0),
+            resolution: 250.0,
+            tile_size: 4,
+        };
+        assert_eq!(grid.tile_for(5500.0, 8500.0, 0), (0, 0));
+        assert_eq!(grid.tile_for(7250.0, 5100.0, 0), (2, 3));
+        // Tiles halve in size with every zoom level
+        assert_eq!(grid.tile_for(7250.0, 5100.0, 1), (4, 7));
+        // Positions before the origin clamp to the first tile
+        assert_eq!(grid.tile_for(0.0, 10000.0, 3), (0, 0));
+        
+        let (px, py) = grid.pixel_in_tile(7250.0, 5100.0, 2, 3, 0);
+        assert!((px - 1.0).abs() < 1e-9 && (py - 3.6).abs() < 1e-9, "{} {}", px, py);
+        assert!(grid.project(f64::NAN, 0.0).is_err());
+        
+        // The default grid matches the WebMercator helpers
+        let default = GridSpec::default();
+        assert_eq!(default.tile_for(139.7671, 35.6812, 10), lonlat_to_tile(139.7671, 35.6812, 10));
+        assert_eq!(default.last_tile(3), 7);
+    }
 }
@@ -0,0 +1,437 @@
+// Shapefile parsing module
+// Read geometry from .shp and attributes from the paired .dbf into features
+
+use crate::geojson_parser::{Feature, GeometryType, ParsedGeoJson};
+use crate::ConverterError;
+use byteorder::{ByteOrder, LittleEndian};
+use geo_types::{Coord, LineString, MultiPolygon, Point, Polygon};
+use serde_json::{Map, Value};
+use shapefile::dbase::{self, FieldValue};
+use shapefile::record::traits::HasXY;
+use shapefile::{PolygonRing, Shape, ShapeReader};
+use std::io::Cursor;
+
+/// The files of one shapefile, as read by `read_shapefile`
+#[derive(Debug, Clone, Copy)]
+pub struct ShapefileSources<'a> {
+    /// Geometry (`.shp`)
+    pub shp: &'a [u8],
+    /// Attributes (`.dbf`)
+    pub dbf: &'a [u8],
+    /// CRS as WKT (`.prj`); WGS84 when absent
+    pub prj: Option<&'a str>,
+    /// Encoding name of the `.dbf` text (`.cpg`); the DBF code page when absent
+    pub cpg: Option<&'a str>,
+}
+
+/// Parse a shapefile (`.shp` geometry, `.dbf` attributes, optional `.prj` CRS) into features
+///
+/// Same as `read_shapefile` without a `.cpg`, keeping only the features.
+pub fn parse_shapefile(shp: &[u8], dbf: &[u8], prj: Option<&str>) -> Result<Vec<Feature>, ConverterError> {
+    Ok(read_shapefile(&ShapefileSources { shp, dbf, prj, cpg: None })?.features)
+}
+
+/// Read a shapefile into features, with null shapes as attribute-only rows
+///
+/// Records are paired with `.dbf` rows by position. Supported shapes are points,
+/// multipoints, polylines and polygons, including their Z and M variants (Z and M are
+/// ignored). A multipoint becomes one feature per point and a polyline one feature per
+/// part, each with the record's attributes.
+///
+/// Text is decoded with the encoding named by the `.cpg` if given, else with the code
+/// page of the DBF header (language driver byte 29), UTF-8 when that is unset.
+/// Coordinates are taken as WGS84 lon/lat when `prj` is absent or geographic; Web
+/// Mercator is converted to lon/lat, other projected CRSs are rejected.
+pub fn read_shapefile(sources: &ShapefileSources) -> Result<ParsedGeoJson, ConverterError> {
+    let to_lonlat = crs_transform(sources.prj)?;
+    let rows = parse_dbf(sources.dbf, sources.cpg)?;
+    
+    let mut reader = ShapeReader::new(Cursor::new(sources.shp))
+        .map_err(|e| ConverterError::Parse(format!("Invalid .shp: {}", e)))?;
+    let mut parsed = ParsedGeoJson::default();
+    for (index, shape) in reader.iter_shapes().enumerate() {
+        let shape = shape.map_err(|e| ConverterError::Parse(format!("Shapefile record {}: {}", index + 1, e)))?;
+        let properties = rows.get(index).cloned().unwrap_or_default();
+        let geometries = shape_geometries(&shape, to_lonlat)?;
+        if matches!(shape, Shape::NullShape) {
+            parsed.attribute_only.push(properties);
+            continue;
+        }
+        for geometry in geometries {
+            parsed.features.push(Feature {
+                geometry,
+                properties: properties.clone(),
+            });
+        }
+    }
+    
+    if parsed.features.is_empty() {
+        return Err(ConverterError::EmptyInput("No valid features found".to_string()));
+    }
+    Ok(parsed)
+}
+
+type Transform = fn(f64, f64) -> (f64, f64);
+
+/// Coordinate conversion to lon/lat for the CRS described by a `.prj` (WKT)
+fn crs_transform(prj: Option<&str>) -> Result<Transform, ConverterError> {
+    let Some(wkt) = prj.map(|wkt| wkt.trim_start_matches('\u{feff}').trim()).filter(|wkt| !wkt.is_empty()) else {
+        return Ok(|x, y| (x, y));
+    };
+    let upper = wkt.to_uppercase();
+    if upper.starts_with("GEOGCS") {
+        return Ok(|x, y| (x, y));
+    }
+    let web_mercator = ["AUXILIARY_SPHERE", "PSEUDO-MERCATOR", "PSEUDO_MERCATOR", "WEB_MERCATOR", "3857"];
+    if upper.starts_with("PROJCS") && web_mercator.iter().any(|name| upper.contains(name)) {
+        return Ok(crate::projection::meters_to_lonlat);
+    }
+    Err(ConverterError::InvalidInput(format!(
+        "Unsupported shapefile CRS (only WGS84 and Web Mercator are supported): {}",
+        wkt.chars().take(80).collect::<String>()
+    )))
+}
+
+/// Geometries of one shape (none for a null shape)
+fn shape_geometries(shape: &Shape, to_lonlat: Transform) -> Result<Vec<GeometryType>, ConverterError> {
+    let coord = |point: &dyn HasXY| {
+        let (x, y) = to_lonlat(point.x(), point.y());
+        Coord { x, y }
+    };
+    let points = |points: Vec<Coord<f64>>| points.into_iter().map(|c| GeometryType::Point(Point(c))).collect();
+    
+    Ok(match shape {
+        Shape::NullShape => Vec::new(),
+        Shape::Point(p) => vec![GeometryType::Point(Point(coord(p)))],
+        Shape::PointM(p) => vec![GeometryType::Point(Point(coord(p)))],
+        Shape::PointZ(p) => vec![GeometryType::Point(Point(coord(p)))],
+        Shape::Multipoint(m) => points(m.points().iter().map(|p| coord(p)).collect()),
+        Shape::MultipointM(m) => points(m.points().iter().map(|p| coord(p)).collect()),
+        Shape::MultipointZ(m) => points(m.points().iter().map(|p| coord(p)).collect()),
+        Shape::Polyline(l) => lines(l.parts(), coord),
+        Shape::PolylineM(l) => lines(l.parts(), coord),
+        Shape::PolylineZ(l) => lines(l.parts(), coord),
+        Shape::Polygon(p) => polygons(p.rings(), coord),
+        Shape::PolygonM(p) => polygons(p.rings(), coord),
+        Shape::PolygonZ(p) => polygons(p.rings(), coord),
+        Shape::Multipatch(_) => {
+            return Err(ConverterError::Parse("Unsupported shapefile shape type Multipatch".to_string()));
+        }
+    })
+}
+
+/// One line string per polyline part with at least two points
+fn lines<P: HasXY>(parts: &[Vec<P>], coord: impl Fn(&dyn HasXY) -> Coord<f64>) -> Vec<GeometryType> {
+    parts
+        .iter()
+        .filter(|part| part.len() >= 2)
+        .map(|part| GeometryType::LineString(part.iter().map(|p| coord(p)).collect()))
+        .collect()
+}
+
+/// Polygons from shapefile rings, grouped by containment: outer rings (clockwise in
+/// shapefiles) contain their holes
+fn polygons<P: HasXY>(rings: &[PolygonRing<P>], coord: impl Fn(&dyn HasXY) -> Coord<f64>) -> Vec<GeometryType> {
+    let polygons = rings
+        .iter()
+        .map(|ring| ring.points())
+        .filter(|ring| ring.len() >= 4)
+        .map(|ring| Polygon::new(ring.iter().map(|p| coord(p)).collect::<LineString<f64>>(), Vec::new()))
+        .collect::<Vec<_>>();
+    if polygons.is_empty() {
+        return Vec::new();
+    }
+    let rings = GeometryType::MultiPolygon(MultiPolygon::new(polygons));
+    crate::tiler::regroup_rings_by_containment(&rings).into_iter().collect()
+}
+
+/// Parse a dBASE table into one property map per record
+///
+/// Deleted records are kept as empty maps so rows stay aligned with `.shp` records.
+/// The header is checked before it is handed to `dbase`, which indexes the first byte
+/// of each record.
+fn parse_dbf(dbf: &[u8], cpg: Option<&str>) -> Result<Vec<Map<String, Value>>, ConverterError> {
+    let header_length = dbf.get(8..10).map(LittleEndian::read_u16);
+    let record_length = dbf.get(10..12).map(LittleEndian::read_u16);
+    match (header_length, record_length) {
+        (Some(header_length), Some(record_length)) if header_length >= 33 && record_length >= 1 => {}
+        _ => return Err(ConverterError::Parse("DBF header is truncated or invalid".to_string())),
+    }
+    let dbf_error = |e: dbase::Error| ConverterError::Parse(format!("Invalid .dbf: {}", e));
+    
+    let source = Cursor::new(dbf);
+    let mut table = match cpg.map(|name| name.trim_start_matches('\u{feff}').trim()).filter(|name| !name.is_empty()) {
+        Some(name) => {
+            let encoding = dbase::encoding::DynEncoding::from_name(name)
+                .ok_or_else(|| ConverterError::InvalidInput(format!("Unsupported .cpg encoding: {}", name)))?;
+            dbase::File::open_with_encoding(source, encoding).map_err(dbf_error)?
+        }
+        None => dbase::File::open(source).map_err(dbf_error)?,
+    };
+    // Decimal places are not exposed by `dbase`; they sit at byte 17 of each descriptor
+    let integral: Vec<bool> = (0..table.fields().len()).map(|i| dbf.get(32 + i * 32 + 17) == Some(&0)).collect();
+    let names: Vec<String> = table.fields().iter().map(|field| field.name().trim().to_string()).collect();
+    
+    let mut rows = Vec::with_capacity(table.num_records().min(dbf.len()));
+    for index in 0..table.num_records() {
+        let mut properties = Map::new();
+        let mut record = table.record(index).ok_or_else(|| ConverterError::Parse(format!("DBF record {} is missing", index + 1)))?;
+        if !record.is_deleted().map_err(dbf_error)? {
+            for (field, name) in names.iter().enumerate() {
+                let value = record.read_field(dbase::FieldIndex(field)).map_err(dbf_error)?;
+                properties.insert(name.clone(), dbf_value(value, integral[field]));
+            }
+        }
+        rows.push(properties);
+    }
+    Ok(rows)
+}
+
+/// Convert a DBF cell to a typed JSON value (blank cells become null)
+///
+/// Numbers of fields without decimal places become integers when they are whole.
+fn dbf_value(value: FieldValue, integral: bool) -> Value {
+    let number = |n: f64| {
+        if integral && n.fract() == 0.0 && n.abs() < 9_007_199_254_740_992.0 {
+            Value::from(n as i64)
+        } else {
+            serde_json::Number::from_f64(n).map(Value::Number).unwrap_or(Value::Null)
+        }
+    };
+    match value {
+        FieldValue::Character(Some(text)) | FieldValue::Memo(text) => {
+            let text = text.trim_matches(|c: char| c.is_whitespace() || c == '\0');
+            if text.is_empty() {
+                Value::Null
+            } else {
+                Value::String(text.to_string())
+            }
+        }
+        FieldValue::Numeric(Some(n)) | FieldValue::Currency(n) | FieldValue::Double(n) => number(n),
+        FieldValue::Float(Some(n)) => number(n as f64),
+        FieldValue::Integer(n) => Value::from(n),
+        FieldValue::Logical(Some(b)) => Value::Bool(b),
+        FieldValue::Date(Some(date)) => {
+            Value::String(format!("{:04}-{:02}-{:02}", date.year(), date.month(), date.day()))
+        }
+        FieldValue::DateTime(datetime) => {
+            let (date, time) = (datetime.date(), datetime.time());
+            Value::String(format!(
+                "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}",
+                date.year(),
+                date.month(),
+                date.day(),
+                time.hours(),
+                time.minutes(),
+                time.seconds()
+            ))
+        }
+        FieldValue::Character(None)
+        | FieldValue::Numeric(None)
+        | FieldValue::Float(None)
+        | FieldValue::Logical(None)
+        | FieldValue::Date(None) => Value::Null,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use byteorder::{BigEndian, WriteBytesExt};
+    
+    /// Point shapefile with the given coordinates
+    fn point_shp(points: &[(f64, f64)]) -> Vec<u8> {
+        let mut shp = vec![0u8; 100];
+        BigEndian::write_i32(&mut shp[0..4], 9994);
+        BigEndian::write_i32(&mut shp[24..28], (100 + points.len() * 28) as i32 / 2);
+        LittleEndian::write_i32(&mut shp[28..32], 1000);
+        LittleEndian::write_i32(&mut shp[32..36], 1);
+        for (i, (x, y)) in points.iter().enumerate() {
+            shp.write_i32::<BigEndian>(i as i32 + 1).unwrap();
+            shp.write_i32::<BigEndian>(10).unwrap();
+            shp.write_i32::<LittleEndian>(1).unwrap();
+            shp.write_f64::<LittleEndian>(*x).unwrap();
+            shp.write_f64::<LittleEndian>(*y).unwrap();
+        }
+        shp
+    }
+    
+    /// DBF table with fields `(name, type, length, decimals)` and pre-padded records
+    fn dbf(fields: &[(&str, u8, u8, u8)], records: &[&str]) -> Vec<u8> {
+        dbf_bytes(fields, &records.iter().map(|r| r.as_bytes()).collect::<Vec<_>>(), 0)
+    }
+    
+    /// DBF table with raw records and the given code page (language driver) byte
+    fn dbf_bytes(fields: &[(&str, u8, u8, u8)], records: &[&[u8]], code_page: u8) -> Vec<u8> {
+        let header_length = 32 + fields.len() * 32 + 1;
+        let record_length = 1 + fields.iter().map(|f| f.2 as usize).sum::<usize>();
+        let mut dbf = vec![0u8; 32];
+        dbf[0] = 3;
+        dbf[1..4].copy_from_slice(&[124, 1, 1]);
+        dbf[29] = code_page;
+        LittleEndian::write_u32(&mut dbf[4..8], records.len() as u32);
+        LittleEndian::write_u16(&mut dbf[8..10], header_length as u16);
+        LittleEndian::write_u16(&mut dbf[10..12], record_length as u16);
+        for (name, kind, length, decimals) in fields {
+            let mut descriptor = [0u8; 32];
+            descriptor[..name.len()].copy_from_slice(name.as_bytes());
+            descriptor[11] = *kind;
+            descriptor[16] = *length;
+            descriptor[17] = *decimals;
+            dbf.extend_from_slice(&descriptor);
+        }
+        dbf.push(0x0d);
+        for record in records {
+            assert_eq!(record.len(), record_length);
+            dbf.extend_from_slice(record);
+        }
+        dbf.push(0x1a);
+        dbf
+    }
+    
+    #[test]
+    fn test_parse_point_shapefile() {
+        let shp = point_shp(&[(139.7671, 35.6812), (135.5023, 34.6937)]);
+        let dbf = dbf(
+            &[("NAME", b'C', 8, 0), ("POP", b'N', 8, 0), ("AREA", b'N', 6, 2), ("CAPITAL", b'L', 1, 0)],
+            &[" Tokyo   139600002194.0T", " Osaka    2750000 225.2F"],
+        );
+        
+        let features = parse_shapefile(&shp, &dbf, None).unwrap();
+        assert_eq!(features.len(), 2);
+        let GeometryType::Point(point) = &features[0].geometry else {
+            panic!("Point expected");
+        };
+        assert_eq!((point.x(), point.y()), (139.7671, 35.6812));
+        assert_eq!(features[0].properties["NAME"], "Tokyo");
+        assert_eq!(features[0].properties["POP"], 13960000);
+        assert_eq!(features[0].properties["AREA"], 2194.0);
+        assert_eq!(features[0].properties["CAPITAL"], true);
+        assert_eq!(features[1].properties["AREA"], 225.2);
+        assert_eq!(features[1].properties["CAPITAL"], false);
+    }
+    
+    #[test]
+    fn test_parse_shapefile_crs() {
+        let (mx, my) = crate::projection::lonlat_to_meters(139.7671, 35.6812);
+        let shp = point_shp(&[(mx, my)]);
+        let dbf = dbf(&[("ID", b'N', 4, 0)], &["    1"]);
+        
+        let prj = r#"PROJCS["WGS_1984_Web_Mercator_Auxiliary_Sphere",GEOGCS["GCS_WGS_1984"]]"#;
+        let features = parse_shapefile(&shp, &dbf, Some(prj)).unwrap();
+        let GeometryType::Point(point) = &features[0].geometry else {
+            panic!("Point expected");
+        };
+        assert!((point.x() - 139.7671).abs() < 1e-9);
+        assert!((point.y() - 35.6812).abs() < 1e-9);
+        
+        let utm = r#"PROJCS["WGS_1984_UTM_Zone_54N",GEOGCS["GCS_WGS_1984"]]"#;
+        assert!(matches!(parse_shapefile(&shp, &dbf, Some(utm)), Err(ConverterError::InvalidInput(_))));
+    }
+    
+    /// Shapefile with one record of the given content
+    fn single_record_shp(shape_type: i32, content: &[u8]) -> Vec<u8> {
+        let mut shp = vec![0u8; 100];
+        BigEndian::write_i32(&mut shp[0..4], 9994);
+        BigEndian::write_i32(&mut shp[24..28], (108 + content.len()) as i32 / 2);
+        LittleEndian::write_i32(&mut shp[28..32], 1000);
+        LittleEndian::write_i32(&mut shp[32..36], shape_type);
+        shp.write_i32::<BigEndian>(1).unwrap();
+        shp.write_i32::<BigEndian>(content.len() as i32 / 2).unwrap();
+        shp.extend_from_slice(content);
+        shp
+    }
+    
+    #[test]
+    fn test_parse_shapefile_malformed() {
+        let dbf = dbf(&[("ID", b'N', 4, 0)], &["    1"]);
+        
+        // Negative content length
+        let mut shp = point_shp(&[(1.0, 2.0)]);
+        BigEndian::write_i32(&mut shp[104..108], -8);
+        assert!(matches!(parse_shapefile(&shp, &dbf, None), Err(ConverterError::Parse(_))));
+        
+        // Polygon claiming i32::MAX parts
+        let mut polygon = Vec::new();
+        polygon.write_i32::<LittleEndian>(5).unwrap();
+        polygon.extend_from_slice(&[0u8; 32]);
+        polygon.write_i32::<LittleEndian>(i32::MAX).unwrap();
+        polygon.write_i32::<LittleEndian>(4).unwrap();
+        polygon.write_i32::<LittleEndian>(0).unwrap();
+        let shp = single_record_shp(5, &polygon);
+        assert!(matches!(parse_shapefile(&shp, &dbf, None), Err(ConverterError::Parse(_))));
+        
+        // Truncated shapefile and DBF with zero-length records
+        let shp = point_shp(&[(1.0, 2.0)]);
+        assert!(matches!(parse_shapefile(&shp[..120], &dbf, None), Err(ConverterError::Parse(_))));
+        let mut empty_records = dbf.clone();
+        empty_records[10..12].fill(0);
+        assert!(matches!(parse_shapefile(&shp, &empty_records, None), Err(ConverterError::Parse(_))));
+        assert!(matches!(parse_shapefile(&shp, &dbf[..20], None), Err(ConverterError::Parse(_))));
+        assert!(matches!(parse_shapefile(b"not a shapefile", &dbf, None), Err(ConverterError::Parse(_))));
+    }
+    
+    #[test]
+    fn test_parse_shapefile_encodings() {
+        let shp = point_shp(&[(139.7671, 35.6812)]);
+        let fields = [("NAME", b'C', 8, 0)];
+        let read = |dbf: &[u8], cpg: Option<&str>| {
+            let sources = ShapefileSources { shp: &shp, dbf, prj: None, cpg };
+            read_shapefile(&sources).map(|parsed| parsed.features[0].properties["NAME"].clone())
+        };
+        
+        // "東京" in Shift_JIS, marked by the DBF code page or named by the .cpg
+        let shift_jis: &[u8] = b" \x93\x8c\x8b\x9e    ";
+        assert_eq!(read(&dbf_bytes(&fields, &[shift_jis], 0x13), None).unwrap(), "東京");
+        assert_eq!(read(&dbf_bytes(&fields, &[shift_jis], 0), Some("SHIFT_JIS\n")).unwrap(), "東京");
+        
+        // "Zürich" in Windows-1252, and in UTF-8 when no code page is set
+        let latin: &[u8] = b" Z\xfcrich  ";
+        assert_eq!(read(&dbf_bytes(&fields, &[latin], 0x03), None).unwrap(), "Zürich");
+        assert_eq!(read(&dbf_bytes(&fields, &[latin], 0), Some("1252")).unwrap(), "Zürich");
+        assert_eq!(read(&dbf_bytes(&fields, &[" Zürich ".as_bytes()], 0), None).unwrap(), "Zürich");
+        
+        assert!(matches!(read(&dbf_bytes(&fields, &[latin], 0), Some("KLINGON")), Err(ConverterError::InvalidInput(_))));
+    }
+    
+    #[test]
+    fn test_parse_shapefile_null_and_deleted() {
+        // A null shape between two points; the second DBF row is deleted
+        let mut shp = point_shp(&[(1.0, 2.0)]);
+        shp.write_i32::<BigEndian>(2).unwrap();
+        shp.write_i32::<BigEndian>(2).unwrap();
+        shp.write_i32::<LittleEndian>(0).unwrap();
+        shp.write_i32::<BigEndian>(3).unwrap();
+        shp.write_i32::<BigEndian>(10).unwrap();
+        shp.write_i32::<LittleEndian>(1).unwrap();
+        shp.write_f64::<LittleEndian>(3.0).unwrap();
+        shp.write_f64::<LittleEndian>(4.0).unwrap();
+        let length = shp.len() as i32 / 2;
+        BigEndian::write_i32(&mut shp[24..28], length);
+        let dbf = dbf(&[("ID", b'N', 4, 0)], &["    1", "*   2", "    3"]);
+        
+        let parsed = read_shapefile(&ShapefileSources { shp: &shp, dbf: &dbf, prj: None, cpg: None }).unwrap();
+        assert_eq!(parsed.features.len(), 2);
+        assert_eq!(parsed.features[0].properties["ID"], 1);
+        assert_eq!(parsed.features[1].properties["ID"], 3);
+        assert_eq!(parsed.attribute_only, vec![Map::new()]);
+    }
+    
+    #[test]
+    fn test_generate_from_shapefile() {
+        let shp = point_shp(&[(139.7671, 35.6812), (135.5023, 34.6937)]);
+        let dbf = dbf(&[("NAME", b'C', 8, 0)], &[" Tokyo   ", " Osaka   "]);
+        let sources = ShapefileSources { shp: &shp, dbf: &dbf, prj: None, cpg: None };
+        let options = crate::TilerOptions::default();
+        
+        let (tiles, metadata) = crate::generate_tiles_from_shapefile(&sources, 0, 4, "cities", &options).unwrap();
+        assert_eq!(metadata.feature_count, 2);
+        assert!(tiles.iter().any(|tile| tile.path == "0/0/0.pbf"));
+        let archive = crate::generate_pmtiles_from_shapefile(&sources, 0, 4, "cities", &options).unwrap();
+        assert!(crate::pmtiles_encoder::validate_pmtiles(&archive).is_ok());
+        
+        let limited = crate::TilerOptions { max_input_bytes: Some(shp.len()), ..Default::default() };
+        let result = crate::generate_tiles_from_shapefile(&sources, 0, 4, "cities", &limited);
+        assert!(matches!(result, Err(ConverterError::TooLarge(_))));
+    }
+}
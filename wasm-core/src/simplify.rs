@@ -0,0 +1,251 @@
+// Simplification module
+// Douglas–Peucker simplification of tile geometry, optionally preserving shared polygon boundaries
+
+use crate::tiler::{TileFeature, TileGeometry};
+use crate::TilerOptions;
+use std::collections::{HashMap, HashSet};
+
+type GridPoint = (i32, i32);
+
+/// Simplify the geometry of one tile in place according to `options.simplify_tolerance`
+///
+/// Lines and polygon rings are simplified with Douglas–Peucker in grid units. With
+/// `options.preserve_shared_boundaries`, polygon rings are first split at the vertices
+/// where boundaries meet, and each boundary section is simplified once, so polygons
+/// sharing an edge (e.g. neighboring administrative areas) keep an identical edge
+/// instead of opening gaps or overlaps. Nothing happens when the tolerance is 0.
+pub fn simplify_tile(features: &mut [TileFeature], options: &TilerOptions) {
+    let tolerance = options.simplify_tolerance;
+    if tolerance <= 0.0 {
+        return;
+    }
+    
+    let nodes = if options.preserve_shared_boundaries {
+        Some(boundary_nodes(features))
+    } else {
+        None
+    };
+    // Boundary sections already simplified in this tile, keyed by their canonical form
+    let mut sections: HashMap<Vec<GridPoint>, Vec<GridPoint>> = HashMap::new();
+    
+    for feature in features.iter_mut() {
+        let mut simplify_ring = |ring: &mut Vec<GridPoint>| match &nodes {
+            Some(nodes) => *ring = simplify_ring_shared(ring, tolerance, nodes, &mut sections),
+            None => *ring = douglas_peucker(ring, tolerance),
+        };
+        
+        match &mut feature.geometry {
+            TileGeometry::Point(..) => {}
+            TileGeometry::LineString(coords) => *coords = douglas_peucker(coords, tolerance),
+            TileGeometry::Polygon(rings) => rings.iter_mut().for_each(&mut simplify_ring),
+            TileGeometry::MultiPolygon(polygons) => {
+                polygons.iter_mut().flatten().for_each(&mut simplify_ring)
+            }
+        }
+    }
+}
+
+/// Vertices where polygon boundaries meet (degree other than 2 in the edge graph of the tile)
+fn boundary_nodes(features: &[TileFeature]) -> HashSet<GridPoint> {
+    let mut edges: HashSet<(GridPoint, GridPoint)> = HashSet::new();
+    for ring in features.iter().flat_map(|feature| polygon_rings(&feature.geometry)) {
+        for pair in ring.windows(2) {
+            if pair[0] != pair[1] {
+                edges.insert((pair[0].min(pair[1]), pair[0].max(pair[1])));
+            }
+        }
+    }
+    
+    let mut degree: HashMap<GridPoint, usize> = HashMap::new();
+    for (a, b) in edges {
+        *degree.entry(a).or_default() += 1;
+        *degree.entry(b).or_default() += 1;
+    }
+    degree.into_iter().filter(|&(_, d)| d != 2).map(|(point, _)| point).collect()
+}
+
+fn polygon_rings(geometry: &TileGeometry) -> Vec<&Vec<GridPoint>> {
+    match geometry {
+        TileGeometry::Polygon(rings) => rings.iter().collect(),
+        TileGeometry::MultiPolygon(polygons) => polygons.iter().flatten().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Simplify a closed ring section by section, splitting at boundary nodes
+fn simplify_ring_shared(
+    ring: &[GridPoint],
+    tolerance: f64,
+    nodes: &HashSet<GridPoint>,
+    sections: &mut HashMap<Vec<GridPoint>, Vec<GridPoint>>,
+) -> Vec<GridPoint> {
+    if ring.len() < 4 || ring[0] != ring[ring.len() - 1] {
+        return douglas_peucker(ring, tolerance);
+    }
+    let open = &ring[..ring.len() - 1];
+    
+    // Start at a node; rings without one start at their smallest vertex, which is the
+    // same for every ring tracing the same closed boundary
+    let start = open
+        .iter()
+        .position(|point| nodes.contains(point))
+        .unwrap_or_else(|| (0..open.len()).min_by_key(|&i| open[i]).unwrap());
+    let rotated: Vec<GridPoint> = open[start..].iter().chain(&open[..start]).chain(&open[start..=start]).copied().collect();
+    
+    let mut simplified = vec![rotated[0]];
+    let mut section_start = 0;
+    for i in 1..rotated.len() {
+        if i == rotated.len() - 1 || nodes.contains(&rotated[i]) {
+            let section = &rotated[section_start..=i];
+            let result = simplify_section(section, tolerance, sections);
+            simplified.extend_from_slice(&result[1..]);
+            section_start = i;
+        }
+    }
+    simplified
+}
+
+/// Simplify a boundary section the same way regardless of the direction it is traced in
+fn simplify_section(
+    section: &[GridPoint],
+    tolerance: f64,
+    sections: &mut HashMap<Vec<GridPoint>, Vec<GridPoint>>,
+) -> Vec<GridPoint> {
+    let reversed = section.last() < section.first()
+        || (section.first() == section.last() && section.get(1) > section.get(section.len().saturating_sub(2)));
+    let canonical: Vec<GridPoint> = if reversed {
+        section.iter().rev().copied().collect()
+    } else {
+        section.to_vec()
+    };
+    
+    let mut result = sections
+        .entry(canonical)
+        .or_insert_with_key(|canonical| douglas_peucker(canonical, tolerance))
+        .clone();
+    if reversed {
+        result.reverse();
+    }
+    result
+}
+
+/// Douglas–Peucker simplification keeping both end points
+pub fn douglas_peucker(points: &[GridPoint], tolerance: f64) -> Vec<GridPoint> {
+    if points.len() < 3 {
+        return points.to_vec();
+    }
+    
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[points.len() - 1] = true;
+    
+    let mut stack = vec![(0, points.len() - 1)];
+    while let Some((first, last)) = stack.pop() {
+        let mut max_distance = 0.0;
+        let mut index = first;
+        for i in first + 1..last {
+            let distance = segment_distance(points[i], points[first], points[last]);
+            if distance > max_distance {
+                max_distance = distance;
+                index = i;
+            }
+        }
+        
+        if max_distance > tolerance {
+            keep[index] = true;
+            stack.push((first, index));
+            stack.push((index, last));
+        }
+    }
+    
+    points.iter().zip(keep).filter(|(_, keep)| *keep).map(|(point, _)| *point).collect()
+}
+
+/// Distance from `p` to the segment `a`-`b`
+fn segment_distance(p: GridPoint, a: GridPoint, b: GridPoint) -> f64 {
+    let (px, py) = (p.0 as f64, p.1 as f64);
+    let (ax, ay) = (a.0 as f64, a.1 as f64);
+    let (dx, dy) = (b.0 as f64 - ax, b.1 as f64 - ay);
+    
+    let length_sq = dx * dx + dy * dy;
+    let t = if length_sq == 0.0 {
+        0.0
+    } else {
+        (((px - ax) * dx + (py - ay) * dy) / length_sq).clamp(0.0, 1.0)
+    };
+    (px - (ax + t * dx)).hypot(py - (ay + t * dy))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn polygon(ring: Vec<GridPoint>) -> TileFeature {
+        TileFeature {
+            geometry: TileGeometry::Polygon(vec![ring]),
+            properties: serde_json::Map::new(),
+            label: false,
+        }
+    }
+    
+    fn exterior(feature: &TileFeature) -> &Vec<GridPoint> {
+        match &feature.geometry {
+            TileGeometry::Polygon(rings) => &rings[0],
+            _ => panic!("Polygon expected"),
+        }
+    }
+    
+    /// Vertices of `ring` on the shared edge near x = 100, 0 <= y <= 200, sorted
+    fn shared_vertices(ring: &[GridPoint]) -> Vec<GridPoint> {
+        let mut vertices: Vec<GridPoint> = ring
+            .iter()
+            .filter(|p| (p.0 - 100).abs() <= 10 && (0..=200).contains(&p.1))
+            .copied()
+            .collect();
+        vertices.sort();
+        vertices.dedup();
+        vertices
+    }
+    
+    #[test]
+    fn test_douglas_peucker() {
+        let line = vec![(0, 0), (10, 1), (20, 0), (30, 10), (40, 0)];
+        assert_eq!(douglas_peucker(&line, 2.0), vec![(0, 0), (20, 0), (30, 10), (40, 0)]);
+        assert_eq!(douglas_peucker(&line, 0.5), line);
+    }
+    
+    #[test]
+    fn test_shared_edge_simplifies_identically() {
+        // Wiggly border between a western and an eastern polygon, from (100, 0) to (100, 200)
+        let border = [(100, 0), (103, 30), (97, 60), (104, 90), (96, 120), (102, 150), (99, 180), (100, 200)];
+        
+        // The western polygon has corners at both ends of the border...
+        let mut west = vec![(0, 0)];
+        west.extend(border.iter().copied());
+        west.extend([(0, 200), (0, 0)]);
+        
+        // ...while for the eastern triangle the border is the middle of a longer side
+        let mut east = vec![(100, -100), (300, 100), (100, 300)];
+        east.extend(border.iter().rev().copied());
+        east.push((100, -100));
+        
+        let simplified = |preserve_shared_boundaries| {
+            let mut features = vec![polygon(west.clone()), polygon(east.clone())];
+            let options = TilerOptions {
+                simplify_tolerance: 5.0,
+                preserve_shared_boundaries,
+                ..Default::default()
+            };
+            simplify_tile(&mut features, &options);
+            (shared_vertices(exterior(&features[0])), shared_vertices(exterior(&features[1])))
+        };
+        
+        // Simplified independently, the eastern polygon drops the border's end points
+        let (west_border, east_border) = simplified(false);
+        assert_ne!(west_border, east_border);
+        
+        let (west_border, east_border) = simplified(true);
+        assert_eq!(west_border, east_border);
+        assert_eq!(west_border, vec![(100, 0), (100, 200)]);
+    }
+}
@@ -1,17 +1,23 @@
 // Tile assignment module
 // Assign features to tiles and convert to tile coordinates
 
+use crate::geometry::coord_ring_area;
 use crate::geojson_parser::{Feature, GeometryType};
-use crate::projection::{lonlat_to_tile, lonlat_to_meters, meters_to_pixel_in_tile};
-use crate::TileCoord;
-use std::collections::HashMap;
-use geo_types::{Point, LineString, Polygon};
+use crate::projection::{
+    lonlat_to_meters, meters_to_lonlat, pixel_in_tile_to_meters, GridProjection, GridSpec,
+};
+use crate::{ConverterError, TileCoord, TilerOptions};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use geo_types::{Coord, Point, LineString, Polygon, MultiPolygon};
 
 /// Feature within tile
 #[derive(Debug, Clone)]
 pub struct TileFeature {
     pub geometry: TileGeometry,
     pub properties: serde_json::Map<String, serde_json::Value>,
+    /// Polygon label anchor added by `label_layer`, written to the `<layer>_labels` layer
+    pub label: bool,
 }
 
 /// Geometry within tile (tile coordinate system: 0-4096)
@@ -20,66 +26,374 @@ pub enum TileGeometry {
     Point(i32, i32),
     LineString(Vec<(i32, i32)>),
     Polygon(Vec<Vec<(i32, i32)>>), // Exterior ring + interior rings (holes)
+    MultiPolygon(Vec<Vec<Vec<(i32, i32)>>>), // One ring list per polygon
 }
 
 /// MVT extent (tile coordinate range)
-const EXTENT: i32 = 4096;
+pub(crate) const EXTENT: i32 = 4096;
+
+/// How the rings of input polygons are sorted into exteriors and holes
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RingClassification {
+    /// Trust the input structure: the first ring of each polygon is its exterior (default)
+    #[default]
+    Order,
+    /// Ignore ring order and winding; a ring nested inside an odd number of other rings
+    /// of the same feature is a hole of the smallest ring containing it
+    Containment,
+}
+
+/// Kind of an input geometry, ignoring single vs multi
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GeometryKind {
+    Point,
+    LineString,
+    /// Polygons and multipolygons
+    Polygon,
+}
+
+impl GeometryKind {
+    pub fn of(geometry: &GeometryType) -> Self {
+        match geometry {
+            GeometryType::Point(_) => GeometryKind::Point,
+            GeometryType::LineString(_) => GeometryKind::LineString,
+            GeometryType::Polygon(_) | GeometryType::MultiPolygon(_) => GeometryKind::Polygon,
+        }
+    }
+}
+
+/// Whether a feature passes `options.keep_geometry_type` (always true without it)
+pub fn keeps_geometry_type(geometry: &GeometryType, options: &TilerOptions) -> bool {
+    options.keep_geometry_type.is_none_or(|kind| GeometryKind::of(geometry) == kind)
+}
+
+/// Whether `zoom` lies within the zoom window a feature sets through
+/// `options.min_zoom_property` and `options.max_zoom_property`
+/// 
+/// Values may be numbers or numeric strings; other values do not restrict the feature.
+pub fn in_feature_zoom_range(
+    properties: &serde_json::Map<String, serde_json::Value>,
+    zoom: u8,
+    options: &TilerOptions,
+) -> bool {
+    let limit = |property: &Option<String>| {
+        property.as_ref().and_then(|key| match properties.get(key)? {
+            serde_json::Value::Number(n) => n.as_f64(),
+            serde_json::Value::String(s) => s.trim().parse().ok(),
+            _ => None,
+        })
+    };
+    let zoom = zoom as f64;
+    limit(&options.min_zoom_property).is_none_or(|min_zoom| zoom >= min_zoom)
+        && limit(&options.max_zoom_property).is_none_or(|max_zoom| zoom <= max_zoom)
+}
 
 /// Assign features to tiles
 pub fn tile_features(
     features: &[Feature],
     zoom: u8,
-) -> Result<HashMap<TileCoord, Vec<TileFeature>>, String> {
+) -> Result<HashMap<TileCoord, Vec<TileFeature>>, ConverterError> {
+    tile_features_in(features, zoom, None, &TilerOptions::default(), &mut 0)
+}
+
+/// Assign features to tiles, restricted to the given tile coordinates
+/// 
+/// Tiles outside `only` are never converted, so this is cheap even when a feature
+/// covers a large tile range. `None` assigns features to every tile they touch.
+/// Each finished tile is simplified and clamped according to the options. Lines dropped
+/// by `min_line_length_px` are added to `short_lines`.
+pub fn tile_features_in(
+    features: &[Feature],
+    zoom: u8,
+    only: Option<&HashSet<TileCoord>>,
+    options: &TilerOptions,
+    short_lines: &mut u64,
+) -> Result<HashMap<TileCoord, Vec<TileFeature>>, ConverterError> {
     let mut tiles: HashMap<TileCoord, Vec<TileFeature>> = HashMap::new();
     
-    for (_idx, feature) in features.iter().enumerate() {
-        match &feature.geometry {
-            GeometryType::Point(point) => {
-                tile_point(point, &feature.properties, zoom, &mut tiles)?;
-            }
-            GeometryType::LineString(line) => {
-                tile_linestring(line, &feature.properties, zoom, &mut tiles)?;
-            }
-            GeometryType::Polygon(polygon) => {
-                tile_polygon(polygon, &feature.properties, zoom, &mut tiles)?;
-            }
-        }
+    crate::projection::with_projection_cache(options.projection_cache_size, || {
+        features
+            .iter()
+            .try_for_each(|feature| add_feature_to_tiles(feature, zoom, only, options, &mut tiles, short_lines))
+    })?;
+    
+    for tile_features in tiles.values_mut() {
+        crate::simplify::simplify_tile(tile_features, options);
+        clamp_tile(tile_features, options);
     }
     
     Ok(tiles)
 }
 
-/// Add Point to tile
-fn tile_point(
+/// Assign a single feature to the tiles it touches at `zoom`
+/// 
+/// This is the building block of `tile_features_in`; streaming callers use it to
+/// tile features as they are parsed and call `simplify::simplify_tile` and `clamp_tile`
+/// once a tile is complete. A line dropped by `min_line_length_px` increments `short_lines`.
+/// The feature must already be clipped to `options.clip_mask` (see `clip_features_to_mask`).
+pub fn add_feature_to_tiles(
+    feature: &Feature,
+    zoom: u8,
+    only: Option<&HashSet<TileCoord>>,
+    options: &TilerOptions,
+    tiles: &mut HashMap<TileCoord, Vec<TileFeature>>,
+    short_lines: &mut u64,
+) -> Result<(), ConverterError> {
+    let Some(geometry) = prepared_geometry(feature, zoom, options) else {
+        return Ok(());
+    };
+    if let Some(point) = label_point(&geometry, zoom, options) {
+        add_label_to_tiles(&point, &feature.properties, zoom, only, options, tiles)?;
+    }
+    add_geometry_to_tiles(&geometry, &feature.properties, zoom, only, options, tiles, short_lines)
+}
+
+/// Geometry of a feature as tiled at `zoom`, with rings regrouped as `options` ask
+/// 
+/// `None` when the feature is dropped by `keep_geometry_type` or its zoom window.
+fn prepared_geometry<'a>(feature: &'a Feature, zoom: u8, options: &TilerOptions) -> Option<Cow<'a, GeometryType>> {
+    if !keeps_geometry_type(&feature.geometry, options) || !in_feature_zoom_range(&feature.properties, zoom, options) {
+        return None;
+    }
+    let regrouped = match options.ring_classification {
+        RingClassification::Order => None,
+        RingClassification::Containment => regroup_rings_by_containment(&feature.geometry),
+    };
+    Some(regrouped.map_or(Cow::Borrowed(&feature.geometry), Cow::Owned))
+}
+
+/// Clip features to `options.clip_mask` once, before they are tiled at every zoom
+/// 
+/// Features whose bounds miss the bounds of the mask are dropped without clipping; a
+/// line cut into several parts becomes one feature per part. Rings are regrouped first
+/// with `RingClassification::Containment`. Without a mask, features are returned as is.
+pub fn clip_features_to_mask(features: Vec<Feature>, options: &TilerOptions) -> Vec<Feature> {
+    match &options.clip_mask {
+        Some(mask) => {
+            let mask_bounds = mask_bounds(mask);
+            features.into_iter().flat_map(|feature| clip_feature(feature, mask, mask_bounds, options)).collect()
+        }
+        None => features,
+    }
+}
+
+/// Clip one feature to `options.clip_mask` (see `clip_features_to_mask`)
+pub fn clip_feature_to_mask(feature: Feature, options: &TilerOptions) -> Vec<Feature> {
+    match &options.clip_mask {
+        Some(mask) => clip_feature(feature, mask, mask_bounds(mask), options),
+        None => vec![feature],
+    }
+}
+
+fn clip_feature(
+    feature: Feature,
+    mask: &Polygon<f64>,
+    mask_bounds: (f64, f64, f64, f64),
+    options: &TilerOptions,
+) -> Vec<Feature> {
+    let (min_x, min_y, max_x, max_y) = crate::geojson_parser::feature_bounds(&feature);
+    if min_x > mask_bounds.2 || max_x < mask_bounds.0 || min_y > mask_bounds.3 || max_y < mask_bounds.1 {
+        return Vec::new();
+    }
+    let regrouped = match options.ring_classification {
+        RingClassification::Order => None,
+        RingClassification::Containment => regroup_rings_by_containment(&feature.geometry),
+    };
+    let parts = clip_to_mask(regrouped.as_ref().unwrap_or(&feature.geometry), mask);
+    parts
+        .into_iter()
+        .map(|geometry| Feature {
+            geometry,
+            properties: feature.properties.clone(),
+        })
+        .collect()
+}
+
+/// Bounds of the exterior ring of a mask
+fn mask_bounds(mask: &Polygon<f64>) -> (f64, f64, f64, f64) {
+    mask.exterior().0.iter().fold(crate::geojson_parser::EMPTY_BOUNDS, |(min_x, min_y, max_x, max_y), c| {
+        (min_x.min(c.x), min_y.min(c.y), max_x.max(c.x), max_y.max(c.y))
+    })
+}
+
+/// Label anchor of a polygon: its centroid, within the zooms of `options.label_layer`
+/// 
+/// Centroids outside `options.clip_mask` are left out.
+fn label_point(geometry: &GeometryType, zoom: u8, options: &TilerOptions) -> Option<Point<f64>> {
+    let in_range = options.label_layer.is_some_and(|(min_zoom, max_zoom)| (min_zoom..=max_zoom).contains(&zoom));
+    if !in_range || GeometryKind::of(geometry) != GeometryKind::Polygon {
+        return None;
+    }
+    centroid(geometry).filter(|point| options.clip_mask.as_ref().is_none_or(|mask| inside_mask(point.0, mask)))
+}
+
+/// Add a polygon label anchor (see `label_point`) as a label feature
+fn add_label_to_tiles(
     point: &Point<f64>,
     properties: &serde_json::Map<String, serde_json::Value>,
     zoom: u8,
+    only: Option<&HashSet<TileCoord>>,
+    options: &TilerOptions,
     tiles: &mut HashMap<TileCoord, Vec<TileFeature>>,
-) -> Result<(), String> {
-    let lon = point.x();
-    let lat = point.y();
-    
-    // Get tile coordinates
-    let (tx, ty) = lonlat_to_tile(lon, lat, zoom);
-    
-    // Convert to WebMercator meters
-    let (mx, my) = lonlat_to_meters(lon, lat);
-    
-    // Convert to pixel coordinates within tile
-    let (px, py) = meters_to_pixel_in_tile(mx, my, tx, ty, zoom);
+) -> Result<(), ConverterError> {
+    let mut labels = HashMap::new();
+    tile_geometry(&GeometryType::Point(*point), properties, zoom, only, options, &mut labels)?;
+    for (coord, features) in labels {
+        let features = features.into_iter().map(|feature| TileFeature { label: true, ..feature });
+        tiles.entry(coord).or_default().extend(features);
+    }
+    Ok(())
+}
+
+/// Assign the (regrouped, masked) geometry of a feature to the tiles it touches at `zoom`
+#[allow(clippy::too_many_arguments)]
+fn add_geometry_to_tiles(
+    geometry: &GeometryType,
+    properties: &serde_json::Map<String, serde_json::Value>,
+    zoom: u8,
+    only: Option<&HashSet<TileCoord>>,
+    options: &TilerOptions,
+    tiles: &mut HashMap<TileCoord, Vec<TileFeature>>,
+    short_lines: &mut u64,
+) -> Result<(), ConverterError> {
+    for part in tiled_parts(geometry, zoom, options, short_lines) {
+        tile_geometry(&part, properties, zoom, only, options, tiles)?;
+    }
+    Ok(())
+}
+
+/// Geometries that a (regrouped, masked) geometry is tiled as at `zoom`
+/// 
+/// Below `point_below_zoom`, lines and polygons are represented by their centroid. A line
+/// shorter than `min_line_length_px` has no parts and increments `short_lines`; with
+/// `split_antimeridian`, a geometry crossing ±180° is tiled as its parts on either side.
+fn tiled_parts<'a>(
+    geometry: &'a GeometryType,
+    zoom: u8,
+    options: &TilerOptions,
+    short_lines: &mut u64,
+) -> Vec<Cow<'a, GeometryType>> {
+    if options.point_below_zoom.is_some_and(|threshold| zoom < threshold)
+        && !matches!(geometry, GeometryType::Point(_))
+    {
+        return centroid(geometry).map(|point| Cow::Owned(GeometryType::Point(point))).into_iter().collect();
+    }
     
-    // Convert to MVT extent coordinates (0-4096)
-    let tile_x = ((px / 256.0) * EXTENT as f64) as i32;
-    let tile_y = ((py / 256.0) * EXTENT as f64) as i32;
+    if is_short_line(geometry, zoom, options) {
+        *short_lines += 1;
+        return Vec::new();
+    }
     
-    // Add to tile
-    let coord = TileCoord::new(zoom, tx, ty);
-    let tile_feature = TileFeature {
-        geometry: TileGeometry::Point(tile_x, tile_y),
-        properties: properties.clone(),
+    let split = if options.split_antimeridian {
+        split_antimeridian(geometry)
+    } else {
+        None
     };
+    match split {
+        Some(parts) => parts.into_iter().map(Cow::Owned).collect(),
+        None => vec![Cow::Borrowed(geometry)],
+    }
+}
+
+/// Length of a line in tile pixels at `zoom` on `grid`
+pub fn line_length_px(line: &LineString<f64>, zoom: u8, grid: &GridSpec) -> Result<f64, ConverterError> {
+    let mut length = 0.0;
+    let mut previous: Option<(f64, f64)> = None;
+    for coord in line.coords() {
+        let current = grid.project(coord.x, coord.y)?;
+        if let Some((px, py)) = previous {
+            length += (current.0 - px).hypot(current.1 - py);
+        }
+        previous = Some(current);
+    }
+    Ok(length / grid.resolution * 2_f64.powi(zoom as i32))
+}
+
+/// Whether `geometry` is a LineString shorter than `min_line_length_px` at `zoom`
+/// 
+/// Lines that cannot be projected are not short; tiling reports their error.
+pub fn is_short_line(geometry: &GeometryType, zoom: u8, options: &TilerOptions) -> bool {
+    match geometry {
+        GeometryType::LineString(line) if options.min_line_length_px > 0.0 => {
+            line_length_px(line, zoom, &options.grid).is_ok_and(|length| length < options.min_line_length_px)
+        }
+        _ => false,
+    }
+}
+
+/// Add a single geometry to the tiles it touches
+fn tile_geometry(
+    geometry: &GeometryType,
+    properties: &serde_json::Map<String, serde_json::Value>,
+    zoom: u8,
+    only: Option<&HashSet<TileCoord>>,
+    options: &TilerOptions,
+    tiles: &mut HashMap<TileCoord, Vec<TileFeature>>,
+) -> Result<(), ConverterError> {
+    let ranges = tile_ranges(geometry, zoom, options);
+    let covered = ranges.iter().flat_map(|&(_, range)| covered_tiles(zoom, range, only));
+    match geometry {
+        GeometryType::Point(point) => tile_point(point, properties, zoom, covered, options, tiles),
+        GeometryType::LineString(line) => tile_linestring(line, properties, zoom, covered, options, tiles),
+        GeometryType::Polygon(polygon) => tile_polygon(polygon, properties, zoom, covered, options, tiles),
+        GeometryType::MultiPolygon(multi_polygon) => {
+            tile_multipolygon(multi_polygon, properties, zoom, &ranges, only, options, tiles)
+        }
+    }
+}
+
+/// Tile ranges that `geometry` is placed in at `zoom`, tile buffers included
+/// 
+/// Each multipolygon member has its own range, paired with its index, so widely separated
+/// parts don't fill the tiles in between; other geometries have a single range (index 0).
+/// Empty geometries and members have none.
+fn tile_ranges(geometry: &GeometryType, zoom: u8, options: &TilerOptions) -> Vec<(usize, TileRange)> {
+    let range = |bounds, buffer| tile_range(bounds, zoom, buffer, &options.grid);
+    match geometry {
+        GeometryType::Point(point) => {
+            vec![(0, range((point.x(), point.y(), point.x(), point.y()), options.buffer_points))]
+        }
+        GeometryType::LineString(line) if line.0.is_empty() => Vec::new(),
+        GeometryType::LineString(line) => vec![(0, range(linestring_bounds(line), options.buffer_lines))],
+        GeometryType::Polygon(polygon) if polygon.exterior().0.is_empty() => Vec::new(),
+        GeometryType::Polygon(polygon) => vec![(0, range(polygon_bounds(polygon), options.buffer_polygons))],
+        GeometryType::MultiPolygon(multi_polygon) => multi_polygon
+            .0
+            .iter()
+            .enumerate()
+            .filter(|(_, polygon)| !polygon.exterior().0.is_empty())
+            .map(|(member, polygon)| (member, range(polygon_bounds(polygon), options.buffer_polygons)))
+            .collect(),
+    }
+}
+
+/// Add Point to its tile, and to neighboring tiles whose buffer it lies in
+fn tile_point(
+    point: &Point<f64>,
+    properties: &serde_json::Map<String, serde_json::Value>,
+    zoom: u8,
+    covered: impl Iterator<Item = (u32, u32)>,
+    options: &TilerOptions,
+    tiles: &mut HashMap<TileCoord, Vec<TileFeature>>,
+) -> Result<(), ConverterError> {
+    let lon = point.x();
+    let lat = point.y();
     
-    tiles.entry(coord).or_insert_with(Vec::new).push(tile_feature);
+    for (tx, ty) in covered {
+        // Convert to MVT extent coordinates (0-4096, outside it in the buffer)
+        let (tile_x, tile_y) = lonlat_to_tile_grid(lon, lat, tx, ty, zoom, &options.grid)?;
+        
+        // Add to tile
+        let tile_feature = TileFeature {
+            geometry: TileGeometry::Point(tile_x, tile_y),
+            properties: properties.clone(),
+            label: false,
+        };
+        
+        tiles.entry(TileCoord::new(zoom, tx, ty)).or_default().push(tile_feature);
+    }
     
     Ok(())
 }
@@ -89,43 +403,33 @@ fn tile_linestring(
     line: &LineString<f64>,
     properties: &serde_json::Map<String, serde_json::Value>,
     zoom: u8,
+    covered: impl Iterator<Item = (u32, u32)>,
+    options: &TilerOptions,
     tiles: &mut HashMap<TileCoord, Vec<TileFeature>>,
-) -> Result<(), String> {
-    if line.0.is_empty() {
-        return Ok(());
-    }
-    
-    // Calculate bounding box of LineString
-    let (min_lon, min_lat, max_lon, max_lat) = linestring_bounds(line);
-    
-    // Get range of intersecting tiles
-    let (tx_min, ty_max) = lonlat_to_tile(min_lon, min_lat, zoom);
-    let (tx_max, ty_min) = lonlat_to_tile(max_lon, max_lat, zoom);
-    
+) -> Result<(), ConverterError> {
     // Place LineString in each tile
-    for tx in tx_min..=tx_max {
-        for ty in ty_min..=ty_max {
-            // Convert all coordinates to this tile's coordinate system
-            let mut tile_coords = Vec::new();
-            for coord in &line.0 {
-                let (mx, my) = lonlat_to_meters(coord.x, coord.y);
-                let (px, py) = meters_to_pixel_in_tile(mx, my, tx, ty, zoom);
-                
-                let tile_x = ((px / 256.0) * EXTENT as f64) as i32;
-                let tile_y = ((py / 256.0) * EXTENT as f64) as i32;
-                
-                tile_coords.push((tile_x, tile_y));
-            }
-            
-            // Add to tile
-            let coord = TileCoord::new(zoom, tx, ty);
-            let tile_feature = TileFeature {
-                geometry: TileGeometry::LineString(tile_coords),
-                properties: properties.clone(),
-            };
-            
-            tiles.entry(coord).or_insert_with(Vec::new).push(tile_feature);
+    for (tx, ty) in covered {
+        // Convert all coordinates to this tile's coordinate system
+        let tile_coords = line.0
+            .iter()
+            .map(|coord| lonlat_to_tile_grid(coord.x, coord.y, tx, ty, zoom, &options.grid))
+            .collect::<Result<Vec<_>, _>>()?;
+        let tile_coords = remove_collinear(dedup_consecutive(tile_coords));
+        
+        // A line that collapsed to a single grid cell has no length at this zoom
+        if tile_coords.len() < 2 {
+            continue;
         }
+        
+        // Add to tile
+        let coord = TileCoord::new(zoom, tx, ty);
+        let tile_feature = TileFeature {
+            geometry: TileGeometry::LineString(tile_coords),
+            properties: properties.clone(),
+            label: false,
+        };
+        
+        tiles.entry(coord).or_default().push(tile_feature);
     }
     
     Ok(())
@@ -136,65 +440,1044 @@ fn tile_polygon(
     polygon: &Polygon<f64>,
     properties: &serde_json::Map<String, serde_json::Value>,
     zoom: u8,
+    covered: impl Iterator<Item = (u32, u32)>,
+    options: &TilerOptions,
     tiles: &mut HashMap<TileCoord, Vec<TileFeature>>,
-) -> Result<(), String> {
-    let exterior = polygon.exterior();
-    if exterior.0.is_empty() {
-        return Ok(());
+) -> Result<(), ConverterError> {
+    // Place Polygon in each tile
+    for (tx, ty) in covered {
+        let tile_rings = polygon_to_tile_rings(polygon, tx, ty, zoom, &options.grid)?;
+        
+        // Add to tile
+        let coord = TileCoord::new(zoom, tx, ty);
+        let tile_feature = TileFeature {
+            geometry: TileGeometry::Polygon(tile_rings),
+            properties: properties.clone(),
+            label: false,
+        };
+        
+        tiles.entry(coord).or_default().push(tile_feature);
     }
     
-    // Calculate bounding box of Polygon
-    let (min_lon, min_lat, max_lon, max_lat) = polygon_bounds(polygon);
+    Ok(())
+}
+
+/// Add MultiPolygon to tiles (supports multiple tiles)
+#[allow(clippy::too_many_arguments)]
+fn tile_multipolygon(
+    multi_polygon: &MultiPolygon<f64>,
+    properties: &serde_json::Map<String, serde_json::Value>,
+    zoom: u8,
+    ranges: &[(usize, TileRange)],
+    only: Option<&HashSet<TileCoord>>,
+    options: &TilerOptions,
+    tiles: &mut HashMap<TileCoord, Vec<TileFeature>>,
+) -> Result<(), ConverterError> {
+    // Each member polygon only touches the tiles of its own range (see `tile_ranges`)
+    let mut members_by_tile: std::collections::BTreeMap<(u32, u32), Vec<&Polygon<f64>>> =
+        std::collections::BTreeMap::new();
+    for &(member, range) in ranges {
+        for tile in covered_tiles(zoom, range, only) {
+            members_by_tile.entry(tile).or_default().push(&multi_polygon.0[member]);
+        }
+    }
     
-    // Get range of intersecting tiles
-    let (tx_min, ty_max) = lonlat_to_tile(min_lon, min_lat, zoom);
-    let (tx_max, ty_min) = lonlat_to_tile(max_lon, max_lat, zoom);
+    // Place MultiPolygon in each tile
+    for ((tx, ty), members) in members_by_tile {
+        let tile_polygons = members
+            .iter()
+            .map(|polygon| polygon_to_tile_rings(polygon, tx, ty, zoom, &options.grid))
+            .collect::<Result<Vec<_>, _>>()?;
+        
+        // Add to tile
+        let coord = TileCoord::new(zoom, tx, ty);
+        let tile_feature = TileFeature {
+            geometry: TileGeometry::MultiPolygon(tile_polygons),
+            properties: properties.clone(),
+            label: false,
+        };
+        
+        tiles.entry(coord).or_default().push(tile_feature);
+    }
     
-    // Place Polygon in each tile
-    for tx in tx_min..=tx_max {
-        for ty in ty_min..=ty_max {
-            // Convert exterior ring
-            let mut tile_rings = Vec::new();
-            let mut exterior_ring = Vec::new();
-            
-            for coord in &exterior.0 {
-                let (mx, my) = lonlat_to_meters(coord.x, coord.y);
-                let (px, py) = meters_to_pixel_in_tile(mx, my, tx, ty, zoom);
-                
-                let tile_x = ((px / 256.0) * EXTENT as f64) as i32;
-                let tile_y = ((py / 256.0) * EXTENT as f64) as i32;
-                
-                exterior_ring.push((tile_x, tile_y));
-            }
-            tile_rings.push(exterior_ring);
-            
-            // Convert interior rings (holes)
-            for interior in polygon.interiors() {
-                let mut interior_ring = Vec::new();
-                for coord in &interior.0 {
-                    let (mx, my) = lonlat_to_meters(coord.x, coord.y);
-                    let (px, py) = meters_to_pixel_in_tile(mx, my, tx, ty, zoom);
-                    
-                    let tile_x = ((px / 256.0) * EXTENT as f64) as i32;
-                    let tile_y = ((py / 256.0) * EXTENT as f64) as i32;
-                    
-                    interior_ring.push((tile_x, tile_y));
+    Ok(())
+}
+
+/// Inclusive tile range (tx_min, ty_min, tx_max, ty_max)
+type TileRange = (u32, u32, u32, u32);
+
+/// Inclusive tile range (tx_min, ty_min, tx_max, ty_max) covering lon/lat bounds at `zoom`
+/// 
+/// With a `buffer` (in MVT grid units), the range also includes neighboring tiles whose
+/// buffer zone the bounds reach into.
+fn tile_range(
+    (min_lon, min_lat, max_lon, max_lat): (f64, f64, f64, f64),
+    zoom: u8,
+    buffer: u32,
+    grid: &GridSpec,
+) -> TileRange {
+    // Tile rows grow southwards, so the minimum latitude gives the maximum row
+    let (tx_min, ty_max) = grid.tile_for(min_lon, min_lat, zoom);
+    let (tx_max, ty_min) = grid.tile_for(max_lon, max_lat, zoom);
+    if buffer == 0 {
+        return (tx_min, ty_min, tx_max, ty_max);
+    }
+    
+    // Grid position of the corners within their tiles, to see how far they reach into
+    // the buffer of the neighboring tiles
+    let position = |lon: f64, lat: f64, tx: u32, ty: u32| {
+        let (gx, gy) = match grid.projection {
+            GridProjection::WebMercator => lonlat_to_meters(lon, lat),
+            GridProjection::Identity => (lon, lat),
+        };
+        let (px, py) = grid.pixel_in_tile(gx, gy, tx, ty, zoom);
+        let scale = EXTENT as f64 / grid.tile_size as f64;
+        (px * scale, py * scale)
+    };
+    let reach = |distance: f64| ((buffer as f64 - distance) / EXTENT as f64).ceil().max(0.0) as u32;
+    let last = grid.last_tile(zoom);
+    
+    let (min_x, max_y) = position(min_lon, min_lat, tx_min, ty_max);
+    let (max_x, min_y) = position(max_lon, max_lat, tx_max, ty_min);
+    (
+        tx_min.saturating_sub(reach(min_x)),
+        ty_min.saturating_sub(reach(min_y)),
+        tx_max.saturating_add(reach(EXTENT as f64 - max_x)).min(last),
+        ty_max.saturating_add(reach(EXTENT as f64 - max_y)).min(last),
+    )
+}
+
+/// Tiles a feature is assigned to at `zoom` with `options`, sorted by x then y
+/// 
+/// Takes the same steps as the generate functions (clip mask, zoom window, labels,
+/// centroids, short lines, antimeridian split and tile buffers) but without converting
+/// any geometry, so it is cheap even for features spanning many tiles. A tile may still
+/// end up without the feature when its part collapses on the tile grid.
+pub fn feature_tiles(feature: &Feature, zoom: u8, options: &TilerOptions) -> Result<Vec<TileCoord>, ConverterError> {
+    let bounds = crate::geojson_parser::feature_bounds(feature);
+    let (min_lon, min_lat, max_lon, max_lat) = bounds;
+    if ![min_lon, min_lat, max_lon, max_lat].iter().all(|v| v.is_finite()) {
+        return Err(ConverterError::Projection(format!("Non-finite feature bounds: {:?}", bounds)));
+    }
+    
+    let clipped;
+    let parts = match options.clip_mask {
+        Some(_) => {
+            clipped = clip_feature_to_mask(feature.clone(), options);
+            clipped.as_slice()
+        }
+        None => std::slice::from_ref(feature),
+    };
+    
+    let mut tiles = std::collections::BTreeSet::new();
+    for part in parts {
+        let Some(geometry) = prepared_geometry(part, zoom, options) else {
+            continue;
+        };
+        let label = label_point(&geometry, zoom, options).map(|point| Cow::Owned(GeometryType::Point(point)));
+        for placed in label.into_iter().chain(tiled_parts(&geometry, zoom, options, &mut 0)) {
+            for (_, range) in tile_ranges(&placed, zoom, options) {
+                tiles.extend(covered_tiles(zoom, range, None));
+            }
+        }
+    }
+    Ok(tiles.into_iter().map(|(tx, ty)| TileCoord::new(zoom, tx, ty)).collect())
+}
+
+/// List the tiles of an inclusive tile range, optionally restricted to a set of coordinates
+/// 
+/// With a restriction, only the requested tiles are visited instead of the whole range.
+fn covered_tiles(
+    zoom: u8,
+    (tx_min, ty_min, tx_max, ty_max): TileRange,
+    only: Option<&HashSet<TileCoord>>,
+) -> Vec<(u32, u32)> {
+    match only {
+        Some(only) => {
+            let mut covered: Vec<(u32, u32)> = only
+                .iter()
+                .filter(|c| c.z == zoom && (tx_min..=tx_max).contains(&c.x) && (ty_min..=ty_max).contains(&c.y))
+                .map(|c| (c.x, c.y))
+                .collect();
+            covered.sort_unstable();
+            covered
+        }
+        None => (tx_min..=tx_max)
+            .flat_map(|tx| (ty_min..=ty_max).map(move |ty| (tx, ty)))
+            .collect(),
+    }
+}
+
+/// Convert Polygon rings (exterior first, then holes) to this tile's coordinate system
+fn polygon_to_tile_rings(
+    polygon: &Polygon<f64>,
+    tx: u32,
+    ty: u32,
+    zoom: u8,
+    grid: &GridSpec,
+) -> Result<Vec<Vec<(i32, i32)>>, ConverterError> {
+    let mut tile_rings = Vec::new();
+    
+    for ring in std::iter::once(polygon.exterior()).chain(polygon.interiors()) {
+        let tile_ring = ring.0
+            .iter()
+            .map(|coord| lonlat_to_tile_grid(coord.x, coord.y, tx, ty, zoom, grid))
+            .collect::<Result<Vec<_>, _>>()?;
+        tile_rings.push(remove_collinear_ring(dedup_consecutive(tile_ring)));
+    }
+    
+    Ok(tile_rings)
+}
+
+/// Project lon/lat into a tile of `grid` and snap it to the integer MVT grid (0-4096)
+/// 
+/// Rounding (rather than truncating) keeps the quantization error within half a
+/// grid cell, so delta encoding works on exact integer positions.
+fn lonlat_to_tile_grid(
+    lon: f64,
+    lat: f64,
+    tx: u32,
+    ty: u32,
+    zoom: u8,
+    grid: &GridSpec,
+) -> Result<(i32, i32), ConverterError> {
+    let (gx, gy) = grid.project(lon, lat)?;
+    let (px, py) = grid.pixel_in_tile(gx, gy, tx, ty, zoom);
+    
+    let tile_size = grid.tile_size as f64;
+    let tile_x = ((px / tile_size) * EXTENT as f64).round() as i32;
+    let tile_y = ((py / tile_size) * EXTENT as f64).round() as i32;
+    
+    Ok((tile_x, tile_y))
+}
+
+/// Convert a point on the MVT grid of tile (tx, ty) back to lon/lat
+pub fn tile_grid_to_lonlat(x: i32, y: i32, tx: u32, ty: u32, zoom: u8) -> (f64, f64) {
+    let px = x as f64 / EXTENT as f64 * 256.0;
+    let py = y as f64 / EXTENT as f64 * 256.0;
+    let (mx, my) = pixel_in_tile_to_meters(px, py, tx, ty, zoom);
+    meters_to_lonlat(mx, my)
+}
+
+/// Convert tile-space geometry back to a lon/lat GeoJSON geometry (for QA dumps)
+/// 
+/// Coordinates are rounded to `precision` decimals, hiding the float noise of the
+/// inverse projection (6 decimals are about 11 cm).
+pub fn tile_geometry_to_geojson(geometry: &TileGeometry, coord: TileCoord, precision: usize) -> geojson::Geometry {
+    let scale = 10f64.powi(precision.min(15) as i32);
+    let position = |&(x, y): &(i32, i32)| {
+        let (lon, lat) = tile_grid_to_lonlat(x, y, coord.x, coord.y, coord.z);
+        vec![(lon * scale).round() / scale, (lat * scale).round() / scale]
+    };
+    let ring = |ring: &Vec<(i32, i32)>| ring.iter().map(position).collect::<Vec<_>>();
+    
+    let value = match geometry {
+        TileGeometry::Point(x, y) => geojson::Value::Point(position(&(*x, *y))),
+        TileGeometry::LineString(coords) => geojson::Value::LineString(ring(coords)),
+        TileGeometry::Polygon(rings) => geojson::Value::Polygon(rings.iter().map(ring).collect()),
+        TileGeometry::MultiPolygon(polygons) => geojson::Value::MultiPolygon(
+            polygons.iter().map(|rings| rings.iter().map(ring).collect()).collect(),
+        ),
+    };
+    geojson::Geometry::new(value)
+}
+
+/// Clip the features of one tile in place to `options.clamp_margin` around the extent
+/// 
+/// Lines are cut where they cross the margin (a line that leaves and re-enters it becomes
+/// one feature per part) and polygon rings are clipped to it, so the remaining segments
+/// keep their slope. Points beyond the margin and geometry left without length or area
+/// are dropped. Nothing happens without a margin.
+pub fn clamp_tile(features: &mut Vec<TileFeature>, options: &TilerOptions) {
+    let Some(margin) = options.clamp_margin else {
+        return;
+    };
+    let (min, max) = (-(margin as i32), EXTENT + margin as i32);
+    let inside = |&(x, y): &(i32, i32)| (min..=max).contains(&x) && (min..=max).contains(&y);
+    
+    let (lo, hi) = (min as f64, max as f64);
+    let bounds = Polygon::new(LineString::from(vec![(lo, lo), (hi, lo), (hi, hi), (lo, hi), (lo, lo)]), vec![]);
+    let to_coords = |points: &[(i32, i32)]| -> Vec<Coord<f64>> {
+        points.iter().map(|&(x, y)| Coord { x: x as f64, y: y as f64 }).collect()
+    };
+    let to_grid = |coords: Vec<Coord<f64>>| -> Vec<(i32, i32)> {
+        dedup_consecutive(coords.into_iter().map(|c| (c.x.round() as i32, c.y.round() as i32)).collect())
+    };
+    // Clip a closed ring against the four sides of the margin
+    let clip_ring = |ring: &Vec<(i32, i32)>| -> Option<Vec<(i32, i32)>> {
+        if ring.iter().all(inside) {
+            return Some(ring.clone());
+        }
+        let mut points = to_coords(ring);
+        if points.len() > 1 && points[0] == points[points.len() - 1] {
+            points.pop();
+        }
+        points = clip_ring_half_plane(&points, |c| c.x - lo);
+        points = clip_ring_half_plane(&points, |c| hi - c.x);
+        points = clip_ring_half_plane(&points, |c| c.y - lo);
+        points = clip_ring_half_plane(&points, |c| hi - c.y);
+        let mut ring = to_grid(points);
+        if ring.len() > 1 && ring[0] == ring[ring.len() - 1] {
+            ring.pop();
+        }
+        if ring.len() < 3 {
+            return None;
+        }
+        ring.push(ring[0]);
+        Some(ring)
+    };
+    // Clip the rings of a polygon; `None` when its exterior is clipped away
+    let clip_polygon = |rings: &Vec<Vec<(i32, i32)>>| -> Option<Vec<Vec<(i32, i32)>>> {
+        let (exterior, holes) = rings.split_first()?;
+        let exterior = clip_ring(exterior)?;
+        Some(std::iter::once(exterior).chain(holes.iter().filter_map(clip_ring)).collect())
+    };
+    
+    // Parts of a geometry within the margin (`None`: unchanged)
+    let clip = |geometry: &TileGeometry| -> Option<Vec<TileGeometry>> {
+        match geometry {
+            TileGeometry::Point(x, y) if inside(&(*x, *y)) => None,
+            TileGeometry::Point(..) => Some(Vec::new()),
+            TileGeometry::LineString(coords) if coords.iter().all(inside) => None,
+            TileGeometry::LineString(coords) => Some(
+                clip_line_to_mask(&to_coords(coords), &bounds)
+                    .into_iter()
+                    .map(to_grid)
+                    .filter(|part| part.len() >= 2)
+                    .map(TileGeometry::LineString)
+                    .collect(),
+            ),
+            TileGeometry::Polygon(rings) => Some(clip_polygon(rings).map(TileGeometry::Polygon).into_iter().collect()),
+            TileGeometry::MultiPolygon(polygons) => {
+                let polygons: Vec<_> = polygons.iter().filter_map(clip_polygon).collect();
+                Some(if polygons.is_empty() { Vec::new() } else { vec![TileGeometry::MultiPolygon(polygons)] })
+            }
+        }
+    };
+    
+    let mut clipped = Vec::with_capacity(features.len());
+    for feature in features.drain(..) {
+        match clip(&feature.geometry) {
+            None => clipped.push(feature),
+            Some(parts) => {
+                clipped.extend(parts.into_iter().map(|geometry| TileFeature { geometry, ..feature.clone() }))
+            }
+        }
+    }
+    *features = clipped;
+}
+
+/// Remove consecutive duplicate points produced by snapping to the grid
+fn dedup_consecutive(mut coords: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+    coords.dedup();
+    coords
+}
+
+/// Distance (in grid units) within which a point counts as lying on its neighbors' segment
+const COLLINEAR_TOLERANCE: f64 = 1e-6;
+
+/// Remove points lying on the segment between their neighbors (after quantization)
+/// 
+/// Such points add no shape but cost bytes. The end points are always kept, and so are
+/// points where the line doubles back on itself.
+fn remove_collinear(coords: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+    let mut kept: Vec<(i32, i32)> = Vec::with_capacity(coords.len());
+    for point in coords {
+        while kept.len() >= 2 && is_between(kept[kept.len() - 2], kept[kept.len() - 1], point) {
+            kept.pop();
+        }
+        kept.push(point);
+    }
+    kept
+}
+
+/// `remove_collinear` for a closed ring, also checking the vertex at the ring's seam
+fn remove_collinear_ring(ring: Vec<(i32, i32)>) -> Vec<(i32, i32)> {
+    let mut ring = remove_collinear(ring);
+    let n = ring.len();
+    if n > 4 && ring[0] == ring[n - 1] && is_between(ring[n - 2], ring[0], ring[1]) {
+        ring.remove(0);
+        ring[n - 2] = ring[0];
+    }
+    ring
+}
+
+/// Whether `b` lies on the segment from `a` to `c`
+fn is_between(a: (i32, i32), b: (i32, i32), c: (i32, i32)) -> bool {
+    let (abx, aby) = ((b.0 - a.0) as f64, (b.1 - a.1) as f64);
+    let (acx, acy) = ((c.0 - a.0) as f64, (c.1 - a.1) as f64);
+    let length = acx.hypot(acy);
+    if length == 0.0 {
+        return false;
+    }
+    
+    let distance = (abx * acy - aby * acx).abs() / length;
+    let projection = abx * acx + aby * acy;
+    distance <= COLLINEAR_TOLERANCE && projection >= 0.0 && projection <= length * length
+}
+
+/// Split a geometry that crosses the antimeridian into parts within [-180, 180]
+/// 
+/// A crossing is a pair of consecutive vertices more than 180° of longitude apart,
+/// which is read as taking the short way across ±180°. Lines become one LineString
+/// per side; polygons are clipped into a western and eastern part (returned as a
+/// MultiPolygon). Rings enclosing a pole are closed along the pole before clipping.
+/// Returns `None` when the geometry does not cross.
+pub fn split_antimeridian(geometry: &GeometryType) -> Option<Vec<GeometryType>> {
+    match geometry {
+        GeometryType::Point(_) => None,
+        GeometryType::LineString(line) => {
+            if !crosses_antimeridian(&line.0) {
+                return None;
+            }
+            let unwrapped = unwrap_longitudes(&line.0, line.0[0].x);
+            let parts = split_line_at_antimeridian(&unwrapped)
+                .into_iter()
+                .map(|coords| GeometryType::LineString(LineString::from(coords)))
+                .collect();
+            Some(parts)
+        }
+        GeometryType::Polygon(polygon) => {
+            if !polygon_crosses_antimeridian(polygon) {
+                return None;
+            }
+            Some(polygons_to_geometry(split_polygon_at_antimeridian(polygon)))
+        }
+        GeometryType::MultiPolygon(multi_polygon) => {
+            if !multi_polygon.0.iter().any(polygon_crosses_antimeridian) {
+                return None;
+            }
+            let polygons = multi_polygon
+                .0
+                .iter()
+                .flat_map(|polygon| {
+                    if polygon_crosses_antimeridian(polygon) {
+                        split_polygon_at_antimeridian(polygon)
+                    } else {
+                        vec![polygon.clone()]
+                    }
+                })
+                .collect();
+            Some(polygons_to_geometry(polygons))
+        }
+    }
+}
+
+/// Rebuild the polygons of a geometry from ring containment (`None` for non-polygons)
+/// 
+/// Every ring of the feature is classified by how many other rings contain it: even
+/// depth makes it an exterior, odd depth a hole of the smallest ring containing it.
+/// Exteriors keep their input order; winding is left to the MVT encoder.
+pub(crate) fn regroup_rings_by_containment(geometry: &GeometryType) -> Option<GeometryType> {
+    let rings: Vec<&LineString<f64>> = match geometry {
+        GeometryType::Polygon(polygon) => std::iter::once(polygon.exterior()).chain(polygon.interiors()).collect(),
+        GeometryType::MultiPolygon(multi_polygon) => multi_polygon
+            .0
+            .iter()
+            .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors()))
+            .collect(),
+        _ => return None,
+    };
+    let rings: Vec<&LineString<f64>> = rings.into_iter().filter(|ring| ring.0.len() >= 4).collect();
+    let areas: Vec<f64> = rings.iter().map(|ring| coord_ring_area(&ring.0).abs()).collect();
+    
+    // Rings containing each ring, smallest first
+    let containers: Vec<Vec<usize>> = (0..rings.len())
+        .map(|i| {
+            let mut containing: Vec<usize> = (0..rings.len())
+                .filter(|&j| j != i && areas[j] > areas[i] && ring_inside(&rings[i].0, &rings[j].0))
+                .collect();
+            containing.sort_by(|&a, &b| areas[a].total_cmp(&areas[b]));
+            containing
+        })
+        .collect();
+    
+    let mut polygons: Vec<(usize, Vec<LineString<f64>>)> = Vec::new();
+    for (i, containing) in containers.iter().enumerate() {
+        if containing.len() % 2 == 0 {
+            polygons.push((i, Vec::new()));
+        }
+    }
+    for (i, containing) in containers.iter().enumerate() {
+        if containing.len() % 2 == 1 {
+            let parent = containing[0];
+            if let Some((_, holes)) = polygons.iter_mut().find(|(exterior, _)| *exterior == parent) {
+                holes.push(rings[i].clone());
+            }
+        }
+    }
+    
+    let polygons = polygons
+        .into_iter()
+        .map(|(exterior, holes)| Polygon::new(rings[exterior].clone(), holes))
+        .collect();
+    polygons_to_geometry(polygons).into_iter().next()
+}
+
+/// Centroid of a geometry in lon/lat
+/// 
+/// Polygons use the area-weighted centroid of all members (holes subtract), lines the
+/// length-weighted centroid of their segments. Degenerate shapes fall back to the
+/// segment centroid of their rings, then to the mean of their vertices. `None` only
+/// for geometry without coordinates.
+pub fn centroid(geometry: &GeometryType) -> Option<Point<f64>> {
+    let polygons: Vec<&Polygon<f64>> = match geometry {
+        GeometryType::Point(point) => return Some(*point),
+        GeometryType::LineString(line) => return lines_centroid(std::iter::once(&line.0[..])),
+        GeometryType::Polygon(polygon) => vec![polygon],
+        GeometryType::MultiPolygon(multi_polygon) => multi_polygon.0.iter().collect(),
+    };
+    
+    // Area moments: exteriors count positive, holes negative, whatever their winding
+    let (mut area, mut moment_x, mut moment_y) = (0.0, 0.0, 0.0);
+    for polygon in &polygons {
+        for (index, ring) in std::iter::once(polygon.exterior()).chain(polygon.interiors()).enumerate() {
+            let ring_area = coord_ring_area(&ring.0);
+            if ring_area == 0.0 {
+                continue;
+            }
+            let (mut cx, mut cy) = (0.0, 0.0);
+            for w in ring.0.windows(2) {
+                let cross = w[0].x * w[1].y - w[1].x * w[0].y;
+                cx += (w[0].x + w[1].x) * cross;
+                cy += (w[0].y + w[1].y) * cross;
+            }
+            // cx / (6 * ring_area) is the ring centroid; weight it by the unsigned area
+            let sign = if index == 0 { 1.0 } else { -1.0 };
+            let weight = sign * ring_area.abs() / (6.0 * ring_area);
+            area += sign * ring_area.abs();
+            moment_x += weight * cx;
+            moment_y += weight * cy;
+        }
+    }
+    if area > f64::EPSILON {
+        return Some(Point::new(moment_x / area, moment_y / area));
+    }
+    
+    let rings = polygons
+        .iter()
+        .flat_map(|polygon| std::iter::once(polygon.exterior()).chain(polygon.interiors()))
+        .map(|ring| &ring.0[..]);
+    lines_centroid(rings)
+}
+
+/// Length-weighted centroid of line segments, or the mean vertex if all have zero length
+fn lines_centroid<'a>(lines: impl Iterator<Item = &'a [Coord<f64>]> + Clone) -> Option<Point<f64>> {
+    let (mut length, mut sum_x, mut sum_y) = (0.0, 0.0, 0.0);
+    for line in lines.clone() {
+        for w in line.windows(2) {
+            let segment = ((w[1].x - w[0].x).powi(2) + (w[1].y - w[0].y).powi(2)).sqrt();
+            length += segment;
+            sum_x += segment * (w[0].x + w[1].x) / 2.0;
+            sum_y += segment * (w[0].y + w[1].y) / 2.0;
+        }
+    }
+    if length > 0.0 {
+        return Some(Point::new(sum_x / length, sum_y / length));
+    }
+    
+    let vertices: Vec<&Coord<f64>> = lines.flatten().collect();
+    if vertices.is_empty() {
+        return None;
+    }
+    let count = vertices.len() as f64;
+    Some(Point::new(
+        vertices.iter().map(|c| c.x).sum::<f64>() / count,
+        vertices.iter().map(|c| c.y).sum::<f64>() / count,
+    ))
+}
+
+/// Whether `inner` lies inside `outer`, judged by its first vertex not on `outer`
+fn ring_inside(inner: &[Coord<f64>], outer: &[Coord<f64>]) -> bool {
+    inner
+        .iter()
+        .find(|point| !outer.contains(point))
+        .is_some_and(|point| point_in_ring(*point, outer))
+}
+
+/// Even-odd ray casting point-in-ring test
+fn point_in_ring(point: Coord<f64>, ring: &[Coord<f64>]) -> bool {
+    let mut inside = false;
+    for w in ring.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        if (a.y > point.y) != (b.y > point.y) && point.x < a.x + (point.y - a.y) / (b.y - a.y) * (b.x - a.x) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+fn crosses_antimeridian(coords: &[Coord<f64>]) -> bool {
+    coords.windows(2).any(|w| (w[1].x - w[0].x).abs() > 180.0)
+}
+
+fn polygon_crosses_antimeridian(polygon: &Polygon<f64>) -> bool {
+    std::iter::once(polygon.exterior())
+        .chain(polygon.interiors())
+        .any(|ring| crosses_antimeridian(&ring.0))
+}
+
+fn polygons_to_geometry(mut polygons: Vec<Polygon<f64>>) -> Vec<GeometryType> {
+    match polygons.len() {
+        0 => Vec::new(),
+        1 => vec![GeometryType::Polygon(polygons.remove(0))],
+        _ => vec![GeometryType::MultiPolygon(MultiPolygon::new(polygons))],
+    }
+}
+
+/// Make longitudes continuous (no jumps over 180°), starting next to `reference_lon`
+fn unwrap_longitudes(coords: &[Coord<f64>], reference_lon: f64) -> Vec<Coord<f64>> {
+    let mut unwrapped = Vec::with_capacity(coords.len());
+    let mut prev_lon = reference_lon;
+    for coord in coords {
+        let mut lon = coord.x;
+        while lon - prev_lon > 180.0 {
+            lon -= 360.0;
+        }
+        while lon - prev_lon < -180.0 {
+            lon += 360.0;
+        }
+        unwrapped.push(Coord { x: lon, y: coord.y });
+        prev_lon = lon;
+    }
+    unwrapped
+}
+
+/// Index of the 360°-wide longitude window containing `lon` (0 = [-180, 180])
+fn antimeridian_window(lon: f64) -> i32 {
+    ((lon + 180.0) / 360.0).floor() as i32
+}
+
+/// Cut an unwrapped line at every ±180° boundary and shift each piece back into range
+fn split_line_at_antimeridian(coords: &[Coord<f64>]) -> Vec<Vec<Coord<f64>>> {
+    let mut pieces: Vec<(i32, Vec<Coord<f64>>)> = Vec::new();
+    let mut current = vec![coords[0]];
+    let mut current_window: Option<i32> = None;
+    
+    for segment in coords.windows(2) {
+        let (a, b) = (segment[0], segment[1]);
+        
+        // Boundaries strictly between the endpoints, in travel direction
+        let (lo, hi) = (a.x.min(b.x), a.x.max(b.x));
+        let mut points = vec![a];
+        let first = ((lo - 180.0) / 360.0).ceil() as i32;
+        let last = ((hi - 180.0) / 360.0).floor() as i32;
+        let mut cuts: Vec<Coord<f64>> = (first..=last)
+            .map(|m| 180.0 + 360.0 * m as f64)
+            .filter(|&boundary| lo < boundary && boundary < hi)
+            .map(|boundary| {
+                let t = (boundary - a.x) / (b.x - a.x);
+                Coord { x: boundary, y: a.y + t * (b.y - a.y) }
+            })
+            .collect();
+        if b.x < a.x {
+            cuts.reverse();
+        }
+        points.extend(cuts);
+        points.push(b);
+        
+        for sub in points.windows(2) {
+            let window = antimeridian_window((sub[0].x + sub[1].x) / 2.0);
+            match current_window {
+                Some(w) if w != window => {
+                    pieces.push((w, std::mem::replace(&mut current, vec![sub[0]])));
                 }
-                tile_rings.push(interior_ring);
+                _ => {}
             }
-            
-            // Add to tile
-            let coord = TileCoord::new(zoom, tx, ty);
-            let tile_feature = TileFeature {
-                geometry: TileGeometry::Polygon(tile_rings),
-                properties: properties.clone(),
-            };
-            
-            tiles.entry(coord).or_insert_with(Vec::new).push(tile_feature);
+            current_window = Some(window);
+            current.push(sub[1]);
         }
     }
+    pieces.push((current_window.unwrap_or(0), current));
     
-    Ok(())
+    pieces
+        .into_iter()
+        .filter(|(_, piece)| piece.len() >= 2)
+        .map(|(window, piece)| shift_longitudes(&piece, window))
+        .collect()
+}
+
+/// Clip an antimeridian-crossing polygon into parts that each fit within [-180, 180]
+fn split_polygon_at_antimeridian(polygon: &Polygon<f64>) -> Vec<Polygon<f64>> {
+    let exterior = &polygon.exterior().0;
+    if exterior.is_empty() {
+        return Vec::new();
+    }
+    
+    let exterior = close_around_pole(unwrap_longitudes(exterior, exterior[0].x));
+    let holes: Vec<Vec<Coord<f64>>> = polygon
+        .interiors()
+        .iter()
+        .filter(|ring| !ring.0.is_empty())
+        .map(|ring| unwrap_longitudes(&ring.0, exterior[0].x))
+        .collect();
+    
+    let (min_lon, max_lon) = exterior
+        .iter()
+        .fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), c| (min.min(c.x), max.max(c.x)));
+    
+    let mut parts = Vec::new();
+    for window in antimeridian_window(min_lon)..=antimeridian_window(max_lon) {
+        let lo = -180.0 + 360.0 * window as f64;
+        let hi = lo + 360.0;
+        
+        let clipped_exterior = clip_ring_to_lon_range(&exterior, lo, hi);
+        if clipped_exterior.len() < 4 {
+            continue;
+        }
+        let clipped_holes: Vec<LineString<f64>> = holes
+            .iter()
+            .map(|hole| clip_ring_to_lon_range(hole, lo, hi))
+            .filter(|hole| hole.len() >= 4)
+            .map(|hole| LineString::from(shift_longitudes(&hole, window)))
+            .collect();
+        
+        parts.push(Polygon::new(
+            LineString::from(shift_longitudes(&clipped_exterior, window)),
+            clipped_holes,
+        ));
+    }
+    parts
+}
+
+/// Close a ring that wraps all the way around the globe (encloses a pole)
+/// 
+/// After unwrapping such a ring ends 360° away from where it started, so it is
+/// completed by walking along the nearer pole back to the start.
+fn close_around_pole(mut ring: Vec<Coord<f64>>) -> Vec<Coord<f64>> {
+    let first = ring[0];
+    let last = ring[ring.len() - 1];
+    if (last.x - first.x).abs() <= 180.0 {
+        return ring;
+    }
+    
+    let mean_lat = ring.iter().map(|c| c.y).sum::<f64>() / ring.len() as f64;
+    let pole_lat = if mean_lat >= 0.0 { 90.0 } else { -90.0 };
+    ring.push(Coord { x: last.x, y: pole_lat });
+    ring.push(Coord { x: first.x, y: pole_lat });
+    ring.push(first);
+    ring
+}
+
+/// Clip a lon/lat geometry to a mask polygon, returning the parts inside (none if outside)
+/// 
+/// Points are kept when they lie inside the mask and outside its holes; lines are cut
+/// exactly where they cross the mask boundary, holes included. Polygons are intersected
+/// with the mask (see `clip_polygon_to_mask`), so masks may be concave and have holes.
+pub fn clip_to_mask(geometry: &GeometryType, mask: &Polygon<f64>) -> Vec<GeometryType> {
+    match geometry {
+        GeometryType::Point(point) if inside_mask(point.0, mask) => vec![geometry.clone()],
+        GeometryType::Point(_) => Vec::new(),
+        GeometryType::LineString(line) => clip_line_to_mask(&line.0, mask)
+            .into_iter()
+            .map(|part| GeometryType::LineString(LineString::new(part)))
+            .collect(),
+        GeometryType::Polygon(polygon) => polygons_to_geometry(clip_polygon_to_mask(polygon, mask)),
+        GeometryType::MultiPolygon(multi) => {
+            polygons_to_geometry(multi.0.iter().flat_map(|polygon| clip_polygon_to_mask(polygon, mask)).collect())
+        }
+    }
+}
+
+fn inside_mask(point: Coord<f64>, mask: &Polygon<f64>) -> bool {
+    point_in_ring(point, &mask.exterior().0) && !mask.interiors().iter().any(|hole| point_in_ring(point, &hole.0))
+}
+
+/// Parts of a line inside the mask, split at every crossing of a mask ring
+fn clip_line_to_mask(coords: &[Coord<f64>], mask: &Polygon<f64>) -> Vec<Vec<Coord<f64>>> {
+    let rings: Vec<&LineString<f64>> = std::iter::once(mask.exterior()).chain(mask.interiors()).collect();
+    let at = |a: Coord<f64>, b: Coord<f64>, t: f64| Coord { x: a.x + t * (b.x - a.x), y: a.y + t * (b.y - a.y) };
+    
+    let mut parts: Vec<Vec<Coord<f64>>> = Vec::new();
+    for w in coords.windows(2) {
+        let (a, b) = (w[0], w[1]);
+        let d = b - a;
+        
+        // Segment parameters of the crossings with every mask edge
+        let mut cuts = vec![0.0, 1.0];
+        for ring in &rings {
+            for edge in ring.0.windows(2) {
+                let (p, e) = (edge[0], edge[1] - edge[0]);
+                let denominator = d.x * e.y - d.y * e.x;
+                if denominator == 0.0 {
+                    continue;
+                }
+                let ap = p - a;
+                let t = (ap.x * e.y - ap.y * e.x) / denominator;
+                let u = (ap.x * d.y - ap.y * d.x) / denominator;
+                if (0.0..=1.0).contains(&t) && (0.0..=1.0).contains(&u) {
+                    cuts.push(t);
+                }
+            }
+        }
+        cuts.sort_by(f64::total_cmp);
+        cuts.dedup();
+        
+        // Keep the pieces whose midpoint is inside, joining pieces that touch
+        for t in cuts.windows(2) {
+            if !inside_mask(at(a, b, (t[0] + t[1]) / 2.0), mask) {
+                continue;
+            }
+            let (start, end) = (at(a, b, t[0]), at(a, b, t[1]));
+            match parts.last_mut() {
+                Some(part) if part.last() == Some(&start) => part.push(end),
+                _ => parts.push(vec![start, end]),
+            }
+        }
+    }
+    parts
+}
+
+/// Intersection of a polygon with the mask; both may be concave and have holes
+/// 
+/// The edges of both are split where they cross or overlap. Polygon edges inside the
+/// mask and mask edges inside the polygon bound the intersection; where the boundaries
+/// run along each other, the edge is kept once if both interiors lie on the same side.
+/// With exterior rings counterclockwise and holes clockwise, every kept edge has the
+/// intersection on its left, so the edges link up into exteriors (counterclockwise)
+/// and holes (clockwise).
+fn clip_polygon_to_mask(polygon: &Polygon<f64>, mask: &Polygon<f64>) -> Vec<Polygon<f64>> {
+    let mut subject = polygon_edges(polygon);
+    let mut clip = polygon_edges(mask);
+    split_edges(&mut subject, &mut clip);
+    
+    let subject = edge_fragments(&subject);
+    let clip = edge_fragments(&clip);
+    let key = |(a, b): &(Coord<f64>, Coord<f64>)| (a.x.to_bits(), a.y.to_bits(), b.x.to_bits(), b.y.to_bits());
+    let subject_keys: HashSet<_> = subject.iter().map(key).collect();
+    let clip_keys: HashSet<_> = clip.iter().map(key).collect();
+    let reversed = |&(a, b): &(Coord<f64>, Coord<f64>)| (b, a);
+    let midpoint = |(a, b): &(Coord<f64>, Coord<f64>)| Coord { x: (a.x + b.x) / 2.0, y: (a.y + b.y) / 2.0 };
+    
+    let mut kept = Vec::new();
+    for fragment in &subject {
+        let shared = clip_keys.contains(&key(fragment));
+        let opposed = clip_keys.contains(&key(&reversed(fragment)));
+        if shared || (!opposed && inside_mask(midpoint(fragment), mask)) {
+            kept.push(*fragment);
+        }
+    }
+    for fragment in &clip {
+        let on_boundary = subject_keys.contains(&key(fragment)) || subject_keys.contains(&key(&reversed(fragment)));
+        if !on_boundary && inside_mask(midpoint(fragment), polygon) {
+            kept.push(*fragment);
+        }
+    }
+    
+    rings_to_polygons(link_fragments(kept))
+}
+
+/// A polygon edge with the points it is split at, as (parameter along the edge, point)
+type Edge = (Coord<f64>, Coord<f64>, Vec<(f64, Coord<f64>)>);
+
+/// Edges of the rings of a polygon, exteriors counterclockwise and holes clockwise
+fn polygon_edges(polygon: &Polygon<f64>) -> Vec<Edge> {
+    let mut edges = Vec::new();
+    for (i, ring) in std::iter::once(polygon.exterior()).chain(polygon.interiors()).enumerate() {
+        let mut points = ring.0.clone();
+        if points.len() > 1 && points[0] == points[points.len() - 1] {
+            points.pop();
+        }
+        if points.len() < 3 {
+            continue;
+        }
+        let area = coord_ring_area(&points);
+        if (i == 0) != (area > 0.0) {
+            points.reverse();
+        }
+        for j in 0..points.len() {
+            let (a, b) = (points[j], points[(j + 1) % points.len()]);
+            if a != b {
+                edges.push((a, b, Vec::new()));
+            }
+        }
+    }
+    edges
+}
+
+/// Record the crossings and collinear overlaps of every pair of edges on both edges
+/// 
+/// Each crossing is computed once and stored on both edges, so the fragments on either
+/// side share exactly the same end points.
+fn split_edges(subject: &mut [Edge], clip: &mut [Edge]) {
+    let cross = |u: Coord<f64>, v: Coord<f64>| u.x * v.y - u.y * v.x;
+    let dot = |u: Coord<f64>, v: Coord<f64>| u.x * v.x + u.y * v.y;
+    for (a, b, subject_cuts) in subject.iter_mut() {
+        let (a, b) = (*a, *b);
+        let d = b - a;
+        for (p, q, clip_cuts) in clip.iter_mut() {
+            let (p, q) = (*p, *q);
+            let e = q - p;
+            let denominator = cross(d, e);
+            let ap = p - a;
+            if denominator != 0.0 {
+                let t = cross(ap, e) / denominator;
+                let u = cross(ap, d) / denominator;
+                if !(0.0..=1.0).contains(&t) || !(0.0..=1.0).contains(&u) {
+                    continue;
+                }
+                // Crossings at an end point reuse that point exactly
+                let point = if t == 0.0 {
+                    a
+                } else if t == 1.0 {
+                    b
+                } else if u == 0.0 {
+                    p
+                } else if u == 1.0 {
+                    q
+                } else {
+                    Coord { x: a.x + t * d.x, y: a.y + t * d.y }
+                };
+                subject_cuts.push((t, point));
+                clip_cuts.push((u, point));
+            } else if cross(ap, d) == 0.0 {
+                // Collinear: split each edge at the end points of the other lying on it
+                for point in [p, q] {
+                    let t = dot(point - a, d) / dot(d, d);
+                    if t > 0.0 && t < 1.0 {
+                        subject_cuts.push((t, point));
+                    }
+                }
+                for point in [a, b] {
+                    let u = dot(point - p, e) / dot(e, e);
+                    if u > 0.0 && u < 1.0 {
+                        clip_cuts.push((u, point));
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Fragments of the split edges, in edge direction (zero-length pieces are dropped)
+fn edge_fragments(edges: &[Edge]) -> Vec<(Coord<f64>, Coord<f64>)> {
+    let mut fragments = Vec::new();
+    for (a, b, cuts) in edges {
+        let mut points: Vec<(f64, Coord<f64>)> = cuts.clone();
+        points.push((0.0, *a));
+        points.push((1.0, *b));
+        points.sort_by(|x, y| x.0.total_cmp(&y.0));
+        points.dedup_by(|x, y| x.1 == y.1);
+        fragments.extend(points.windows(2).map(|w| (w[0].1, w[1].1)));
+    }
+    fragments
+}
+
+/// Chain directed fragments into closed rings by matching end points
+/// 
+/// Chains that do not close (left over by rounding in degenerate input) are dropped.
+fn link_fragments(fragments: Vec<(Coord<f64>, Coord<f64>)>) -> Vec<Vec<Coord<f64>>> {
+    let key = |c: Coord<f64>| (c.x.to_bits(), c.y.to_bits());
+    let mut outgoing: HashMap<(u64, u64), Vec<Coord<f64>>> = HashMap::new();
+    for (a, b) in &fragments {
+        outgoing.entry(key(*a)).or_default().push(*b);
+    }
+    
+    let mut rings = Vec::new();
+    for (start, _) in fragments {
+        let mut ring = vec![start];
+        let mut current = start;
+        while let Some(next) = outgoing.get_mut(&key(current)).and_then(Vec::pop) {
+            ring.push(next);
+            current = next;
+            if current == start {
+                break;
+            }
+        }
+        if ring.len() >= 4 && current == start {
+            rings.push(ring);
+        }
+    }
+    rings
+}
+
+/// Group linked rings into polygons: counterclockwise rings are exteriors, clockwise
+/// rings holes of the smallest exterior containing them
+fn rings_to_polygons(rings: Vec<Vec<Coord<f64>>>) -> Vec<Polygon<f64>> {
+    let (exteriors, holes): (Vec<_>, Vec<_>) = rings.into_iter().partition(|ring| coord_ring_area(ring) > 0.0);
+    let mut polygons: Vec<(f64, LineString<f64>, Vec<LineString<f64>>)> = exteriors
+        .into_iter()
+        .map(|ring| (coord_ring_area(&ring), LineString::new(ring), Vec::new()))
+        .collect();
+    for hole in holes {
+        let point = Coord { x: (hole[0].x + hole[1].x) / 2.0, y: (hole[0].y + hole[1].y) / 2.0 };
+        let parent = polygons
+            .iter_mut()
+            .filter(|(_, exterior, _)| point_in_ring(point, &exterior.0))
+            .min_by(|a, b| a.0.total_cmp(&b.0));
+        if let Some((_, _, interiors)) = parent {
+            interiors.push(LineString::new(hole));
+        }
+    }
+    polygons.into_iter().map(|(_, exterior, interiors)| Polygon::new(exterior, interiors)).collect()
+}
+
+/// Clip an (open) ring against the half-plane where `side` is non-negative
+fn clip_ring_half_plane(points: &[Coord<f64>], side: impl Fn(Coord<f64>) -> f64) -> Vec<Coord<f64>> {
+    let intersect = |p: Coord<f64>, q: Coord<f64>| {
+        let t = side(p) / (side(p) - side(q));
+        Coord { x: p.x + t * (q.x - p.x), y: p.y + t * (q.y - p.y) }
+    };
+    
+    let mut clipped = Vec::new();
+    for i in 0..points.len() {
+        let current = points[i];
+        let previous = points[(i + points.len() - 1) % points.len()];
+        if side(current) >= 0.0 {
+            if side(previous) < 0.0 {
+                clipped.push(intersect(previous, current));
+            }
+            clipped.push(current);
+        } else if side(previous) >= 0.0 {
+            clipped.push(intersect(previous, current));
+        }
+    }
+    clipped
+}
+
+/// Sutherland–Hodgman clip of a ring to the longitude strip [lo, hi]; returns a closed ring
+fn clip_ring_to_lon_range(ring: &[Coord<f64>], lo: f64, hi: f64) -> Vec<Coord<f64>> {
+    let mut points = ring.to_vec();
+    if points.len() > 1 && points[0] == points[points.len() - 1] {
+        points.pop();
+    }
+    
+    let points = clip_ring_at_lon(&points, lo, true);
+    let mut points = clip_ring_at_lon(&points, hi, false);
+    
+    if let Some(&first) = points.first() {
+        points.push(first);
+    }
+    points
+}
+
+/// Clip an (open) ring against the half-plane `lon >= boundary` (or `lon <= boundary`)
+fn clip_ring_at_lon(points: &[Coord<f64>], boundary: f64, keep_east: bool) -> Vec<Coord<f64>> {
+    let inside = |c: &Coord<f64>| if keep_east { c.x >= boundary } else { c.x <= boundary };
+    let intersect = |p: &Coord<f64>, q: &Coord<f64>| {
+        let t = (boundary - p.x) / (q.x - p.x);
+        Coord { x: boundary, y: p.y + t * (q.y - p.y) }
+    };
+    
+    let mut clipped = Vec::new();
+    for i in 0..points.len() {
+        let current = &points[i];
+        let previous = &points[(i + points.len() - 1) % points.len()];
+        if inside(current) {
+            if !inside(previous) {
+                clipped.push(intersect(previous, current));
+            }
+            clipped.push(*current);
+        } else if inside(previous) {
+            clipped.push(intersect(previous, current));
+        }
+    }
+    clipped
+}
+
+fn shift_longitudes(coords: &[Coord<f64>], window: i32) -> Vec<Coord<f64>> {
+    let offset = 360.0 * window as f64;
+    coords.iter().map(|c| Coord { x: c.x - offset, y: c.y }).collect()
 }
 
 /// Calculate LineString bounding box
@@ -243,8 +1526,570 @@ mod tests {
         let properties = serde_json::Map::new();
         let mut tiles = HashMap::new();
         
-        tile_point(&point, &properties, 5, &mut tiles).unwrap();
+        tile_geometry(&GeometryType::Point(point), &properties, 5, None, &TilerOptions::default(), &mut tiles).unwrap();
+        
+        assert_eq!(tiles.len(), 1);
+    }
+
+    #[test]
+    fn test_point_below_zoom() {
+        let square = Polygon::new(
+            LineString::from(vec![(139.0, 35.0), (140.0, 35.0), (140.0, 36.0), (139.0, 36.0), (139.0, 35.0)]),
+            vec![],
+        );
+        let feature = Feature {
+            geometry: GeometryType::Polygon(square.clone()),
+            properties: serde_json::Map::new(),
+        };
+        let options = TilerOptions {
+            point_below_zoom: Some(10),
+            ..Default::default()
+        };
+        
+        let low = tile_features_in(std::slice::from_ref(&feature), 3, None, &options, &mut 0).unwrap();
+        assert_eq!(low.len(), 1);
+        let features = low.values().next().unwrap();
+        assert!(matches!(features[..], [TileFeature { geometry: TileGeometry::Point(..), .. }]));
+        
+        let high = tile_features_in(std::slice::from_ref(&feature), 12, None, &options, &mut 0).unwrap();
+        assert!(high.len() > 1);
+        assert!(high.values().flatten().all(|f| matches!(f.geometry, TileGeometry::Polygon(_))));
+        
+        // Multipolygon members weigh by area; a hole moves the centroid away from it
+        let small = Polygon::new(
+            LineString::from(vec![(150.0, 35.0), (150.5, 35.0), (150.5, 35.5), (150.0, 35.5), (150.0, 35.0)]),
+            vec![],
+        );
+        let multi = GeometryType::MultiPolygon(MultiPolygon(vec![square, small]));
+        let point = centroid(&multi).unwrap();
+        assert!((point.x() - (139.5 * 1.0 + 150.25 * 0.25) / 1.25).abs() < 1e-9);
+        let holed = Polygon::new(
+            LineString::from(vec![(0.0, 0.0), (4.0, 0.0), (4.0, 4.0), (0.0, 4.0), (0.0, 0.0)]),
+            vec![LineString::from(vec![(2.0, 0.0), (4.0, 0.0), (4.0, 4.0), (2.0, 4.0), (2.0, 0.0)])],
+        );
+        assert_eq!(centroid(&GeometryType::Polygon(holed)).unwrap(), Point::new(1.0, 2.0));
+        
+        // Degenerate shapes fall back to their segments, then to their vertices
+        let flat = Polygon::new(LineString::from(vec![(0.0, 0.0), (2.0, 0.0), (0.0, 0.0)]), vec![]);
+        assert_eq!(centroid(&GeometryType::Polygon(flat)).unwrap(), Point::new(1.0, 0.0));
+        let collapsed = GeometryType::LineString(LineString::from(vec![(3.0, 4.0), (3.0, 4.0)]));
+        assert_eq!(centroid(&collapsed).unwrap(), Point::new(3.0, 4.0));
+    }
+    
+    #[test]
+    fn test_buffer_per_geometry_type() {
+        // Tile 10/512/511 spans lon 0..0.3515625; both features lie about 0.001° (11 grid units) east of it
+        let point = Feature {
+            geometry: GeometryType::Point(Point::new(0.3525, 0.1)),
+            properties: serde_json::Map::new(),
+        };
+        let line = Feature {
+            geometry: GeometryType::LineString(LineString::from(vec![(0.3525, 0.1), (0.36, 0.2)])),
+            properties: serde_json::Map::new(),
+        };
+        let inner = TileCoord::new(10, 512, 511);
+        let options = TilerOptions {
+            buffer_points: 0,
+            buffer_lines: 64,
+            ..Default::default()
+        };
         
+        let tiles = tile_features_in(&[point.clone(), line.clone()], 10, None, &options, &mut 0).unwrap();
+        let features = &tiles[&inner];
+        assert_eq!(features.len(), 1);
+        let TileGeometry::LineString(coords) = &features[0].geometry else {
+            panic!("expected the line in the buffer of the neighboring tile");
+        };
+        assert!(coords[0].0 > EXTENT && coords[0].0 < EXTENT + 64, "{:?}", coords);
+        
+        // The point is added too once its buffer covers it, and nothing reaches further
+        let options = TilerOptions { buffer_points: 64, ..options };
+        let tiles = tile_features_in(&[point], 10, None, &options, &mut 0).unwrap();
+        assert!(tiles.contains_key(&inner));
+        assert_eq!(tiles.len(), 2);
+        assert_eq!(tile_features_in(&[line], 10, None, &TilerOptions::default(), &mut 0).unwrap().len(), 1);
+    }
+    
+    #[test]
+    fn test_custom_grid() {
+        // A flat 1000 x 1000 unit grid: zoom 2 tiles are 250 units wide
+        let options = TilerOptions {
+            grid: GridSpec {
+                projection: GridProjection::Identity,
+                origin: (0.0, 1000.0),
+                resolution: 1000.0 / 256.0,
+                tile_size: 256,
+            },
+            ..Default::default()
+        };
+        let feature = Feature {
+            geometry: GeometryType::Point(Point::new(625.0, 375.0)),
+            properties: serde_json::Map::new(),
+        };
+        let tiles = tile_features_in(&[feature], 2, None, &options, &mut 0).unwrap();
+        
+        let features = &tiles[&TileCoord::new(2, 2, 2)];
+        assert!(matches!(features[0].geometry, TileGeometry::Point(2048, 2048)));
         assert_eq!(tiles.len(), 1);
     }
+    
+    #[test]
+    fn test_clip_mask() {
+        let mask = Polygon::new(LineString::from(vec![(0.0, 0.0), (10.0, 0.0), (0.0, 10.0), (0.0, 0.0)]), vec![]);
+        let options = TilerOptions { clip_mask: Some(mask.clone()), ..Default::default() };
+        
+        // A 10 x 10 grid of points; 45 lie inside the triangle
+        let grid: Vec<Feature> = (0..100)
+            .map(|i| Feature {
+                geometry: GeometryType::Point(Point::new((i % 10) as f64 + 0.5, (i / 10) as f64 + 0.5)),
+                properties: serde_json::Map::new(),
+            })
+            .collect();
+        let tiles = tile_features_in(&clip_features_to_mask(grid, &options), 0, None, &options, &mut 0).unwrap();
+        assert_eq!(tiles[&TileCoord::new(0, 0, 0)].len(), 45);
+        
+        // Features are clipped once: far away ones are dropped, cut lines become one feature per part
+        let holed = Polygon::new(
+            LineString::from(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)]),
+            vec![LineString::from(vec![(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0), (4.0, 4.0)])],
+        );
+        let holed_options = TilerOptions { clip_mask: Some(holed), ..Default::default() };
+        let features = vec![
+            Feature {
+                geometry: GeometryType::LineString(LineString::from(vec![(-5.0, 5.0), (15.0, 5.0)])),
+                properties: serde_json::Map::from_iter([("id".to_string(), serde_json::json!(1))]),
+            },
+            Feature {
+                geometry: GeometryType::Point(Point::new(50.0, 50.0)),
+                properties: serde_json::Map::new(),
+            },
+        ];
+        let clipped = clip_features_to_mask(features, &holed_options);
+        assert_eq!(clipped.len(), 2);
+        assert!(clipped.iter().all(|f| f.properties["id"] == 1 && matches!(f.geometry, GeometryType::LineString(_))));
+        
+        let line = GeometryType::LineString(LineString::from(vec![(-5.0, 2.0), (15.0, 2.0)]));
+        let clipped = clip_to_mask(&line, &mask);
+        assert!(matches!(&clipped[..], [GeometryType::LineString(l)] if l.0 == vec![Coord { x: 0.0, y: 2.0 }, Coord { x: 8.0, y: 2.0 }]));
+        
+        let square = Polygon::new(LineString::from(vec![(-1.0, -1.0), (4.0, -1.0), (4.0, 4.0), (-1.0, 4.0), (-1.0, -1.0)]), vec![]);
+        let clipped = clip_to_mask(&GeometryType::Polygon(square), &mask);
+        match &clipped[..] {
+            [GeometryType::Polygon(polygon)] => assert_eq!(coord_ring_area(&polygon.exterior().0), 16.0),
+            other => panic!("unexpected {:?}", other),
+        }
+        
+        let outside = GeometryType::Polygon(Polygon::new(
+            LineString::from(vec![(20.0, 20.0), (21.0, 20.0), (21.0, 21.0), (20.0, 20.0)]),
+            vec![],
+        ));
+        assert!(clip_to_mask(&outside, &mask).is_empty());
+    }
+    
+    #[test]
+    fn test_clip_mask_concave_and_holes() {
+        let area = |geometries: &[GeometryType]| -> f64 {
+            geometries
+                .iter()
+                .map(|g| match g {
+                    GeometryType::Polygon(p) => {
+                        coord_ring_area(&p.exterior().0) + p.interiors().iter().map(|r| coord_ring_area(&r.0)).sum::<f64>()
+                    }
+                    other => panic!("unexpected {:?}", other),
+                })
+                .sum()
+        };
+        
+        // L-shaped mask: the 10 x 10 square without its top-right 5 x 5 quadrant
+        let l_mask = Polygon::new(
+            LineString::from(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 5.0), (5.0, 5.0), (5.0, 10.0), (0.0, 10.0), (0.0, 0.0)]),
+            vec![],
+        );
+        let square = Polygon::new(LineString::from(vec![(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0), (2.0, 2.0)]), vec![]);
+        let clipped = clip_to_mask(&GeometryType::Polygon(square.clone()), &l_mask);
+        assert_eq!(clipped.len(), 1);
+        assert_eq!(area(&clipped), 36.0 - 9.0);
+        
+        // A bar across the notch is cut in two
+        let bar = Polygon::new(LineString::from(vec![(2.0, 6.0), (12.0, 6.0), (12.0, 7.0), (2.0, 7.0), (2.0, 6.0)]), vec![]);
+        let bar_in_l = Polygon::new(LineString::from(vec![(6.0, 2.0), (6.0, 12.0), (7.0, 12.0), (7.0, 2.0), (6.0, 2.0)]), vec![]);
+        assert_eq!(area(&clip_to_mask(&GeometryType::Polygon(bar), &l_mask)), 3.0);
+        assert_eq!(area(&clip_to_mask(&GeometryType::Polygon(bar_in_l), &l_mask)), 3.0);
+        
+        // Polygon sharing edges with the mask is kept whole
+        let shared = Polygon::new(LineString::from(vec![(0.0, 0.0), (5.0, 0.0), (5.0, 10.0), (0.0, 10.0), (0.0, 0.0)]), vec![]);
+        assert_eq!(area(&clip_to_mask(&GeometryType::Polygon(shared), &l_mask)), 50.0);
+        
+        // Mask with a hole: the hole is cut out of the polygon
+        let holed = Polygon::new(
+            LineString::from(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)]),
+            vec![LineString::from(vec![(4.0, 4.0), (6.0, 4.0), (6.0, 6.0), (4.0, 6.0), (4.0, 4.0)])],
+        );
+        let clipped = clip_to_mask(&GeometryType::Polygon(square), &holed);
+        match &clipped[..] {
+            [GeometryType::Polygon(polygon)] => assert_eq!(polygon.interiors().len(), 1),
+            other => panic!("unexpected {:?}", other),
+        }
+        assert_eq!(area(&clipped), 36.0 - 4.0);
+        
+        // Mask hole crossing the polygon edge notches it instead
+        let edge_hole = Polygon::new(
+            LineString::from(vec![(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0), (0.0, 0.0)]),
+            vec![LineString::from(vec![(1.0, 4.0), (3.0, 4.0), (3.0, 6.0), (1.0, 6.0), (1.0, 4.0)])],
+        );
+        let small = Polygon::new(LineString::from(vec![(2.0, 2.0), (8.0, 2.0), (8.0, 8.0), (2.0, 8.0), (2.0, 2.0)]), vec![]);
+        assert_eq!(area(&clip_to_mask(&GeometryType::Polygon(small), &edge_hole)), 36.0 - 2.0);
+    }
+    
+    #[test]
+    fn test_min_line_length() {
+        // About 111m long: ~0.006px at zoom 3, ~11.6px at zoom 14
+        let options = TilerOptions { min_line_length_px: 4.0, ..Default::default() };
+        let features = vec![
+            Feature {
+                geometry: GeometryType::LineString(LineString::from(vec![(10.0, 0.5), (10.001, 0.5)])),
+                properties: serde_json::Map::new(),
+            },
+            Feature {
+                geometry: GeometryType::Point(Point::new(10.0, 0.5)),
+                properties: serde_json::Map::new(),
+            },
+        ];
+        
+        let count = |features: &[Feature], zoom, options: &TilerOptions| {
+            let mut short_lines = 0;
+            let tiles = tile_features_in(features, zoom, None, options, &mut short_lines).unwrap();
+            (tiles.values().map(|features| features.len()).sum::<usize>(), short_lines)
+        };
+        assert!(is_short_line(&features[0].geometry, 3, &options));
+        assert_eq!(count(&features, 3, &options), (1, 1));
+        assert!(!is_short_line(&features[0].geometry, 14, &options));
+        assert_eq!(count(&features, 14, &options), (2, 0));
+        
+        // Lines outside their zoom window are not tiled, so they are not counted as short
+        let mut features = features;
+        features[0].properties.insert("maxz".to_string(), serde_json::json!(0));
+        let windowed = TilerOptions { max_zoom_property: Some("maxz".to_string()), ..options.clone() };
+        assert_eq!(count(&features, 0, &windowed), (1, 1));
+        assert_eq!(count(&features, 3, &windowed), (1, 0));
+    }
+    
+    #[test]
+    fn test_feature_tiles() {
+        // A small polygon around the point where four zoom 10 tiles meet
+        let near_origin = Polygon::new(
+            LineString::from(vec![(-0.1, -0.1), (0.1, -0.1), (0.1, 0.1), (-0.1, 0.1), (-0.1, -0.1)]),
+            vec![],
+        );
+        let feature = Feature {
+            geometry: GeometryType::Polygon(near_origin),
+            properties: serde_json::Map::new(),
+        };
+        
+        let tiles = feature_tiles(&feature, 10, &TilerOptions::default()).unwrap();
+        assert_eq!(
+            tiles,
+            vec![
+                TileCoord::new(10, 511, 511),
+                TileCoord::new(10, 511, 512),
+                TileCoord::new(10, 512, 511),
+                TileCoord::new(10, 512, 512),
+            ]
+        );
+        // The same tiles receive the feature when tiling
+        let mut tiled: Vec<TileCoord> = tile_features(std::slice::from_ref(&feature), 10).unwrap().into_keys().collect();
+        tiled.sort_by_key(|coord| (coord.x, coord.y));
+        assert_eq!(tiled, tiles);
+    }
+    
+    #[test]
+    fn test_feature_tiles_agree_with_tiling() {
+        let feature = |geometry: GeometryType, properties: serde_json::Value| Feature {
+            geometry,
+            properties: properties.as_object().unwrap().clone(),
+        };
+        let square = |x: f64, y: f64, size: f64| {
+            Polygon::new(LineString::from(vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size), (x, y)]), vec![])
+        };
+        let features = vec![
+            // Next to a tile edge at zoom 4, within the point buffer of the neighboring tile
+            feature(GeometryType::Point(Point::new(-0.01, 10.0)), serde_json::json!({})),
+            // Across the antimeridian
+            feature(GeometryType::LineString(LineString::from(vec![(170.0, 5.0), (-170.0, 6.0)])), serde_json::json!({})),
+            // Partly outside the mask, with a label
+            feature(GeometryType::Polygon(square(-50.0, -50.0, 80.0)), serde_json::json!({})),
+            // Members far apart
+            feature(
+                GeometryType::MultiPolygon(MultiPolygon::new(vec![square(-40.0, 10.0, 5.0), square(20.0, -40.0, 5.0)])),
+                serde_json::json!({}),
+            ),
+            // Only tiled up to zoom 2
+            feature(GeometryType::Polygon(square(0.0, 0.0, 30.0)), serde_json::json!({"maxz": 2})),
+            // Too short from zoom 0 to 3
+            feature(GeometryType::LineString(LineString::from(vec![(10.0, 10.0), (10.5, 10.0)])), serde_json::json!({})),
+        ];
+        let options = TilerOptions {
+            split_antimeridian: true,
+            label_layer: Some((0, 3)),
+            max_zoom_property: Some("maxz".to_string()),
+            min_line_length_px: 8.0,
+            clip_mask: Some(square(-60.0, -45.0, 240.0)),
+            point_below_zoom: Some(1),
+            ..Default::default()
+        };
+        
+        for zoom in 0..=5 {
+            for feature in &features {
+                let clipped = clip_features_to_mask(vec![feature.clone()], &options);
+                let mut tiled: Vec<TileCoord> =
+                    tile_features_in(&clipped, zoom, None, &options, &mut 0).unwrap().into_keys().collect();
+                tiled.sort_by_key(|coord| (coord.x, coord.y));
+                assert_eq!(feature_tiles(feature, zoom, &options).unwrap(), tiled, "zoom {} {:?}", zoom, feature.geometry);
+            }
+        }
+    }
+    
+    #[test]
+    fn test_rings_classified_by_containment() {
+        // The hole comes first and the exterior runs clockwise, against the right-hand rule
+        let hole = LineString::from(vec![(-10.0, -10.0), (10.0, -10.0), (10.0, 10.0), (-10.0, 10.0), (-10.0, -10.0)]);
+        let exterior = LineString::from(vec![(-40.0, -40.0), (-40.0, 40.0), (40.0, 40.0), (40.0, -40.0), (-40.0, -40.0)]);
+        let feature = Feature {
+            geometry: GeometryType::Polygon(Polygon::new(hole, vec![exterior])),
+            properties: serde_json::Map::new(),
+        };
+        let options = TilerOptions {
+            ring_classification: RingClassification::Containment,
+            ..Default::default()
+        };
+        
+        let tiles = tile_features_in(std::slice::from_ref(&feature), 0, None, &options, &mut 0).unwrap();
+        let tile = &tiles[&TileCoord::new(0, 0, 0)];
+        let TileGeometry::Polygon(rings) = &tile[0].geometry else {
+            panic!("Polygon expected");
+        };
+        assert_eq!(rings.len(), 2);
+        let width = |ring: &Vec<(i32, i32)>| {
+            ring.iter().map(|p| p.0).max().unwrap() - ring.iter().map(|p| p.0).min().unwrap()
+        };
+        assert!(width(&rings[0]) > width(&rings[1]));
+        
+        // Encoded as one exterior (positive area) followed by one hole (negative area)
+        let mvt = crate::mvt_encoder::encode_tile(tile, "test").unwrap();
+        let decoded = <crate::mvt_encoder::vector_tile::Tile as prost::Message>::decode(mvt.as_slice()).unwrap();
+        let geometry = &decoded.layers[0].features[0].geometry;
+        let move_to_count = geometry.iter().filter(|&&command| command == 9).count();
+        assert_eq!(move_to_count, 2);
+        
+        // Trusting the input order instead makes the small ring the exterior
+        let tiles = tile_features(std::slice::from_ref(&feature), 0).unwrap();
+        let TileGeometry::Polygon(rings) = &tiles[&TileCoord::new(0, 0, 0)][0].geometry else {
+            panic!("Polygon expected");
+        };
+        assert!(width(&rings[0]) < width(&rings[1]));
+    }
+
+    #[test]
+    fn test_split_antimeridian_polygon() {
+        let polygon = Polygon::new(
+            LineString::from(vec![(170.0, 10.0), (-170.0, 10.0), (-170.0, 20.0), (170.0, 20.0), (170.0, 10.0)]),
+            vec![],
+        );
+        let feature = Feature {
+            geometry: GeometryType::Polygon(polygon),
+            properties: serde_json::Map::new(),
+        };
+        let options = TilerOptions {
+            split_antimeridian: true,
+            ..Default::default()
+        };
+        
+        let tiles = tile_features_in(std::slice::from_ref(&feature), 3, None, &options, &mut 0).unwrap();
+        let mut columns: Vec<u32> = tiles.keys().map(|c| c.x).collect();
+        columns.sort_unstable();
+        columns.dedup();
+        assert_eq!(columns, vec![0, 7]);
+        
+        // Without splitting, the polygon smears across every column
+        let tiles = tile_features(std::slice::from_ref(&feature), 3).unwrap();
+        assert_eq!(tiles.len(), 8);
+    }
+
+    #[test]
+    fn test_split_antimeridian_pole_enclosing_ring() {
+        let polygon = Polygon::new(
+            LineString::from(vec![(0.0, 80.0), (90.0, 80.0), (180.0, 80.0), (-90.0, 80.0), (0.0, 80.0)]),
+            vec![],
+        );
+        
+        let parts = split_antimeridian(&GeometryType::Polygon(polygon)).unwrap();
+        let polygons = match &parts[..] {
+            [GeometryType::MultiPolygon(multi_polygon)] => &multi_polygon.0,
+            _ => panic!("Expected a single MultiPolygon"),
+        };
+        assert_eq!(polygons.len(), 2);
+        for polygon in polygons {
+            assert!(polygon.exterior().0.iter().all(|c| (-180.0..=180.0).contains(&c.x)));
+            assert!(polygon.exterior().0.iter().any(|c| c.y == 90.0));
+        }
+    }
+
+    #[test]
+    fn test_split_antimeridian_line_touching_180() {
+        // Touching 180° and turning back is not a crossing
+        let touching = LineString::from(vec![(170.0, 0.0), (180.0, 0.0), (170.0, 5.0)]);
+        assert!(split_antimeridian(&GeometryType::LineString(touching)).is_none());
+        
+        // Passing through 180° exactly splits there
+        let crossing = LineString::from(vec![(170.0, 0.0), (180.0, 0.0), (-170.0, 0.0)]);
+        let parts = split_antimeridian(&GeometryType::LineString(crossing)).unwrap();
+        assert_eq!(parts.len(), 2);
+        match (&parts[0], &parts[1]) {
+            (GeometryType::LineString(west), GeometryType::LineString(east)) => {
+                assert_eq!(west.0.last().unwrap().x, 180.0);
+                assert_eq!(east.0[0].x, -180.0);
+                assert_eq!(east.0.last().unwrap().x, -170.0);
+            }
+            _ => panic!("Expected LineString parts"),
+        }
+    }
+
+    #[test]
+    fn test_quantization_collapses_duplicate_grid_points() {
+        // The first two vertices are ~1mm apart and snap to the same grid cell at z5
+        let line = LineString::from(vec![
+            (139.7671, 35.6812),
+            (139.76710001, 35.68120001),
+            (139.9, 35.8),
+        ]);
+        let properties = serde_json::Map::new();
+        let mut tiles = HashMap::new();
+        
+        tile_geometry(&GeometryType::LineString(line), &properties, 5, None, &TilerOptions::default(), &mut tiles).unwrap();
+        
+        let features = tiles.values().next().unwrap();
+        match &features[0].geometry {
+            TileGeometry::LineString(coords) => assert_eq!(coords.len(), 2),
+            _ => panic!("Expected LineString geometry"),
+        }
+    }
+    
+    #[test]
+    fn test_feature_zoom_range() {
+        let properties = serde_json::json!({"name": "Tokyo", "minzoom": 5, "maxzoom": "8"});
+        let feature = Feature {
+            geometry: GeometryType::Point(Point::new(139.7671, 35.6812)),
+            properties: properties.as_object().unwrap().clone(),
+        };
+        let options = TilerOptions {
+            min_zoom_property: Some("minzoom".to_string()),
+            max_zoom_property: Some("maxzoom".to_string()),
+            ..Default::default()
+        };
+        let tile_count = |zoom: u8, options: &TilerOptions| {
+            tile_features_in(std::slice::from_ref(&feature), zoom, None, options, &mut 0).unwrap().len()
+        };
+        
+        assert_eq!(tile_count(4, &options), 0);
+        assert_eq!(tile_count(5, &options), 1);
+        assert_eq!(tile_count(6, &options), 1);
+        assert_eq!(tile_count(8, &options), 1);
+        assert_eq!(tile_count(10, &options), 0);
+        // Without the options the properties are ignored
+        assert_eq!(tile_count(10, &TilerOptions::default()), 1);
+    }
+    
+    #[test]
+    fn test_label_layer_features() {
+        let polygon = Polygon::new(
+            LineString::from(vec![(139.70, 35.60), (139.72, 35.60), (139.72, 35.62), (139.70, 35.62), (139.70, 35.60)]),
+            vec![],
+        );
+        let feature = Feature { geometry: GeometryType::Polygon(polygon), properties: serde_json::Map::new() };
+        let options = TilerOptions { label_layer: Some((0, 12)), ..Default::default() };
+        
+        let tiles = tile_features_in(std::slice::from_ref(&feature), 12, None, &options, &mut 0).unwrap();
+        let features: Vec<&TileFeature> = tiles.values().flatten().collect();
+        let labels: Vec<&&TileFeature> = features.iter().filter(|f| f.label).collect();
+        assert_eq!(labels.len(), 1);
+        assert!(matches!(labels[0].geometry, TileGeometry::Point(..)));
+        assert!(features.iter().any(|f| !f.label && matches!(f.geometry, TileGeometry::Polygon(_))));
+        
+        // Outside the zoom range only the polygon is tiled
+        let tiles = tile_features_in(std::slice::from_ref(&feature), 13, None, &options, &mut 0).unwrap();
+        assert!(tiles.values().flatten().all(|f| !f.label));
+    }
+    
+    #[test]
+    fn test_clamp_margin() {
+        // From the middle of tile 10/909/403 far east into the neighbor; the vertex near
+        // the end survives simplification and lies well outside a 64 unit margin
+        let line = LineString::from(vec![(139.70, 35.66), (140.10, 35.663), (140.20, 35.66)]);
+        let properties = serde_json::Map::new();
+        let options = TilerOptions {
+            simplify_tolerance: 1.0,
+            clamp_margin: Some(64),
+            ..Default::default()
+        };
+        let only: HashSet<TileCoord> = [TileCoord::new(10, 909, 403)].into();
+        let feature = Feature { geometry: GeometryType::LineString(line), properties };
+        
+        let tiles = tile_features_in(std::slice::from_ref(&feature), 10, Some(&only), &options, &mut 0).unwrap();
+        let TileGeometry::LineString(coords) = &tiles[&TileCoord::new(10, 909, 403)][0].geometry else {
+            panic!("Expected LineString geometry");
+        };
+        assert!(coords.iter().all(|&(x, y)| (-64..=4160).contains(&x) && (-64..=4160).contains(&y)));
+        let clamped = coords.clone();
+        
+        let unclamped = TilerOptions { simplify_tolerance: 1.0, ..Default::default() };
+        let tiles = tile_features_in(std::slice::from_ref(&feature), 10, Some(&only), &unclamped, &mut 0).unwrap();
+        let TileGeometry::LineString(coords) = &tiles[&TileCoord::new(10, 909, 403)][0].geometry else {
+            panic!("Expected LineString geometry");
+        };
+        assert!(coords.iter().any(|&(x, _)| x > 4160));
+        
+        // The line is cut where its first segment crosses x = 4160, keeping the slope
+        let ((x0, y0), (x1, y1)) = (coords[0], coords[1]);
+        let y = y0 as f64 + (4160 - x0) as f64 / (x1 - x0) as f64 * (y1 - y0) as f64;
+        assert_eq!(clamped, vec![coords[0], (4160, y.round() as i32)]);
+    }
+    
+    #[test]
+    fn test_clamp_margin_clips_polygons_and_splits_lines() {
+        let options = TilerOptions { clamp_margin: Some(64), ..Default::default() };
+        let feature = |geometry| TileFeature { geometry, properties: serde_json::Map::new(), label: false };
+        let mut features = vec![
+            feature(TileGeometry::Polygon(vec![vec![(0, 0), (8000, 4000), (0, 4000), (0, 0)]])),
+            // Leaves the margin to the east and comes back
+            feature(TileGeometry::LineString(vec![(4000, 100), (5000, 100), (5000, 200), (4000, 200)])),
+            feature(TileGeometry::Point(-100, 0)),
+        ];
+        clamp_tile(&mut features, &options);
+        
+        assert_eq!(features.len(), 3);
+        assert!(matches!(&features[0].geometry, TileGeometry::Polygon(rings)
+            if rings[..] == [vec![(0, 0), (4160, 2080), (4160, 4000), (0, 4000), (0, 0)]]));
+        assert!(matches!(&features[1].geometry, TileGeometry::LineString(l) if l[..] == [(4000, 100), (4160, 100)]));
+        assert!(matches!(&features[2].geometry, TileGeometry::LineString(l) if l[..] == [(4160, 200), (4000, 200)]));
+    }
+    
+    #[test]
+    fn test_remove_collinear_points() {
+        // (0,0) -> (10,0) -> (20,0) is a straight run; (20,10) is a corner
+        let line = vec![(0, 0), (10, 0), (20, 0), (20, 10)];
+        assert_eq!(remove_collinear(line), vec![(0, 0), (20, 0), (20, 10)]);
+        
+        // Three collinear points collapse to two
+        assert_eq!(remove_collinear(vec![(0, 0), (5, 5), (10, 10)]), vec![(0, 0), (10, 10)]);
+        
+        // A point where the line doubles back is not removed
+        assert_eq!(remove_collinear(vec![(0, 0), (10, 0), (5, 0)]), vec![(0, 0), (10, 0), (5, 0)]);
+        
+        // Ring starting in the middle of an edge
+        let ring = vec![(5, 0), (10, 0), (10, 10), (0, 10), (0, 0), (5, 0)];
+        assert_eq!(
+            remove_collinear_ring(ring),
+            vec![(10, 0), (10, 10), (0, 10), (0, 0), (10, 0)]
+        );
+    }
 }
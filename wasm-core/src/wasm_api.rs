@@ -10,11 +10,50 @@ pub fn init_panic_hook() {
     console_error_panic_hook::set_once();
 }
 
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console, js_name = debug)]
+    fn console_debug(message: &str);
+}
+
+/// Current size of the Wasm heap in bytes (linear memory, which never shrinks)
+#[wasm_bindgen]
+pub fn current_heap_estimate() -> usize {
+    crate::memory::current_heap_estimate()
+}
+
+/// Log heap size checkpoints (after parsing, tiling and encoding each zoom) to `console.debug`
+#[wasm_bindgen]
+pub fn set_memory_logging(enabled: bool) {
+    let hook: Option<crate::memory::CheckpointHook> = if enabled {
+        Some(Box::new(|stage, heap| console_debug(&format!("[memory] {}: {} bytes", stage, heap))))
+    } else {
+        None
+    };
+    crate::memory::set_checkpoint_hook(hook);
+}
+
 /// Tile generation result (with metadata)
 #[wasm_bindgen]
 pub struct TileResult {
     tiles: Vec<TileData>,
     metadata: MetadataData,
+    /// Tile indices per zoom level
+    by_zoom: std::collections::BTreeMap<u8, Vec<usize>>,
+}
+
+impl TileResult {
+    fn new(tiles: Vec<crate::TileFile>, metadata: MetadataData) -> Self {
+        let by_zoom = crate::index_tiles_by_zoom(&tiles);
+        let tiles = tiles
+            .into_iter()
+            .map(|tile| TileData {
+                path: tile.path,
+                data: tile.data,
+            })
+            .collect();
+        Self { tiles, metadata, by_zoom }
+    }
 }
 
 #[wasm_bindgen]
@@ -34,6 +73,21 @@ impl TileResult {
         self.tiles.get(index).map(|t| t.data.clone())
     }
     
+    /// Get the number of tiles at zoom level `z`
+    pub fn count_for_zoom(&self, z: u8) -> usize {
+        self.by_zoom.get(&z).map_or(0, Vec::len)
+    }
+    
+    /// Get the paths of the tiles at zoom level `z`
+    pub fn paths_for_zoom(&self, z: u8) -> Vec<String> {
+        self.indices_for_zoom(z).into_iter().map(|index| self.tiles[index].path.clone()).collect()
+    }
+    
+    /// Get the indices (for `get_path` / `get_data`) of the tiles at zoom level `z`
+    pub fn indices_for_zoom(&self, z: u8) -> Vec<usize> {
+        self.by_zoom.get(&z).cloned().unwrap_or_default()
+    }
+    
     /// Get metadata
     pub fn get_metadata(&self) -> JsValue {
         serde_wasm_bindgen::to_value(&self.metadata).unwrap_or(JsValue::NULL)
@@ -53,6 +107,20 @@ struct MetadataData {
     layer_name: String,
     bounds: (f64, f64, f64, f64),
     center: (f64, f64),
+    diagnostics: crate::diagnostics::Diagnostics,
+}
+
+impl From<crate::TileMetadata> for MetadataData {
+    fn from(metadata: crate::TileMetadata) -> Self {
+        Self {
+            min_zoom: metadata.min_zoom,
+            max_zoom: metadata.max_zoom,
+            layer_name: metadata.layer_name,
+            bounds: metadata.bounds,
+            center: metadata.center,
+            diagnostics: metadata.diagnostics,
+        }
+    }
 }
 
 /// Generate vector tiles from GeoJSON (for Wasm, with metadata)
@@ -74,29 +142,116 @@ pub fn generate_pbf_tiles(
 ) -> Result<TileResult, JsValue> {
     // Generate tiles (with metadata)
     let (tiles, metadata) = generate_tiles_with_metadata(geojson_bytes, min_zoom, max_zoom, layer_name)
-        .map_err(|e| JsValue::from_str(&e))?;
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
     
     // Convert to Wasm data structure
-    let tile_data: Vec<TileData> = tiles
-        .into_iter()
-        .map(|tile| TileData {
-            path: tile.path,
-            data: tile.data,
-        })
-        .collect();
+    Ok(TileResult::new(tiles, MetadataData::from(metadata)))
+}
+
+/// Generation settings passed in from JS as one JSON object
+#[derive(serde::Deserialize)]
+#[serde(deny_unknown_fields)]
+struct GenerationConfig {
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: String,
+    #[serde(default)]
+    options: crate::TilerOptions,
+}
+
+/// Generate vector tiles from GeoJSON with settings given as JSON (for Wasm)
+/// 
+/// # Arguments
+/// * `geojson_bytes` - GeoJSON byte array
+/// * `config` - `{ "min_zoom", "max_zoom", "layer_name", "options"? }` with `options` in
+///   the format of `TilerOptions::from_json`; unknown keys are rejected
+/// 
+/// # Returns
+/// * `Result<TileResult, JsValue>` - TileResult on success, error message on failure
+#[wasm_bindgen]
+pub fn generate_pbf_tiles_with_config(geojson_bytes: &[u8], config: &str) -> Result<TileResult, JsValue> {
+    let config: GenerationConfig =
+        serde_json::from_str(config).map_err(|e| JsValue::from_str(&format!("Invalid config: {}", e)))?;
+    let (tiles, metadata) = crate::generate_tiles_with_options(
+        geojson_bytes,
+        config.min_zoom,
+        config.max_zoom,
+        &config.layer_name,
+        &config.options,
+    )
+    .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(TileResult::new(tiles, MetadataData::from(metadata)))
+}
+
+/// Read optional `TilerOptions` JSON (defaults when absent)
+fn options_from_json(json: Option<String>) -> Result<crate::TilerOptions, JsValue> {
+    match json {
+        Some(json) => crate::TilerOptions::from_json(&json).map_err(|e| JsValue::from_str(&e.to_string())),
+        None => Ok(crate::TilerOptions::default()),
+    }
+}
+
+/// Incremental tile generation from chunks of a GeoJSON FeatureCollection (for Wasm)
+/// 
+/// Feed the chunks of a `ReadableStream` (e.g. `file.stream()`) to `push_bytes` as they
+/// arrive, then call `finish`; the file never has to be loaded into one `Uint8Array`.
+/// The optional `options` are JSON in the format of `TilerOptions::from_json`.
+#[wasm_bindgen]
+pub struct TileGenerator {
+    inner: crate::TileGenerator,
+}
+
+#[wasm_bindgen]
+impl TileGenerator {
+    #[wasm_bindgen(constructor)]
+    pub fn new(min_zoom: u8, max_zoom: u8, layer_name: &str, options: Option<String>) -> Result<TileGenerator, JsValue> {
+        let options = options_from_json(options)?;
+        let inner = crate::TileGenerator::new(min_zoom, max_zoom, layer_name, &options)
+            .map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(Self { inner })
+    }
     
-    let metadata_data = MetadataData {
-        min_zoom: metadata.min_zoom,
-        max_zoom: metadata.max_zoom,
-        layer_name: metadata.layer_name,
-        bounds: metadata.bounds,
-        center: metadata.center,
-    };
+    /// Feed the next chunk of input
+    pub fn push_bytes(&mut self, chunk: &[u8]) -> Result<(), JsValue> {
+        self.inner.push_bytes(chunk).map_err(|e| JsValue::from_str(&e.to_string()))
+    }
     
-    Ok(TileResult { 
-        tiles: tile_data,
-        metadata: metadata_data,
-    })
+    /// Encode the tiles once all chunks were pushed (the generator is consumed)
+    pub fn finish(self) -> Result<TileResult, JsValue> {
+        let (tiles, metadata) = self.inner.finish().map_err(|e| JsValue::from_str(&e.to_string()))?;
+        Ok(TileResult::new(tiles, MetadataData::from(metadata)))
+    }
+}
+
+/// Check a zoom range before generating tiles (for Wasm)
+/// 
+/// # Arguments
+/// * `geojson_bytes` - GeoJSON byte array
+/// * `min_zoom` - Minimum zoom level
+/// * `max_zoom` - Maximum zoom level
+/// 
+/// # Returns
+/// * `Result<JsValue, JsValue>` - `{ warnings, estimated_tiles, suggested_max_zoom }` on success
+#[wasm_bindgen]
+pub fn check_zoom_range(geojson_bytes: &[u8], min_zoom: u8, max_zoom: u8) -> Result<JsValue, JsValue> {
+    let diagnostics = crate::preflight_geojson(geojson_bytes, min_zoom, max_zoom, &crate::TilerOptions::default())
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    serde_wasm_bindgen::to_value(&diagnostics).map_err(|e| JsValue::from_str(&e.to_string()))
+}
+
+/// Suggest a max zoom where the densest tile has a manageable number of features
+/// 
+/// # Arguments
+/// * `geojson_bytes` - GeoJSON byte array
+/// * `target_features_per_tile` - Largest acceptable feature count in one tile
+/// 
+/// # Returns
+/// * `Result<u8, JsValue>` - Suggested max zoom on success, error message on failure
+#[wasm_bindgen]
+pub fn suggest_max_zoom(geojson_bytes: &[u8], target_features_per_tile: usize) -> Result<u8, JsValue> {
+    let parsed = crate::parse_input(geojson_bytes, &crate::TilerOptions::default())
+        .map_err(|e| JsValue::from_str(&e.to_string()))?;
+    Ok(crate::diagnostics::suggest_max_zoom(&parsed.features, target_features_per_tile))
 }
 
 /// Generate PMTiles archive from GeoJSON (for Wasm)
@@ -144,6 +299,197 @@ pub fn generate_pmtiles_archive(
     Ok(pmtiles_data)
 }
 
+/// Generate PMTiles from a shapefile (for Wasm)
+/// 
+/// # Arguments
+/// * `shp` - `.shp` byte array (geometry)
+/// * `dbf` - `.dbf` byte array (attributes)
+/// * `prj` - Optional `.prj` text (CRS as WKT; WGS84 when absent)
+/// * `cpg` - Optional `.cpg` text (encoding of the `.dbf`; its code page when absent)
+/// * `min_zoom` - Minimum zoom level
+/// * `max_zoom` - Maximum zoom level
+/// * `layer_name` - Layer name
+/// * `options` - Optional JSON in the format of `TilerOptions::from_json`
+/// 
+/// # Returns
+/// * `Result<Vec<u8>, JsValue>` - PMTiles file data on success, error message on failure
+#[wasm_bindgen]
+#[allow(clippy::too_many_arguments)]
+pub fn generate_pmtiles_from_shapefile(
+    shp: &[u8],
+    dbf: &[u8],
+    prj: Option<String>,
+    cpg: Option<String>,
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: Option<String>,
+) -> Result<Vec<u8>, JsValue> {
+    let options = options_from_json(options)?;
+    let sources = crate::shapefile_parser::ShapefileSources {
+        shp,
+        dbf,
+        prj: prj.as_deref(),
+        cpg: cpg.as_deref(),
+    };
+    crate::generate_pmtiles_from_shapefile(&sources, min_zoom, max_zoom, layer_name, &options)
+        .map_err(|e| JsValue::from_str(&format!("Shapefile conversion error: {}", e)))
+}
+
+/// Generate a ZIP archive of raw `z/x/y.pbf` tiles and `metadata.json` (for Wasm)
+/// 
+/// # Arguments
+/// * `geojson_bytes` - GeoJSON byte array
+/// * `min_zoom` - Minimum zoom level
+/// * `max_zoom` - Maximum zoom level
+/// * `layer_name` - Layer name
+/// * `options` - Optional JSON in the format of `TilerOptions::from_json`
+/// 
+/// # Returns
+/// * `Result<Vec<u8>, JsValue>` - ZIP file data on success, error message on failure
+#[wasm_bindgen]
+pub fn generate_tiles_zip_archive(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    options: Option<String>,
+) -> Result<Vec<u8>, JsValue> {
+    let options = options_from_json(options)?;
+    crate::generate_tiles_zip(geojson_bytes, min_zoom, max_zoom, layer_name, &options)
+        .map_err(|e| JsValue::from_str(&format!("ZIP generation error: {}", e)))
+}
+
+#[wasm_bindgen]
+extern "C" {
+    /// JS object receiving archive chunks, e.g. a wrapper around a `WritableStream` writer
+    /// 
+    /// `write` gets a view into Wasm memory that is only valid during the call, so the
+    /// chunk must be copied (e.g. `chunk.slice()`) before it is queued.
+    pub type ChunkSink;
+    
+    #[wasm_bindgen(method)]
+    fn write(this: &ChunkSink, chunk: &[u8]);
+}
+
+/// `std::io::Write` adapter passing every write to a JS `ChunkSink`
+struct JsSink<'a>(&'a ChunkSink);
+
+impl std::io::Write for JsSink<'_> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.write(buf);
+        Ok(buf.len())
+    }
+    
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// Generate a PMTiles archive from GeoJSON and hand it to `sink` in chunks (for Wasm)
+/// 
+/// Avoids returning the whole archive as one buffer; `sink.write(chunk)` is called for
+/// the header, directory, metadata and each tile in file order.
+/// 
+/// # Arguments
+/// * `geojson_bytes` - GeoJSON byte array
+/// * `min_zoom` - Minimum zoom level
+/// * `max_zoom` - Maximum zoom level
+/// * `layer_name` - Layer name
+/// * `sink` - Object with a `write(chunk: Uint8Array)` method
+#[wasm_bindgen]
+pub fn write_pmtiles_archive(
+    geojson_bytes: &[u8],
+    min_zoom: u8,
+    max_zoom: u8,
+    layer_name: &str,
+    sink: &ChunkSink,
+) -> Result<(), JsValue> {
+    let (tile_files, metadata) = generate_tiles_with_metadata(geojson_bytes, min_zoom, max_zoom, layer_name)
+        .map_err(|e| JsValue::from_str(&format!("Tile generation error: {}", e)))?;
+    
+    // Tiles are converted lazily as the encoder takes them
+    let tiles = tile_files.into_iter().map(|tile_file| {
+        let path_parts: Vec<&str> = tile_file.path.split('/').collect();
+        let z = path_parts[0].parse::<u8>().unwrap_or(0);
+        let x = path_parts[1].parse::<u32>().unwrap_or(0);
+        let y = path_parts[2].trim_end_matches(".pbf").parse::<u32>().unwrap_or(0);
+        (crate::TileCoord::new(z, x, y), tile_file.data)
+    });
+    
+    crate::pmtiles_encoder::write_pmtiles(tiles, &metadata, &crate::TilerOptions::default(), &mut JsSink(sink))
+        .map_err(|e| JsValue::from_str(&format!("PMTiles encoding error: {}", e)))
+}
+
+/// Tile passed in from JS for `pack_pmtiles`
+#[derive(serde::Deserialize)]
+struct ExternalTile {
+    z: u8,
+    x: u32,
+    y: u32,
+    data: Vec<u8>,
+}
+
+/// Metadata passed in from JS for `pack_pmtiles`; missing values are derived from the tiles
+#[derive(serde::Deserialize)]
+struct ExternalMetadata {
+    layer_name: String,
+    min_zoom: Option<u8>,
+    max_zoom: Option<u8>,
+    bounds: Option<(f64, f64, f64, f64)>,
+    center: Option<(f64, f64)>,
+    center_zoom: Option<u8>,
+    geometry_type: Option<String>,
+    #[serde(default)]
+    fields: std::collections::HashMap<String, String>,
+    tileset_type: Option<String>,
+}
+
+/// Pack already generated MVT tiles (e.g. from tippecanoe) into a PMTiles archive (for Wasm)
+/// 
+/// # Arguments
+/// * `tiles` - Array of `{ z, x, y, data }` with `data` a `Uint8Array` (raw or gzipped MVT)
+/// * `metadata` - `{ layer_name, min_zoom?, max_zoom?, bounds?, center?, center_zoom?, geometry_type?, fields?, tileset_type? }`
+/// * `validate_mvt` - Reject tiles that do not decode as MVT
+/// 
+/// # Returns
+/// * `Result<Vec<u8>, JsValue>` - PMTiles file data on success, error message on failure
+#[wasm_bindgen]
+pub fn pack_pmtiles(tiles: JsValue, metadata: JsValue, validate_mvt: bool) -> Result<Vec<u8>, JsValue> {
+    let tiles: Vec<ExternalTile> =
+        serde_wasm_bindgen::from_value(tiles).map_err(|e| JsValue::from_str(&format!("Invalid tiles: {}", e)))?;
+    let metadata: ExternalMetadata =
+        serde_wasm_bindgen::from_value(metadata).map_err(|e| JsValue::from_str(&format!("Invalid metadata: {}", e)))?;
+    
+    let min_zoom = metadata.min_zoom.or_else(|| tiles.iter().map(|tile| tile.z).min()).unwrap_or(0);
+    let max_zoom = metadata.max_zoom.or_else(|| tiles.iter().map(|tile| tile.z).max()).unwrap_or(0);
+    let max_lat = crate::projection::MAX_LATITUDE;
+    let bounds = metadata.bounds.unwrap_or((-180.0, -max_lat, 180.0, max_lat));
+    let tileset_type = match metadata.tileset_type.as_deref() {
+        Some(value) => crate::pmtiles_encoder::TilesetType::parse(value).map_err(|e| JsValue::from_str(&e.to_string()))?,
+        None => Default::default(),
+    };
+    let tile_metadata = crate::TileMetadata {
+        min_zoom,
+        max_zoom,
+        layer_name: metadata.layer_name,
+        bounds,
+        center: metadata.center.unwrap_or_else(|| crate::geojson_parser::calculate_center(bounds)),
+        center_zoom: metadata.center_zoom.unwrap_or(min_zoom),
+        geometry_type: metadata.geometry_type.unwrap_or_else(|| "Point".to_string()),
+        fields: metadata.fields,
+        tileset_type,
+        ..Default::default()
+    };
+    
+    let tiles = tiles
+        .into_iter()
+        .map(|tile| (crate::TileCoord::new(tile.z, tile.x, tile.y), tile.data))
+        .collect();
+    crate::pmtiles_encoder::pack_tiles(tiles, &tile_metadata, &crate::TilerOptions::default(), validate_mvt)
+        .map_err(|e| JsValue::from_str(&format!("PMTiles encoding error: {}", e)))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -152,14 +498,26 @@ mod tests {
     fn test_wasm_api_structure() {
         // Basic structure test
         let tile_data = vec![
-            TileData {
+            crate::TileFile {
                 path: "0/0/0.pbf".to_string(),
                 data: vec![1, 2, 3],
             },
         ];
         
-        let result = TileResult { tiles: tile_data };
+        let metadata = MetadataData {
+            min_zoom: 0,
+            max_zoom: 0,
+            layer_name: "test".to_string(),
+            bounds: (0.0, 0.0, 0.0, 0.0),
+            center: (0.0, 0.0),
+            diagnostics: Default::default(),
+        };
+        
+        let result = TileResult::new(tile_data, metadata);
         assert_eq!(result.count(), 1);
         assert_eq!(result.get_path(0), Some("0/0/0.pbf".to_string()));
+        assert_eq!(result.count_for_zoom(0), 1);
+        assert_eq!(result.paths_for_zoom(0), vec!["0/0/0.pbf".to_string()]);
+        assert_eq!(result.count_for_zoom(1), 0);
     }
 }